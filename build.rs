@@ -0,0 +1,65 @@
+use std::{
+    env, fs,
+    path::{Path, PathBuf},
+};
+
+const VENDORED_SCRIPTS: [&str; 3] = ["htmx.min.js", "class-tools.js", "sse.js"];
+
+// Embeds every file in templates and resources into the binary, so cyclops runs
+// from any directory. src/assets.rs includes the generated lists.
+fn main() {
+    let manifest_dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let out_dir = PathBuf::from(env::var("OUT_DIR").unwrap());
+
+    // The pages load these from /resources, they are not fetched at runtime
+    for script in VENDORED_SCRIPTS {
+        if !manifest_dir.join("resources").join(script).is_file() {
+            panic!("resources/{} is missing, the UI pages can't work without it. See the README for the pinned version.", script);
+        }
+    }
+
+    let mut generated = String::from("// Generated by build.rs\n");
+    generated.push_str(&embed_list(
+        "EMBEDDED_TEMPLATES",
+        "str",
+        "include_str",
+        &manifest_dir.join("templates"),
+    ));
+    generated.push_str(&embed_list(
+        "EMBEDDED_RESOURCES",
+        "[u8]",
+        "include_bytes",
+        &manifest_dir.join("resources"),
+    ));
+    fs::write(out_dir.join("embedded_assets.rs"), generated).expect("Unable to write embedded assets");
+
+    println!("cargo:rerun-if-changed=build.rs");
+    println!("cargo:rerun-if-changed=templates");
+    println!("cargo:rerun-if-changed=resources");
+    // Read by embed_migrations! in src/db/mod.rs
+    println!("cargo:rerun-if-changed=migrations");
+}
+
+// A static slice of (file name, contents) for the files directly in dir, sorted by name
+fn embed_list(name: &str, data_type: &str, include_macro: &str, dir: &Path) -> String {
+    let mut files: Vec<PathBuf> = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("Unable to read {:?}: {:?}", dir, e))
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file())
+        .collect();
+    files.sort();
+
+    let mut list = format!("pub static {}: &[(&str, &{})] = &[\n", name, data_type);
+    for path in files {
+        let file_name = path.file_name().unwrap().to_string_lossy();
+        list.push_str(&format!(
+            "    ({:?}, {}!({:?})),\n",
+            file_name,
+            include_macro,
+            path.to_string_lossy()
+        ));
+    }
+    list.push_str("];\n");
+    list
+}
@@ -0,0 +1,57 @@
+-- View: proc_rates
+-- A proc fire is credited to the last non-proc activation before it
+DROP VIEW IF EXISTS proc_rates;
+CREATE VIEW IF NOT EXISTS proc_rates AS
+SELECT pf.summary_key,
+       pf.proc_name,
+       pf.power_name,
+       count(pf.proc_name) AS fires,
+       (
+           SELECT count(pa.power_name)
+             FROM player_activation pa
+            WHERE pa.summary_key = pf.summary_key AND 
+                  pa.power_name = pf.power_name AND 
+                  pa.proc_fire = 0
+       )
+       AS activations,
+       (
+           SELECT CAST (ROUND(AVG(recharge_time) ) AS INTEGER) 
+             FROM (
+                      SELECT (JULIANDAY( (
+                                             SELECT ppr.log_date
+                                               FROM player_power_recharged ppr
+                                              WHERE pa.summary_key = ppr.summary_key AND 
+                                                    pa.line_number < ppr.line_number AND 
+                                                    pa.power_name = ppr.power_name
+                                              ORDER BY ppr.line_number
+                                              LIMIT 1
+                                         )
+                             ) - JULIANDAY(pa.log_date) ) * 86400 AS recharge_time
+                        FROM player_activation pa
+                       WHERE pa.summary_key = pf.summary_key AND 
+                             pa.power_name = pf.power_name AND 
+                             pa.proc_fire = 0
+                  )
+       )
+       AS average_recharge
+  FROM (
+           SELECT p.summary_key,
+                  p.power_name AS proc_name,
+                  (
+                      SELECT pa.power_name
+                        FROM player_activation pa
+                       WHERE pa.summary_key = p.summary_key AND 
+                             pa.line_number < p.line_number AND 
+                             pa.proc_fire = 0
+                       ORDER BY pa.line_number DESC
+                       LIMIT 1
+                  )
+                  AS power_name
+             FROM player_activation p
+            WHERE p.proc_fire = 1
+       )
+       pf
+ WHERE pf.power_name IS NOT NULL
+ GROUP BY pf.summary_key,
+          pf.proc_name,
+          pf.power_name;
@@ -0,0 +1,4 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS player_death;
+DROP TABLE IF EXISTS heal_endurance_action;
+DROP TABLE IF EXISTS control_action;
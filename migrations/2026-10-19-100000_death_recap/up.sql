@@ -0,0 +1,11 @@
+-- Table: player_death
+DROP TABLE IF EXISTS player_death;
+CREATE TABLE IF NOT EXISTS player_death (summary_key INTEGER NOT NULL, line_number INTEGER NOT NULL, log_date TEXT NOT NULL, event_type TEXT CHECK (event_type IN ('Defeated', 'Resurrected', 'Awakened')) NOT NULL, source_name TEXT, PRIMARY KEY (summary_key, line_number, log_date), FOREIGN KEY (summary_key) REFERENCES summary (summary_key) ON DELETE CASCADE) STRICT;
+
+-- Table: heal_endurance_action
+DROP TABLE IF EXISTS heal_endurance_action;
+CREATE TABLE IF NOT EXISTS heal_endurance_action (summary_key INTEGER NOT NULL, line_number INTEGER NOT NULL, log_date TEXT NOT NULL, action_type TEXT CHECK (action_type IN ('Heal', 'Endurance')) NOT NULL, source_name TEXT NOT NULL, target_name TEXT NOT NULL, power_name TEXT NOT NULL, amount INTEGER NOT NULL, PRIMARY KEY (summary_key, line_number, log_date), FOREIGN KEY (summary_key) REFERENCES summary (summary_key) ON DELETE CASCADE) STRICT;
+
+-- Table: control_action
+DROP TABLE IF EXISTS control_action;
+CREATE TABLE IF NOT EXISTS control_action (summary_key INTEGER NOT NULL, line_number INTEGER NOT NULL, log_date TEXT NOT NULL, source_type TEXT CHECK (source_type IN ('Player', 'PlayerPet', 'Mob', 'MobPet')) NOT NULL, source_name TEXT NOT NULL, target_name TEXT NOT NULL, power_name TEXT NOT NULL, control_type TEXT NOT NULL, PRIMARY KEY (summary_key, line_number, log_date), FOREIGN KEY (summary_key) REFERENCES summary (summary_key) ON DELETE CASCADE) STRICT;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP VIEW IF EXISTS benchmark_report;
+DROP TABLE IF EXISTS benchmark_run;
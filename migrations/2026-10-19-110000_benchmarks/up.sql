@@ -0,0 +1,58 @@
+-- Table: benchmark_run
+-- Sessions started with STARTPARSE <test name>
+DROP TABLE IF EXISTS benchmark_run;
+CREATE TABLE IF NOT EXISTS benchmark_run (summary_key INTEGER PRIMARY KEY NOT NULL, test_name TEXT NOT NULL, FOREIGN KEY (summary_key) REFERENCES summary (summary_key) ON DELETE CASCADE) STRICT;
+
+-- View: benchmark_report
+DROP VIEW IF EXISTS benchmark_report;
+CREATE VIEW IF NOT EXISTS benchmark_report AS
+SELECT summary_key,
+       test_name,
+       player_name,
+       log_date,
+       total_damage,
+       combat_seconds,
+       time_to_kill,
+       CAST (total_damage / MAX(combat_seconds, 1) AS INTEGER) AS sustained_dps,
+       activations,
+       ROUND(activations * 60.0 / MAX(combat_seconds, 1), 1) AS activations_per_minute
+  FROM (
+           SELECT b.summary_key,
+                  b.test_name,
+                  s.player_name,
+                  s.log_date,
+                  COALESCE( (
+                                SELECT sum(da.damage)
+                                  FROM damage_action da
+                                 WHERE da.summary_key = b.summary_key AND 
+                                       da.source_type IN ('Player', 'PlayerPet')
+                            ), 0) AS total_damage,
+                  COALESCE( (
+                                SELECT CAST (ROUND( (max(julianday(da.log_date) ) - min(julianday(da.log_date) ) ) * 86400) AS INTEGER) 
+                                  FROM damage_action da
+                                 WHERE da.summary_key = b.summary_key AND 
+                                       da.source_type IN ('Player', 'PlayerPet')
+                            ), 0) AS combat_seconds,
+                  (
+                      SELECT CAST (ROUND( (max(julianday(dt.log_date) ) - (
+                                                                               SELECT min(julianday(da.log_date) ) 
+                                                                                 FROM damage_action da
+                                                                                WHERE da.summary_key = b.summary_key AND 
+                                                                                      da.source_type IN ('Player', 'PlayerPet')
+                                                                           )
+                                          ) * 86400) AS INTEGER) 
+                        FROM defeated_targets dt
+                       WHERE dt.summary_key = b.summary_key
+                  )
+                  AS time_to_kill,
+                  (
+                      SELECT count(pa.power_name) 
+                        FROM player_activation pa
+                       WHERE pa.summary_key = b.summary_key AND 
+                             pa.proc_fire = 0
+                  )
+                  AS activations
+             FROM benchmark_run b
+                  INNER JOIN
+                  summary s ON s.summary_key = b.summary_key
+       );
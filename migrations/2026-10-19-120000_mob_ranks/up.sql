@@ -0,0 +1,34 @@
+-- View: defeated_target_damage
+-- Player and pet damage to a target since the last defeat of a target with the same name
+DROP VIEW IF EXISTS defeated_target_damage;
+CREATE VIEW IF NOT EXISTS defeated_target_damage AS
+SELECT summary_key,
+       line_number,
+       target_name,
+       total_damage,
+       damage_before_final_blow,
+       time_to_kill
+  FROM (
+           SELECT dt.summary_key,
+                  dt.line_number,
+                  dt.target_name,
+                  COALESCE(sum(da.damage), 0) AS total_damage,
+                  COALESCE(sum(CASE WHEN julianday(da.log_date) < julianday(dt.log_date) THEN da.damage ELSE 0 END), 0) AS damage_before_final_blow,
+                  COALESCE(CAST (ROUND( (julianday(dt.log_date) - min(julianday(da.log_date) ) ) * 86400) AS INTEGER), 0) AS time_to_kill
+             FROM defeated_targets dt
+                  INNER JOIN
+                  damage_action da ON da.summary_key = dt.summary_key AND 
+                                      da.target_name = dt.target_name AND 
+                                      da.source_type IN ('Player', 'PlayerPet') AND 
+                                      da.line_number <= dt.line_number AND 
+                                      da.line_number > COALESCE( (
+                                                                     SELECT max(p.line_number) 
+                                                                       FROM defeated_targets p
+                                                                      WHERE p.summary_key = dt.summary_key AND 
+                                                                            p.target_name = dt.target_name AND 
+                                                                            p.line_number < dt.line_number
+                                                                 ), 0)
+            GROUP BY dt.summary_key,
+                     dt.line_number,
+                     dt.target_name
+       );
@@ -0,0 +1,3 @@
+-- Table: level_up
+DROP TABLE IF EXISTS level_up;
+CREATE TABLE IF NOT EXISTS level_up (summary_key INTEGER NOT NULL, line_number INTEGER NOT NULL, log_date TEXT NOT NULL, level INTEGER NOT NULL, PRIMARY KEY (summary_key, line_number, log_date), FOREIGN KEY (summary_key) REFERENCES summary (summary_key) ON DELETE CASCADE) STRICT;
@@ -0,0 +1,4 @@
+-- Table: consumable_activation
+-- Inspirations, temp powers and incarnate clicks moved out of player_activation
+DROP TABLE IF EXISTS consumable_activation;
+CREATE TABLE IF NOT EXISTS consumable_activation (summary_key INTEGER NOT NULL, line_number INTEGER NOT NULL, log_date TEXT NOT NULL, power_name TEXT NOT NULL, consumable_type TEXT CHECK (consumable_type IN ('Inspiration', 'TempPower', 'Incarnate')) NOT NULL, PRIMARY KEY (summary_key, line_number, log_date), FOREIGN KEY (summary_key) REFERENCES summary (summary_key) ON DELETE CASCADE) STRICT;
@@ -0,0 +1,3 @@
+-- This file should undo anything in `up.sql`
+DROP TABLE IF EXISTS location_event;
+DROP TABLE IF EXISTS report_scope;
@@ -0,0 +1,8 @@
+-- Table: location_event
+DROP TABLE IF EXISTS location_event;
+CREATE TABLE IF NOT EXISTS location_event (summary_key INTEGER NOT NULL, line_number INTEGER NOT NULL, log_date TEXT NOT NULL, event_type TEXT CHECK (event_type IN ('Zone', 'MissionEntered', 'MissionExited', 'MissionComplete', 'TaskForceComplete')) NOT NULL, name TEXT, PRIMARY KEY (summary_key, line_number, log_date), FOREIGN KEY (summary_key) REFERENCES summary (summary_key) ON DELETE CASCADE) STRICT;
+
+-- Table: report_scope
+-- Only filled in the scoped copies of a report, records the part of the session that was kept
+DROP TABLE IF EXISTS report_scope;
+CREATE TABLE IF NOT EXISTS report_scope (summary_key INTEGER PRIMARY KEY NOT NULL, scope_name TEXT NOT NULL, first_line_number INTEGER NOT NULL, last_line_number INTEGER NOT NULL, source_db TEXT NOT NULL, FOREIGN KEY (summary_key) REFERENCES summary (summary_key) ON DELETE CASCADE) STRICT;
@@ -0,0 +1,4 @@
+-- Table: team_event
+-- Team and league chat and autohit powers used on the player by someone else, used to find who was on the team
+DROP TABLE IF EXISTS team_event;
+CREATE TABLE IF NOT EXISTS team_event (summary_key INTEGER NOT NULL, line_number INTEGER NOT NULL, log_date TEXT NOT NULL, event_type TEXT CHECK (event_type IN ('Chat', 'Buff')) NOT NULL, member_name TEXT NOT NULL, power_name TEXT, PRIMARY KEY (summary_key, line_number, log_date), FOREIGN KEY (summary_key) REFERENCES summary (summary_key) ON DELETE CASCADE) STRICT;
@@ -0,0 +1,4 @@
+-- Table: merge_source
+-- Only filled in merged team reports, records where each session came from and the time period all the logs cover
+DROP TABLE IF EXISTS merge_source;
+CREATE TABLE IF NOT EXISTS merge_source (summary_key INTEGER PRIMARY KEY NOT NULL, source_db TEXT NOT NULL, window_start TEXT NOT NULL, window_end TEXT NOT NULL, FOREIGN KEY (summary_key) REFERENCES summary (summary_key) ON DELETE CASCADE) STRICT;
@@ -0,0 +1,4 @@
+-- Table: market_transaction
+-- Items sold or bought on the consignment house and the influence paid or received
+DROP TABLE IF EXISTS market_transaction;
+CREATE TABLE IF NOT EXISTS market_transaction (summary_key INTEGER NOT NULL, line_number INTEGER NOT NULL, log_date TEXT NOT NULL, transaction_type TEXT CHECK (transaction_type IN ('Sold', 'Bought')) NOT NULL, item_name TEXT NOT NULL, quantity INTEGER NOT NULL, influence INTEGER NOT NULL, PRIMARY KEY (summary_key, line_number, log_date), FOREIGN KEY (summary_key) REFERENCES summary (summary_key) ON DELETE CASCADE) STRICT;
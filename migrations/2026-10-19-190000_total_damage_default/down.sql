@@ -0,0 +1,48 @@
+-- This file should undo anything in `up.sql`
+DROP VIEW IF EXISTS total_damage_report;
+CREATE VIEW IF NOT EXISTS total_damage_report AS select s.summary_key, 
+(select count(*) from player_activation pa where s.summary_key = pa.summary_key) as activations,
+(select count(*) from hit_or_miss hm where s.summary_key = hm.summary_key AND hm.hit = 1 AND source_type IN ('Player', 'PlayerPet')) AS hits,
+(select count(*) from hit_or_miss hm where s.summary_key = hm.summary_key AND hm.streakbreaker = 1 AND source_type IN ('Player', 'PlayerPet')) AS streak_breakers,
+(select count(*) from hit_or_miss hm where s.summary_key = hm.summary_key AND hm.hit = 0 AND source_type IN ('Player', 'PlayerPet')) AS misses,
+(select sum(da.damage) from damage_action da where s.summary_key = da.summary_key AND source_type IN ('Player', 'PlayerPet')) AS total_damage,
+(CASE WHEN(select sum(da.damage) from damage_action da where s.summary_key = da.summary_key AND damage_mode = 'Direct' AND source_type IN ('Player', 'PlayerPet')) IS NULL
+THEN 0
+ELSE
+(select sum(da.damage) from damage_action da where s.summary_key = da.summary_key AND damage_mode = 'Direct' AND source_type IN ('Player', 'PlayerPet'))
+END) AS direct_damage,
+
+(CASE WHEN(select sum(da.damage) from damage_action da where s.summary_key = da.summary_key AND damage_mode = 'DoT' AND source_type IN ('Player', 'PlayerPet')) IS NULL
+THEN 0
+ELSE
+(select sum(da.damage) from damage_action da where s.summary_key = da.summary_key AND damage_mode = 'DoT' AND source_type IN ('Player', 'PlayerPet'))
+END) AS dot_damage,
+
+(CASE WHEN(select sum(da.damage) from damage_action da where s.summary_key = da.summary_key AND damage_mode = 'Critical' AND source_type IN ('Player', 'PlayerPet')) IS NULL
+THEN 0
+ELSE
+(select sum(da.damage) from damage_action da where s.summary_key = da.summary_key AND damage_mode = 'Critical' AND source_type IN ('Player', 'PlayerPet'))
+END) AS critical_damage,
+
+(select count(da.damage) from damage_action da where s.summary_key = da.summary_key AND damage_mode = 'Critical' AND source_type IN ('Player', 'PlayerPet')) AS critical_hits,
+
+(CASE
+WHEN (select ROUND(1.0 * count(da.damage) / (select count(*) from hit_or_miss hm where s.summary_key = hm.summary_key AND hm.hit = 1) *100 )
+ from damage_action da where s.summary_key = da.summary_key AND damage_mode = 'Critical' AND source_type IN ('Player', 'PlayerPet')) IS NULL
+THEN 0
+ELSE
+(select ROUND(1.0 * count(da.damage) / (select count(*) from hit_or_miss hm where s.summary_key = hm.summary_key AND hm.hit = 1) *100 )
+ from damage_action da where s.summary_key = da.summary_key AND damage_mode = 'Critical' AND source_type IN ('Player', 'PlayerPet'))
+END) AS critical_hit_percentage,
+ 
+(CASE 
+WHEN (select ROUND((1.0 * sum(da.damage) / (select sum(da.damage) from damage_action da where s.summary_key = da.summary_key AND source_type IN ('Player', 'PlayerPet')) * 100))
+ from damage_action da where s.summary_key = da.summary_key AND damage_mode = 'Critical' AND source_type IN ('Player', 'PlayerPet')) IS NULL
+THEN 0
+ELSE
+(select ROUND((1.0 * sum(da.damage) / (select sum(da.damage) from damage_action da where s.summary_key = da.summary_key AND source_type IN ('Player', 'PlayerPet')) * 100))
+ from damage_action da where s.summary_key = da.summary_key AND damage_mode = 'Critical' AND source_type IN ('Player', 'PlayerPet')) 
+END) AS critical_damage_percentage
+ 
+from summary s
+order by total_damage desc;
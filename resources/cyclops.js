@@ -22,3 +22,20 @@ function summary_close(name) {
         e.hidden = 'hidden';
     }
 };
+
+function compare_selected() {
+    const selected = document.querySelectorAll('input.compare-select:checked');
+    if (selected.length != 2) {
+        alert('Select exactly two summaries to compare.');
+        return;
+    }
+    const left = selected[0].dataset;
+    const right = selected[1].dataset;
+    const params = new URLSearchParams({
+        left_key: left.key,
+        left_report: left.report,
+        right_key: right.key,
+        right_report: right.report,
+    });
+    window.open('/compare?' + params.toString(), '_blank');
+};
@@ -50,4 +50,22 @@ pub struct Args {
         value_name = "Monitoring configuration file."
     )]
     pub monitorconfig: Option<PathBuf>,
+    #[arg(
+        long,
+        required = false,
+        value_name = "Merge the logs into one team report. For logs from teammates or multi-boxed characters covering the same time."
+    )]
+    pub merge: bool,
+    #[arg(
+        long,
+        required = false,
+        value_name = "Write a self-contained HTML copy of each summary into its report directory while parsing."
+    )]
+    pub export: bool,
+    #[arg(
+        long,
+        required = false,
+        value_name = "Directory with templates and resources folders. Files in them replace the built in pages, css, js and data tables."
+    )]
+    pub assetsdir: Option<PathBuf>,
 }
@@ -0,0 +1,88 @@
+use std::{
+    borrow::Cow,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use lazy_static::lazy_static;
+use tera::Tera;
+
+// EMBEDDED_TEMPLATES and EMBEDDED_RESOURCES, the templates and resources directories at build time
+include!(concat!(env!("OUT_DIR"), "/embedded_assets.rs"));
+
+const TEMPLATES_DIR: &str = "templates";
+const RESOURCES_DIR: &str = "resources";
+
+lazy_static! {
+    // Files in the templates and resources folders of this directory replace the embedded ones
+    static ref OVERRIDE_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+}
+
+pub fn set_override_dir(dir: PathBuf) {
+    *OVERRIDE_DIR.lock().unwrap() = Some(dir);
+}
+
+// Only plain file names, a name from a url can't reach outside the override directory
+fn override_file(sub_dir: &str, name: &str) -> Option<PathBuf> {
+    if Path::new(name).file_name().and_then(|f| f.to_str()) != Some(name) {
+        return None;
+    }
+    let dir = OVERRIDE_DIR.lock().unwrap().clone()?;
+    let path = dir.join(sub_dir).join(name);
+    if path.is_file() {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+pub fn resource(name: &str) -> Option<Cow<'static, [u8]>> {
+    if let Some(path) = override_file(RESOURCES_DIR, name) {
+        match fs::read(&path) {
+            Ok(data) => return Some(Cow::Owned(data)),
+            Err(e) => println!("Unable to read {:?}, using the built in copy: {:?}", path, e),
+        }
+    }
+    EMBEDDED_RESOURCES
+        .iter()
+        .find(|(n, _)| *n == name)
+        .map(|(_, data)| Cow::Borrowed(*data))
+}
+
+pub fn resource_string(name: &str) -> String {
+    match resource(name) {
+        Some(data) => String::from_utf8_lossy(&data).into_owned(),
+        None => {
+            println!("Missing resource: {}", name);
+            String::new()
+        }
+    }
+}
+
+// The embedded templates, then any templates in the override directory on top
+pub fn load_templates() -> tera::Result<Tera> {
+    let mut tera = Tera::default();
+    tera.add_raw_templates(EMBEDDED_TEMPLATES.iter().copied())?;
+
+    let override_dir = OVERRIDE_DIR.lock().unwrap().clone();
+    if let Some(dir) = override_dir {
+        let templates: Vec<(PathBuf, Option<String>)> = match fs::read_dir(dir.join(TEMPLATES_DIR)) {
+            Ok(entries) => entries
+                .flatten()
+                .map(|e| e.path())
+                .filter(|p| p.is_file() && p.extension().is_some_and(|e| e == "html"))
+                .map(|p| {
+                    let name = p.file_name().map(|n| n.to_string_lossy().to_string());
+                    (p, name)
+                })
+                .collect(),
+            Err(_) => Vec::new(),
+        };
+        for (path, _) in &templates {
+            println!("Using template {:?}", path);
+        }
+        tera.add_template_files(templates)?;
+    }
+    Ok(tera)
+}
@@ -5,9 +5,11 @@ use diesel::SqliteConnection;
 
 use crate::game_data;
 use crate::log_processing::parser_model::*;
-use crate::models::{DamageAction, DefeatedTarget, HitOrMiss, PlayerActivation, PlayerPowerRecharged, Reward, Summary};
+use crate::models::{BenchmarkRun, ControlAction, DamageAction, DebuffAction, DefeatedTarget, HitOrMiss, LevelUp, LocationEvent, MarketTransaction, PlayerActivation, PlayerDeath, PlayerPowerRecharged, Reward, Summary, TeamEvent};
 
-use crate::schema::{damage_action, defeated_targets, hit_or_miss, player_activation, player_power_recharged, reward, summary};
+use crate::schema::{benchmark_run, control_action, damage_action, debuff_action, defeated_targets, heal_endurance_action, hit_or_miss, level_up, location_event, market_transaction, player_activation, player_death, player_power_recharged, reward, summary, team_event};
+
+pub const PLACEHOLDER_NAME: &str = "NO NAME";
 
 pub fn write_to_database(
     conn: &mut SqliteConnection,
@@ -22,11 +24,21 @@ pub fn write_to_database(
     let mut damage_actions: Vec<DamageAction> = Vec::new();
     let mut defeats: Vec<DefeatedTarget> = Vec::new();
     let mut rewards: Vec<Reward> = Vec::new();
+    let mut deaths: Vec<PlayerDeath> = Vec::new();
+    let mut heals_endurance: Vec<crate::models::HealEnduranceAction> = Vec::new();
+    let mut controls: Vec<ControlAction> = Vec::new();
+    let mut benchmarks: Vec<BenchmarkRun> = Vec::new();
+    let mut debuffs: Vec<DebuffAction> = Vec::new();
+    let mut level_ups: Vec<LevelUp> = Vec::new();
+    let mut locations: Vec<LocationEvent> = Vec::new();
+    let mut team_events: Vec<TeamEvent> = Vec::new();
+    let mut market_transactions: Vec<MarketTransaction> = Vec::new();
+    let mut benchmark_lines: Vec<i32> = Vec::new();
 
     // Create placeholder summary
     let placeholder = Summary {
         summary_key: key,
-        player_name: String::from("NO NAME"),
+        player_name: String::from(PLACEHOLDER_NAME),
         log_date: String::from("PLACEHOLDER"),
         first_line_number: 1,
         last_line_number: data_points.len() as i32,
@@ -39,6 +51,7 @@ pub fn write_to_database(
             FileDataPoint::SessionMarker {
                 data_position,
                 player_name,
+                test_name,
             } => {
                 summaries.push(Summary {
                     summary_key: data_position.date.timestamp() as i32,
@@ -48,17 +61,35 @@ pub fn write_to_database(
                     last_line_number: data_points.len() as i32,
                     log_file_name: String::from(&file_name),
                 });
+                if let Some(name) = test_name {
+                    benchmark_lines.push(data_position.line_number as i32);
+                    benchmarks.push(BenchmarkRun {
+                        summary_key: data_position.date.timestamp() as i32,
+                        test_name: name.clone(),
+                    });
+                }
             }
             FileDataPoint::PlayerPowerActivation {
                 data_position,
                 power_name,
-            } => activations.push(PlayerActivation {
-                summary_key: key,
-                line_number: data_position.line_number as i32,
-                log_date: data_position.date.to_rfc3339(),
-                power_name: power_name.clone(),
-                proc_fire: 0,
-            }),
+            } => {
+                activations.push(PlayerActivation {
+                    summary_key: key,
+                    line_number: data_position.line_number as i32,
+                    log_date: data_position.date.to_rfc3339(),
+                    power_name: power_name.clone(),
+                    proc_fire: 0,
+                });
+                if game_data::is_self_resurrect(power_name) {
+                    deaths.push(PlayerDeath {
+                        summary_key: key,
+                        line_number: data_position.line_number as i32,
+                        log_date: data_position.date.to_rfc3339(),
+                        event_type: String::from("Awakened"),
+                        source_name: Some(power_name.clone()),
+                    });
+                }
+            }
             FileDataPoint::PlayerPowerRecharged {
                 data_position,
                 power_name,
@@ -73,17 +104,13 @@ pub fn write_to_database(
                 source: _,
                 target: _,
                 power_name,
-            } => {
-                if power_name.contains("Force Feedback") {
-                    activations.push(PlayerActivation {
-                        summary_key: key,
-                        line_number: data_position.line_number as i32,
-                        log_date: data_position.date.to_rfc3339(),
-                        power_name: power_name.clone(),
-                        proc_fire: 1,
-                    })
-                }
-            }
+            } if game_data::is_proc(power_name) => activations.push(PlayerActivation {
+                summary_key: key,
+                line_number: data_position.line_number as i32,
+                log_date: data_position.date.to_rfc3339(),
+                power_name: power_name.clone(),
+                proc_fire: 1,
+            }),
             FileDataPoint::PlayerHit {
                 data_position,
                 action_result,
@@ -309,9 +336,7 @@ pub fn write_to_database(
                     source_name: String::from("Player"),
                 });
 
-                if damage_dealt.power_name.contains("Chance for")
-                    || damage_dealt.power_name.contains("Spider's Bite")
-                {
+                if game_data::is_proc(&damage_dealt.power_name) {
                     activations.push(PlayerActivation {
                         summary_key: key,
                         line_number: data_position.line_number as i32,
@@ -337,7 +362,7 @@ pub fn write_to_database(
                     source_type: String::from("Player"),
                     source_name: String::from("Player"),
                 });
-                if damage_dealt.power_name.contains("Interface") {
+                if game_data::is_proc(&damage_dealt.power_name) {
                     activations.push(PlayerActivation {
                         summary_key: key,
                         line_number: data_position.line_number as i32,
@@ -398,9 +423,7 @@ pub fn write_to_database(
                 //     power_name: damage_dealt.power_name.clone(),
                 //     streakbreaker: 0,
                 // });
-                if damage_dealt.power_name.contains("Chance for")
-                    || damage_dealt.power_name.contains("Spider's Bite")
-                {
+                if game_data::is_proc(&damage_dealt.power_name) {
                     activations.push(PlayerActivation {
                         summary_key: key,
                         line_number: data_position.line_number as i32,
@@ -585,6 +608,162 @@ pub fn write_to_database(
                 item_drop: None,
                 reward_type: String::from("ExpAndInf"),
             }),
+            FileDataPoint::LevelUp {
+                data_position,
+                level,
+            } => level_ups.push(LevelUp {
+                summary_key: key,
+                line_number: data_position.line_number as i32,
+                log_date: data_position.date.to_rfc3339(),
+                level: *level as i32,
+            }),
+            FileDataPoint::MarketTransaction {
+                data_position,
+                transaction_type,
+                item_name,
+                quantity,
+                influence,
+            } => market_transactions.push(MarketTransaction {
+                summary_key: key,
+                line_number: data_position.line_number as i32,
+                log_date: data_position.date.to_rfc3339(),
+                transaction_type: format!("{:?}", transaction_type),
+                item_name: item_name.clone(),
+                quantity: *quantity as i32,
+                influence: *influence as i64,
+            }),
+            FileDataPoint::LocationEvent {
+                data_position,
+                event_type,
+                name,
+            } => locations.push(LocationEvent {
+                summary_key: key,
+                line_number: data_position.line_number as i32,
+                log_date: data_position.date.to_rfc3339(),
+                event_type: format!("{:?}", event_type),
+                name: name.clone(),
+            }),
+            FileDataPoint::ChatMessage {
+                data_position,
+                category,
+                message,
+            } => {
+                if let Some(speaker) = team_chat_speaker(category, message) {
+                    team_events.push(TeamEvent {
+                        summary_key: key,
+                        line_number: data_position.line_number as i32,
+                        log_date: data_position.date.to_rfc3339(),
+                        event_type: String::from("Chat"),
+                        member_name: String::from(speaker),
+                        power_name: None,
+                    });
+                }
+            }
+            FileDataPoint::OtherAutoHit {
+                data_position,
+                action_result,
+                name,
+            } => team_events.push(TeamEvent {
+                summary_key: key,
+                line_number: data_position.line_number as i32,
+                log_date: data_position.date.to_rfc3339(),
+                event_type: String::from("Buff"),
+                member_name: String::from(name),
+                power_name: Some(action_result.power_name.clone()),
+            }),
+            FileDataPoint::PlayerDefeated {
+                data_position,
+                source,
+            } => deaths.push(PlayerDeath {
+                summary_key: key,
+                line_number: data_position.line_number as i32,
+                log_date: data_position.date.to_rfc3339(),
+                event_type: String::from("Defeated"),
+                source_name: source.clone(),
+            }),
+            FileDataPoint::PlayerResurrected {
+                data_position,
+                source,
+                awakened,
+            } => deaths.push(PlayerDeath {
+                summary_key: key,
+                line_number: data_position.line_number as i32,
+                log_date: data_position.date.to_rfc3339(),
+                event_type: if *awakened {
+                    String::from("Awakened")
+                } else {
+                    String::from("Resurrected")
+                },
+                source_name: source.clone(),
+            }),
+            FileDataPoint::PlayerHealed {
+                data_position,
+                heal_action,
+            }
+            | FileDataPoint::PlayerHealOther {
+                data_position,
+                heal_action,
+            } => heals_endurance.push(heal_endurance_row(key, data_position, "Heal", heal_action)),
+            FileDataPoint::PlayerEndurance {
+                data_position,
+                heal_action,
+            }
+            | FileDataPoint::PlayerEnduranceOther {
+                data_position,
+                heal_action,
+            } => heals_endurance.push(heal_endurance_row(key, data_position, "Endurance", heal_action)),
+            FileDataPoint::MobControl {
+                data_position,
+                name,
+                control_type,
+            } => controls.push(control_row(key, data_position, "Mob", name, "Player", control_type)),
+            FileDataPoint::MobPseudoPetControl {
+                data_position,
+                name,
+                control_type,
+            } => controls.push(control_row(key, data_position, "MobPet", name, "Player", control_type)),
+            FileDataPoint::PlayerControl {
+                data_position,
+                control_type,
+            } => controls.push(control_row(
+                key,
+                data_position,
+                "Player",
+                "Player",
+                &control_type.target,
+                control_type,
+            )),
+            FileDataPoint::PseudoPetControl {
+                data_position,
+                pet_name,
+                control_type,
+            } => controls.push(control_row(
+                key,
+                data_position,
+                "PlayerPet",
+                pet_name,
+                &control_type.target,
+                control_type,
+            )),
+            FileDataPoint::PlayerResistDebuff {
+                data_position,
+                target,
+                power_name,
+            } => debuffs.push(debuff_row(key, data_position, "Player", "Player", power_name, target, "Resistance")),
+            FileDataPoint::PseudoPetResistDebuff {
+                data_position,
+                pet_name,
+                target,
+                power_name,
+            } => debuffs.push(debuff_row(key, data_position, "PlayerPet", pet_name, power_name, target, "Resistance")),
+            FileDataPoint::AutohitPower {
+                data_position,
+                source: _,
+                target,
+                power_name,
+            } if !game_data::is_proc(power_name) => {
+                debuffs.push(debuff_row(key, data_position, "Player", "Player", power_name, target, "Autohit"))
+            }
             _ => (),
         }
     }
@@ -616,7 +795,41 @@ pub fn write_to_database(
             insert_rewards(conn, &rewards);
         }
 
-        let final_summaries = finalize_summaries(conn, data_points.len(), &summaries[..]);
+        if !deaths.is_empty() {
+            insert_deaths(conn, &deaths);
+        }
+
+        if !heals_endurance.is_empty() {
+            insert_heals_endurance(conn, &heals_endurance);
+        }
+
+        if !controls.is_empty() {
+            insert_controls(conn, &controls);
+        }
+
+        if !benchmarks.is_empty() {
+            insert_benchmarks(conn, &benchmarks);
+        }
+
+        if !debuffs.is_empty() {
+            insert_debuffs(conn, &debuffs);
+        }
+
+        if !level_ups.is_empty() {
+            insert_level_ups(conn, &level_ups);
+        }
+
+        if !locations.is_empty() {
+            insert_locations(conn, &locations);
+        }
+        if !team_events.is_empty() {
+            insert_team_events(conn, &team_events);
+        }
+        if !market_transactions.is_empty() {
+            insert_market_transactions(conn, &market_transactions);
+        }
+
+        let final_summaries = finalize_summaries(conn, data_points.len(), &summaries[..], &benchmark_lines);
         finalize_data(conn, &final_summaries[..]);
         cleanup_summaries(conn);
     }
@@ -634,11 +847,12 @@ pub fn write_to_monitor(
     let mut recharges: Vec<PlayerPowerRecharged> = Vec::new();
     let mut damage_actions: Vec<DamageAction> = Vec::new();
     let mut rewards: Vec<Reward> = Vec::new();
+    let mut benchmark_lines: Vec<i32> = Vec::new();
 
     // Create placeholder summary
     let placeholder = Summary {
         summary_key: key,
-        player_name: String::from("NO NAME"),
+        player_name: String::from(PLACEHOLDER_NAME),
         log_date: String::from("PLACEHOLDER"),
         first_line_number: 1,
         last_line_number: line_count as i32,
@@ -651,7 +865,11 @@ pub fn write_to_monitor(
             FileDataPoint::SessionMarker {
                 data_position,
                 player_name,
+                test_name,
             } => {
+                if test_name.is_some() {
+                    benchmark_lines.push(data_position.line_number as i32);
+                }
                 summaries.push(Summary {
                     summary_key: data_position.date.timestamp() as i32,
                     player_name: player_name.clone(),
@@ -697,9 +915,7 @@ pub fn write_to_monitor(
                     source_name: String::from("Player"),
                 });
 
-                if damage_dealt.power_name.contains("Chance for")
-                    || damage_dealt.power_name.contains("Spider's Bite")
-                {
+                if game_data::is_proc(&damage_dealt.power_name) {
                     activations.push(PlayerActivation {
                         summary_key: key,
                         line_number: data_position.line_number as i32,
@@ -725,7 +941,7 @@ pub fn write_to_monitor(
                     source_type: String::from("Player"),
                     source_name: String::from("Player"),
                 });
-                if damage_dealt.power_name.contains("Interface") {
+                if game_data::is_proc(&damage_dealt.power_name) {
                     activations.push(PlayerActivation {
                         summary_key: key,
                         line_number: data_position.line_number as i32,
@@ -786,9 +1002,7 @@ pub fn write_to_monitor(
                 //     power_name: damage_dealt.power_name.clone(),
                 //     streakbreaker: 0,
                 // });
-                if damage_dealt.power_name.contains("Chance for")
-                    || damage_dealt.power_name.contains("Spider's Bite")
-                {
+                if game_data::is_proc(&damage_dealt.power_name) {
                     activations.push(PlayerActivation {
                         summary_key: key,
                         line_number: data_position.line_number as i32,
@@ -871,7 +1085,7 @@ pub fn write_to_monitor(
             insert_rewards(conn, &rewards);
         }
 
-        let final_summaries = finalize_summaries(conn, line_count as usize, &summaries[..]);
+        let final_summaries = finalize_summaries(conn, line_count as usize, &summaries[..], &benchmark_lines);
         finalize_data(conn, &final_summaries[..]);
         cleanup_summaries(conn);
     }
@@ -926,10 +1140,144 @@ fn insert_rewards(conn: &mut SqliteConnection, rewards: &Vec<Reward>) {
         .expect("Error saving new damage action");
 }
 
+fn insert_deaths(conn: &mut SqliteConnection, deaths: &[PlayerDeath]) {
+    diesel::insert_into(player_death::table)
+        .values(deaths)
+        .execute(conn)
+        .expect("Error saving new player death");
+}
+
+fn insert_heals_endurance(conn: &mut SqliteConnection, actions: &[crate::models::HealEnduranceAction]) {
+    diesel::insert_into(heal_endurance_action::table)
+        .values(actions)
+        .execute(conn)
+        .expect("Error saving new heal or endurance action");
+}
+
+fn insert_controls(conn: &mut SqliteConnection, actions: &[ControlAction]) {
+    diesel::insert_into(control_action::table)
+        .values(actions)
+        .execute(conn)
+        .expect("Error saving new control action");
+}
+
+fn insert_benchmarks(conn: &mut SqliteConnection, runs: &[BenchmarkRun]) {
+    diesel::insert_into(benchmark_run::table)
+        .values(runs)
+        .execute(conn)
+        .expect("Error saving new benchmark run");
+}
+
+fn insert_debuffs(conn: &mut SqliteConnection, debuffs: &[DebuffAction]) {
+    diesel::insert_into(debuff_action::table)
+        .values(debuffs)
+        .execute(conn)
+        .expect("Error saving new debuff action");
+}
+
+fn insert_level_ups(conn: &mut SqliteConnection, level_ups: &[LevelUp]) {
+    diesel::insert_into(level_up::table)
+        .values(level_ups)
+        .execute(conn)
+        .expect("Error saving new level up");
+}
+
+fn insert_locations(conn: &mut SqliteConnection, locations: &[LocationEvent]) {
+    diesel::insert_into(location_event::table)
+        .values(locations)
+        .execute(conn)
+        .expect("Error saving new location event");
+}
+
+fn insert_team_events(conn: &mut SqliteConnection, events: &[TeamEvent]) {
+    diesel::insert_into(team_event::table)
+        .values(events)
+        .execute(conn)
+        .expect("Error saving new team event");
+}
+
+fn insert_market_transactions(conn: &mut SqliteConnection, transactions: &[MarketTransaction]) {
+    diesel::insert_into(market_transaction::table)
+        .values(transactions)
+        .execute(conn)
+        .expect("Error saving new market transaction");
+}
+
+// Team and league chat lines start with the speaker's name
+fn team_chat_speaker<'a>(category: &str, message: &'a str) -> Option<&'a str> {
+    if category != "Team" && category != "League" {
+        return None;
+    }
+    message
+        .split_once(": ")
+        .map(|(speaker, _)| speaker.trim())
+        .filter(|speaker| !speaker.is_empty())
+}
+
+fn debuff_row(
+    key: i32,
+    data_position: &DataPosition,
+    source_type: &str,
+    source_name: &str,
+    power_name: &str,
+    target_name: &str,
+    debuff_type: &str,
+) -> DebuffAction {
+    DebuffAction {
+        summary_key: key,
+        line_number: data_position.line_number as i32,
+        log_date: data_position.date.to_rfc3339(),
+        source_type: Some(source_type.to_string()),
+        source_name: source_name.to_string(),
+        power_name: Some(power_name.to_string()),
+        target_name: Some(target_name.to_string()),
+        debuff_type: Some(debuff_type.to_string()),
+    }
+}
+
+fn heal_endurance_row(
+    key: i32,
+    data_position: &DataPosition,
+    action_type: &str,
+    heal_action: &HealEnduranceAction,
+) -> crate::models::HealEnduranceAction {
+    crate::models::HealEnduranceAction {
+        summary_key: key,
+        line_number: data_position.line_number as i32,
+        log_date: data_position.date.to_rfc3339(),
+        action_type: String::from(action_type),
+        source_name: heal_action.source.clone(),
+        target_name: heal_action.target.clone(),
+        power_name: heal_action.power_name.clone(),
+        amount: heal_action.amount.round() as i32,
+    }
+}
+
+fn control_row(
+    key: i32,
+    data_position: &DataPosition,
+    source_type: &str,
+    source_name: &str,
+    target_name: &str,
+    control_power: &ControlPower,
+) -> ControlAction {
+    ControlAction {
+        summary_key: key,
+        line_number: data_position.line_number as i32,
+        log_date: data_position.date.to_rfc3339(),
+        source_type: String::from(source_type),
+        source_name: String::from(source_name),
+        target_name: String::from(target_name),
+        power_name: control_power.power_name.clone(),
+        control_type: control_power.control_type.to_string(),
+    }
+}
+
 fn finalize_summaries(
     conn: &mut SqliteConnection,
     end_line: usize,
     summaries: &[Summary],
+    benchmark_lines: &[i32],
 ) -> Vec<Summary> {
     use crate::schema::summary::dsl::*;
 
@@ -946,10 +1294,7 @@ fn finalize_summaries(
             start_lines.push(s.first_line_number);
         }
 
-        let mut end_lines: Vec<i32> = start_lines.iter().map(|i| i - 1).collect();
-
-        end_lines.remove(0);
-        end_lines.push(end_line as i32);
+        let end_lines = session_end_lines(&start_lines, end_line as i32, benchmark_lines);
 
         for (i, _) in summaries.iter().enumerate() {
             let query = diesel::update(summary)
@@ -959,12 +1304,35 @@ fn finalize_summaries(
         }
     }
 
-    conn.batch_execute("update summary set log_date = (select pa.log_date from player_activation pa, summary s where s.summary_key = pa.summary_key AND s.log_date = 'PLACEHOLDER' group by s.summary_key)
+    // Market only sessions have no activations, use the first transaction instead
+    conn.batch_execute("update summary set log_date = coalesce((select pa.log_date from player_activation pa, summary s where s.summary_key = pa.summary_key AND s.log_date = 'PLACEHOLDER' group by s.summary_key),
+    (select min(mt.log_date) from market_transaction mt, summary s where s.summary_key = mt.summary_key AND s.log_date = 'PLACEHOLDER'), log_date)
     where log_date = 'PLACEHOLDER'").expect("Unable to update date for placeholder summary");
 
     summary.select(Summary::as_select()).load(conn).unwrap()
 }
 
+fn session_end_lines(start_lines: &[i32], end_line: i32, benchmark_lines: &[i32]) -> Vec<i32> {
+    let mut end_lines: Vec<i32> = start_lines.iter().map(|i| i - 1).collect();
+
+    end_lines.remove(0);
+    end_lines.push(end_line);
+
+    // A benchmark run typed right after another marker, or ended right away, leaves a session
+    // with no lines. Give it one line to pass the table check, cleanup_summaries removes it.
+    for (i, end) in end_lines.iter_mut().enumerate() {
+        let start = start_lines[i];
+        let next_is_benchmark = start_lines
+            .get(i + 1)
+            .is_some_and(|next| benchmark_lines.contains(next));
+        if benchmark_lines.contains(&start) || next_is_benchmark {
+            *end = (*end).max(start + 1);
+        }
+    }
+
+    end_lines
+}
+
 // There be fence post dragons here, pay attention to the gt(greater than) vs le(less than equals)
 fn finalize_data(conn: &mut SqliteConnection, summaries: &[Summary]) {
     for s in summaries {
@@ -974,8 +1342,17 @@ fn finalize_data(conn: &mut SqliteConnection, summaries: &[Summary]) {
         finalize_damage_action(conn, s);
         finalize_defeats(conn, s);
         finalize_rewards(conn, s);
+        finalize_deaths(conn, s);
+        finalize_heals_endurance(conn, s);
+        finalize_controls(conn, s);
+        finalize_debuffs(conn, s);
+        finalize_level_ups(conn, s);
+        finalize_locations(conn, s);
+        finalize_team_events(conn, s);
+        finalize_market_transactions(conn, s);
     }
     finalize_name_normalization(conn);
+    finalize_consumables(conn);
     finalize_pseudo_pets(conn);
     finalize_sim_hits(conn)
 }
@@ -1056,17 +1433,19 @@ fn finalize_defeats(conn: &mut SqliteConnection, s: &Summary) {
     let le_ln = line_number.le(s.last_line_number);
 
     use crate::schema::defeated_targets::dsl::*;
-    let player_damage_pre = crate::schema::defeated_targets::source_name.eq("Player");
+    // The placeholder summary covers every line and is finalized first,
+    // so player defeats may already carry its name
+    let player_names = vec!["Player", PLACEHOLDER_NAME];
 
     diesel::update(defeated_targets)
         .filter(gt_ln.and(le_ln))
-        .filter(not(player_damage_pre))
+        .filter(not(source_name.eq_any(player_names.clone())))
         .set(summary_key.eq(s.summary_key))
         .execute(conn)
         .expect("Unable to update other defeats");
 
     diesel::update(defeated_targets)
-        .filter(gt_ln.and(le_ln).and(player_damage_pre))
+        .filter(gt_ln.and(le_ln).and(source_name.eq_any(player_names)))
         .set((
             summary_key.eq(s.summary_key),
             source_name.eq(s.player_name.clone()),
@@ -1087,6 +1466,102 @@ fn finalize_rewards(conn: &mut SqliteConnection, s: &Summary) {
         .execute(conn)
         .expect("Unable to update rewards");
 }
+fn finalize_deaths(conn: &mut SqliteConnection, s: &Summary) {
+    let gt_ln = line_number.gt(s.first_line_number);
+    let le_ln = line_number.le(s.last_line_number);
+
+    use crate::schema::player_death::dsl::*;
+    diesel::update(player_death)
+        .filter(gt_ln.and(le_ln))
+        .set((summary_key.eq(s.summary_key),))
+        .execute(conn)
+        .expect("Unable to update deaths");
+}
+
+fn finalize_heals_endurance(conn: &mut SqliteConnection, s: &Summary) {
+    let gt_ln = line_number.gt(s.first_line_number);
+    let le_ln = line_number.le(s.last_line_number);
+
+    use crate::schema::heal_endurance_action::dsl::*;
+    diesel::update(heal_endurance_action)
+        .filter(gt_ln.and(le_ln))
+        .set((summary_key.eq(s.summary_key),))
+        .execute(conn)
+        .expect("Unable to update heals and endurance");
+}
+
+fn finalize_controls(conn: &mut SqliteConnection, s: &Summary) {
+    let gt_ln = line_number.gt(s.first_line_number);
+    let le_ln = line_number.le(s.last_line_number);
+
+    use crate::schema::control_action::dsl::*;
+    diesel::update(control_action)
+        .filter(gt_ln.and(le_ln))
+        .set((summary_key.eq(s.summary_key),))
+        .execute(conn)
+        .expect("Unable to update controls");
+}
+
+fn finalize_debuffs(conn: &mut SqliteConnection, s: &Summary) {
+    let gt_ln = line_number.gt(s.first_line_number);
+    let le_ln = line_number.le(s.last_line_number);
+
+    use crate::schema::debuff_action::dsl::*;
+    diesel::update(debuff_action)
+        .filter(gt_ln.and(le_ln))
+        .set((summary_key.eq(s.summary_key),))
+        .execute(conn)
+        .expect("Unable to update debuffs");
+}
+
+fn finalize_level_ups(conn: &mut SqliteConnection, s: &Summary) {
+    let gt_ln = line_number.gt(s.first_line_number);
+    let le_ln = line_number.le(s.last_line_number);
+
+    use crate::schema::level_up::dsl::*;
+    diesel::update(level_up)
+        .filter(gt_ln.and(le_ln))
+        .set((summary_key.eq(s.summary_key),))
+        .execute(conn)
+        .expect("Unable to update level ups");
+}
+
+fn finalize_locations(conn: &mut SqliteConnection, s: &Summary) {
+    let gt_ln = line_number.gt(s.first_line_number);
+    let le_ln = line_number.le(s.last_line_number);
+
+    use crate::schema::location_event::dsl::*;
+    diesel::update(location_event)
+        .filter(gt_ln.and(le_ln))
+        .set((summary_key.eq(s.summary_key),))
+        .execute(conn)
+        .expect("Unable to update location events");
+}
+
+fn finalize_team_events(conn: &mut SqliteConnection, s: &Summary) {
+    let gt_ln = line_number.gt(s.first_line_number);
+    let le_ln = line_number.le(s.last_line_number);
+
+    use crate::schema::team_event::dsl::*;
+    diesel::update(team_event)
+        .filter(gt_ln.and(le_ln))
+        .set((summary_key.eq(s.summary_key),))
+        .execute(conn)
+        .expect("Unable to update team events");
+}
+
+fn finalize_market_transactions(conn: &mut SqliteConnection, s: &Summary) {
+    let gt_ln = line_number.gt(s.first_line_number);
+    let le_ln = line_number.le(s.last_line_number);
+
+    use crate::schema::market_transaction::dsl::*;
+    diesel::update(market_transaction)
+        .filter(gt_ln.and(le_ln))
+        .set((summary_key.eq(s.summary_key),))
+        .execute(conn)
+        .expect("Unable to update market transactions");
+}
+
 fn finalize_name_normalization(conn: &mut SqliteConnection) {
     for power in game_data::NAME_NORMALIZATION_TABLE.iter() {
         diesel::update(player_activation::table)
@@ -1102,6 +1577,23 @@ fn finalize_name_normalization(conn: &mut SqliteConnection) {
     }
 }
 
+// Moves inspirations, temp powers and incarnate clicks out of player_activation
+// so they do not count as attacks
+fn finalize_consumables(conn: &mut SqliteConnection) {
+    for c in game_data::CONSUMABLES_TABLE.iter() {
+        diesel::sql_query("INSERT OR IGNORE INTO consumable_activation (summary_key, line_number, log_date, power_name, consumable_type) SELECT summary_key, line_number, log_date, power_name, ? FROM player_activation WHERE power_name LIKE ?")
+            .bind::<diesel::sql_types::Text, _>(format!("{:?}", c.consumable_type))
+            .bind::<diesel::sql_types::Text, _>(&c.power_name)
+            .execute(conn)
+            .expect("Unable to copy consumable activations");
+
+        diesel::delete(player_activation::table)
+            .filter(player_activation::power_name.like(&c.power_name))
+            .execute(conn)
+            .expect("Unable to remove consumable activations");
+    }
+}
+
 fn finalize_pseudo_pets(conn: &mut SqliteConnection) {
     for pet in game_data::PSEUDO_PETS_TABLE.iter() {
         diesel::update(player_activation::table)
@@ -1188,7 +1680,29 @@ fn finalize_sim_hits(conn: &mut SqliteConnection) {
 }
 
 fn cleanup_summaries(conn: &mut SqliteConnection) {
-    diesel::sql_query("delete from summary as s WHERE summary_key NOT IN (select summary_key from damage_action a where s.summary_key = a.summary_key)")
+    diesel::sql_query("delete from summary as s WHERE summary_key NOT IN (select summary_key from damage_action a where s.summary_key = a.summary_key)
+    AND summary_key NOT IN (select summary_key from market_transaction m where s.summary_key = m.summary_key)")
         .execute(conn)
         .expect("An error has occured");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::session_end_lines;
+
+    #[test]
+    fn sessions_without_benchmarks_split_on_the_next_marker() {
+        // Placeholder, then logins and parse markers
+        let start_lines = [1, 4, 120, 121, 300];
+
+        assert_eq!(session_end_lines(&start_lines, 500, &[]), vec![3, 119, 120, 299, 500]);
+    }
+
+    #[test]
+    fn empty_sessions_around_a_benchmark_get_one_line() {
+        // ENDPARSE at 50, STARTPARSE pylon at 51, ENDPARSE at 52
+        let start_lines = [1, 10, 50, 51, 52];
+
+        assert_eq!(session_end_lines(&start_lines, 200, &[51]), vec![9, 49, 51, 52, 200]);
+    }
+}
@@ -2,12 +2,20 @@ pub mod event_processing;
 pub mod queries;
 
 use std::fs;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
+use diesel::sql_types::{Integer, Text};
 use diesel::{Connection, RunQueryDsl, SqliteConnection};
-use diesel_migrations::FileBasedMigrations;
+use diesel_migrations::{embed_migrations, EmbeddedMigrations};
 use diesel_migrations::MigrationHarness;
 
+use crate::error::CyclopsError;
+use crate::models::{MergeSource, ReportScope};
+use crate::schema::{merge_source, report_scope};
+
+// Built into the binary so the migrations directory is not needed at run time
+const MIGRATIONS: EmbeddedMigrations = embed_migrations!("migrations");
+
 pub fn get_file_conn(path: PathBuf) -> SqliteConnection {
     let mut conn = SqliteConnection::establish(path.to_str().unwrap())
         .unwrap_or_else(|_| panic!("Unable to connect to database"));
@@ -15,6 +23,37 @@ pub fn get_file_conn(path: PathBuf) -> SqliteConnection {
     conn
 }
 
+// Id used in urls in place of the database path. Derived from the path inside the output directory,
+// so it stays the same between runs. Databases outside the output directory have no id.
+pub fn report_id(output_dir: &Path, db_path: &Path) -> Option<String> {
+    let output_dir = dunce::canonicalize(output_dir).ok()?;
+    let db_path = dunce::canonicalize(db_path).ok()?;
+    let relative = db_path.strip_prefix(&output_dir).ok()?;
+    let name: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+
+    // FNV-1a
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for b in name.join("/").bytes() {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    Some(format!("{:016x}", hash))
+}
+
+// A report opened from the web has to be a readable database with sessions in it,
+// so a deleted or damaged file is reported instead of failing part way through a page
+pub fn check_report(db_path: &Path) -> Result<(), CyclopsError> {
+    if !db_path.is_file() {
+        return Err(CyclopsError::NotFound(format!("The report database {:?} no longer exists", db_path)));
+    }
+    let mut conn = SqliteConnection::establish(&db_path.to_string_lossy())?;
+    diesel::sql_query("SELECT summary_key FROM summary LIMIT 1").execute(&mut conn)?;
+    Ok(())
+}
+
 pub fn establish_connection() -> SqliteConnection {
     //let database_url = "summary.db";
     //let mut conn = SqliteConnection::establish(&database_url)
@@ -22,9 +61,7 @@ pub fn establish_connection() -> SqliteConnection {
     let mut conn = SqliteConnection::establish(":memory:")
         .unwrap_or_else(|_| panic!("Unable to create in memory database"));
 
-    let migrations_harness =
-        FileBasedMigrations::find_migrations_directory().expect("Unable to find migrations dir");
-    conn.run_pending_migrations(migrations_harness)
+    conn.run_pending_migrations(MIGRATIONS)
         .expect("Unable to migrate db");
 
     diesel::sql_query("pragma foreign_keys=ON")
@@ -46,4 +83,168 @@ pub fn copy_db(conn: &mut SqliteConnection, path: PathBuf) {
     if let Err(e) = diesel::sql_query(command).execute(conn) {
         println!("Unable to copy db: {:?}", e);
     }
-}
\ No newline at end of file
+}
+// Tables holding per line data for a session
+const LINE_TABLES: [&str; 15] = [
+    "damage_action",
+    "debuff_action",
+    "defeated_targets",
+    "hit_or_miss",
+    "player_activation",
+    "player_power_recharged",
+    "reward",
+    "player_death",
+    "heal_endurance_action",
+    "control_action",
+    "level_up",
+    "consumable_activation",
+    "location_event",
+    "team_event",
+    "market_transaction",
+];
+
+// Copies a report keeping only one session and the lines inside the scope,
+// so every report can be run against part of a session
+pub fn create_scoped_copy(source: PathBuf, target: PathBuf, scope: &ReportScope, log_date: &str) {
+    let mut conn = get_file_conn(source);
+    copy_db(&mut conn, target.clone());
+
+    let mut conn = get_file_conn(target);
+    for table in LINE_TABLES {
+        let command = format!(
+            "DELETE FROM {} WHERE summary_key != ? OR line_number < ? OR line_number > ?",
+            table
+        );
+        // Tables added by later migrations are missing from older reports
+        let _ = diesel::sql_query(command)
+            .bind::<Integer, _>(scope.summary_key)
+            .bind::<Integer, _>(scope.first_line_number)
+            .bind::<Integer, _>(scope.last_line_number)
+            .execute(&mut conn);
+    }
+    let _ = diesel::sql_query("DELETE FROM benchmark_run").execute(&mut conn);
+
+    diesel::sql_query("DELETE FROM summary WHERE summary_key != ?")
+        .bind::<Integer, _>(scope.summary_key)
+        .execute(&mut conn)
+        .expect("Unable to remove other summaries");
+    diesel::sql_query("UPDATE summary SET first_line_number = ?, last_line_number = ?, log_date = ? WHERE summary_key = ?")
+        .bind::<Integer, _>(scope.first_line_number)
+        .bind::<Integer, _>(scope.last_line_number)
+        .bind::<Text, _>(log_date)
+        .bind::<Integer, _>(scope.summary_key)
+        .execute(&mut conn)
+        .expect("Unable to update scoped summary");
+
+    if let Err(e) = diesel::insert_into(report_scope::table)
+        .values(scope)
+        .execute(&mut conn)
+    {
+        println!("Unable to record report scope: {:?}", e);
+    }
+}
+
+// Copies sessions from another report into this one, keeping only the lines logged inside the time window.
+// A session whose key is already used by another log is copied under the next free key.
+// Returns the keys of the sessions copied and a message for each session that could not be copied.
+pub fn merge_sessions(
+    conn: &mut SqliteConnection,
+    source: &Path,
+    keys: &[i32],
+    window_start: &str,
+    window_end: &str,
+) -> (Vec<i32>, Vec<String>) {
+    if let Err(e) = diesel::sql_query("ATTACH DATABASE ? AS source")
+        .bind::<Text, _>(source.to_string_lossy())
+        .execute(conn)
+    {
+        return (Vec::new(), vec![format!("Unable to open for merging: {:?}", e)]);
+    }
+
+    let mut merged = Vec::new();
+    let mut skipped = Vec::new();
+    for key in keys {
+        let new_key = free_summary_key(conn, *key);
+        if new_key != *key {
+            println!("Session {} from {:?} merged as {}, the key is used by another log", key, source, new_key);
+        }
+        let result = copy_merge_rows(conn, "summary", "summary_key = ?", *key, new_key, None);
+        if let Err(e) = result {
+            skipped.push(format!("Skipped session {}: {:?}", key, e));
+            continue;
+        }
+
+        for table in LINE_TABLES {
+            let window = Some((window_start, window_end));
+            let filter = "summary_key = ? AND julianday(log_date) BETWEEN julianday(?) AND julianday(?)";
+            if let Err(e) = copy_merge_rows(conn, table, filter, *key, new_key, window) {
+                println!("Unable to merge {} from {:?}: {:?}", table, source, e);
+            }
+        }
+        merged.push(new_key);
+    }
+
+    let _ = diesel::sql_query("DETACH DATABASE source").execute(conn);
+    (merged, skipped)
+}
+
+// The key, or the first one after it not already used by a summary
+fn free_summary_key(conn: &mut SqliteConnection, key: i32) -> i32 {
+    use crate::schema::summary::dsl::*;
+    use diesel::prelude::*;
+
+    let mut free_key = key;
+    while summary
+        .filter(summary_key.eq(free_key))
+        .count()
+        .get_result::<i64>(conn)
+        .unwrap_or(0)
+        > 0
+    {
+        free_key += 1;
+    }
+    free_key
+}
+
+// Copies the rows of one session from the attached source db through a temp table, so the key can be changed
+fn copy_merge_rows(
+    conn: &mut SqliteConnection,
+    table: &str,
+    filter: &str,
+    key: i32,
+    new_key: i32,
+    window: Option<(&str, &str)>,
+) -> diesel::QueryResult<()> {
+    let _ = diesel::sql_query("DROP TABLE IF EXISTS temp.merge_rows").execute(conn);
+    let command = format!("CREATE TEMP TABLE merge_rows AS SELECT * FROM source.{} WHERE {}", table, filter);
+    match window {
+        Some((start, end)) => diesel::sql_query(command)
+            .bind::<Integer, _>(key)
+            .bind::<Text, _>(start)
+            .bind::<Text, _>(end)
+            .execute(conn)?,
+        None => diesel::sql_query(command).bind::<Integer, _>(key).execute(conn)?,
+    };
+    diesel::sql_query("UPDATE temp.merge_rows SET summary_key = ?")
+        .bind::<Integer, _>(new_key)
+        .execute(conn)?;
+    let result = diesel::sql_query(format!("INSERT INTO main.{} SELECT * FROM temp.merge_rows", table)).execute(conn);
+    let _ = diesel::sql_query("DROP TABLE IF EXISTS temp.merge_rows").execute(conn);
+    result.map(|_| ())
+}
+
+// Starts every merged session at the beginning of the window so times line up across the logs
+pub fn record_merge_sources(conn: &mut SqliteConnection, sources: &[MergeSource]) {
+    for s in sources {
+        diesel::sql_query("UPDATE summary SET log_date = ? WHERE summary_key = ? AND julianday(log_date) < julianday(?)")
+            .bind::<Text, _>(&s.window_start)
+            .bind::<Integer, _>(s.summary_key)
+            .bind::<Text, _>(&s.window_start)
+            .execute(conn)
+            .expect("Unable to align merged summary");
+    }
+    diesel::insert_into(merge_source::table)
+        .values(sources)
+        .execute(conn)
+        .expect("Unable to record merge sources");
+}
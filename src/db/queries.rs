@@ -10,7 +10,9 @@ use crate::db::get_file_conn;
 use crate::models::{
     DamageDealtByType, DamageDealtToMobByPower, DamageIntervals, DamageReportByPower, DamageTaken,
     DamageTakenByMob, DamageTakenByMobPower, DamageTakenByType, IndexDetails, RewardsDefeats,
-    Summary, TotalDamageReport, PlayerPowerRecharged, PlayerActivation, SessionStats
+    Summary, TotalDamageReport, PlayerPowerRecharged, PlayerActivation, ProcRate, SessionStats, PlayerDeath, DeathRecapDamage,
+    DeathRecapHeal, DeathRecapControl, BenchmarkReport, DamageTimeline, RewardTimeline, DefeatedTargetDamage, DamageAction, DebuffAction, LevelUp, ConsumableActivation, LocationEvent, MarketTransaction, ReportScope, Reward,
+    TeamEvent, HealEnduranceAction, DefeatedTarget, MergeSource
 };
 use crate::web::web_structs_enums::DamageByPowerQuery;
 use crate::web::web_structs_enums::PowersMobsData;
@@ -41,7 +43,8 @@ pub fn index_details(conn: &mut SqliteConnection) -> Vec<IndexDetails> {
         .expect("Unable to load index details")
 }
 
-pub fn get_total_damage(query: &DamageByPowerQuery) -> i32 {
+// None when the report has no session with the key
+pub fn get_total_damage(query: &DamageByPowerQuery) -> Option<i32> {
     use crate::schema::total_damage_report::dsl::*;
     let db_path: PathBuf = query.db_path.clone().into();
     let mut conn = get_file_conn(db_path);
@@ -52,17 +55,17 @@ pub fn get_total_damage(query: &DamageByPowerQuery) -> i32 {
         .load(&mut conn)
         .expect("Unable to load total damage report");
 
-    result.pop().unwrap()
+    result.pop()
 }
 
-pub fn get_total_damage_report(conn: &mut SqliteConnection, key: i32) -> TotalDamageReport {
+pub fn get_total_damage_report(conn: &mut SqliteConnection, key: i32) -> Option<TotalDamageReport> {
     use crate::schema::total_damage_report::dsl::*;
     let mut result: Vec<TotalDamageReport> = total_damage_report
         .filter(summary_key.eq(key))
         .load(conn)
         .expect("Unable to load total damage report");
 
-    result.pop().unwrap()
+    result.pop()
 }
 
 pub fn get_damage_taken_report(conn: &mut SqliteConnection, key: i32) -> Option<DamageTaken> {
@@ -158,6 +161,26 @@ pub fn get_damage_taken_by_mob_power_query(
     }
 }
 
+pub fn get_proc_rates_query(query: &TableQuery) -> Option<Vec<ProcRate>> {
+    use crate::schema::proc_rates::dsl::*;
+    let db_path: PathBuf = query.db_path.clone().into();
+    let mut conn = get_file_conn(db_path);
+
+    match proc_rates
+        .filter(summary_key.eq(query.key))
+        .load::<ProcRate>(&mut conn)
+    {
+        Ok(data) => {
+            if data.is_empty() {
+                None
+            } else {
+                Some(data)
+            }
+        }
+        Err(_) => None,
+    }
+}
+
 pub fn get_damage_dealt_by_power_or_mob(
     query: &PowersMobsData,
 ) -> Option<Vec<DamageDealtToMobByPower>> {
@@ -205,6 +228,19 @@ pub fn get_damage_by_power_report(query: &DamageByPowerQuery) -> Vec<DamageRepor
         .expect("Unable to load damage report by power")
 }
 
+// Average targets per activation for each power, used to tell area powers apart
+pub fn get_power_targets(query: &TableQuery) -> Vec<(String, Option<i32>)> {
+    use crate::schema::damage_report_by_power::dsl::*;
+    let db_path: PathBuf = query.db_path.clone().into();
+    let mut conn = get_file_conn(db_path);
+
+    damage_report_by_power
+        .filter(summary_key.eq(query.key))
+        .select((power_name, ate))
+        .load(&mut conn)
+        .unwrap_or_default()
+}
+
 pub fn select_damage_intervals(conn: &mut SqliteConnection) -> Vec<DamageIntervals> {
     use crate::schema::damage_intervals::dsl::*;
     damage_intervals
@@ -401,3 +437,388 @@ pub fn get_session_stats(conn: &mut SqliteConnection, key: i32) -> Option<Sessio
         Err(_) => None,
     }
 }
+
+pub fn get_player_deaths(conn: &mut SqliteConnection, key: i32) -> Vec<PlayerDeath> {
+    use crate::schema::player_death::dsl::*;
+    // Databases created before deaths were tracked have no player_death table
+    player_death
+        .filter(summary_key.eq(key))
+        .order(line_number)
+        .load(conn)
+        .unwrap_or_default()
+}
+
+pub fn get_death_count(conn: &mut SqliteConnection, key: i32) -> Option<i64> {
+    use crate::schema::player_death::dsl::*;
+    player_death
+        .filter(summary_key.eq(key))
+        .filter(event_type.eq("Defeated"))
+        .count()
+        .get_result(conn)
+        .ok()
+}
+
+pub fn get_death_recap_damage(
+    conn: &mut SqliteConnection,
+    death: &PlayerDeath,
+    seconds: i32,
+) -> Vec<DeathRecapDamage> {
+    use diesel::sql_query;
+    use diesel::sql_types::*;
+
+    let recap_query = sql_query("select da.source_name, da.power_name, count(da.power_name) as hits, sum(da.damage) as total_damage from damage_action da where da.summary_key = ? AND da.source_type IN ('Mob', 'MobPet') AND da.line_number <= ? AND julianday(da.log_date) >= julianday(?) - (? / 86400.0) group by da.source_name, da.power_name order by total_damage desc");
+    recap_query
+        .bind::<Integer, _>(death.summary_key)
+        .bind::<Integer, _>(death.line_number)
+        .bind::<Text, _>(&death.log_date)
+        .bind::<Integer, _>(seconds)
+        .load::<DeathRecapDamage>(conn)
+        .unwrap_or_default()
+}
+
+pub fn get_death_recap_heals(
+    conn: &mut SqliteConnection,
+    death: &PlayerDeath,
+    seconds: i32,
+) -> Vec<DeathRecapHeal> {
+    use diesel::sql_query;
+    use diesel::sql_types::*;
+
+    let recap_query = sql_query("select hea.source_name, hea.power_name, count(hea.power_name) as heals, sum(hea.amount) as total_healed from heal_endurance_action hea where hea.summary_key = ? AND hea.action_type = 'Heal' AND hea.target_name = 'Player' AND hea.line_number <= ? AND julianday(hea.log_date) >= julianday(?) - (? / 86400.0) group by hea.source_name, hea.power_name order by total_healed desc");
+    recap_query
+        .bind::<Integer, _>(death.summary_key)
+        .bind::<Integer, _>(death.line_number)
+        .bind::<Text, _>(&death.log_date)
+        .bind::<Integer, _>(seconds)
+        .load::<DeathRecapHeal>(conn)
+        .unwrap_or_default()
+}
+
+pub fn get_death_recap_controls(
+    conn: &mut SqliteConnection,
+    death: &PlayerDeath,
+    seconds: i32,
+) -> Vec<DeathRecapControl> {
+    use diesel::sql_query;
+    use diesel::sql_types::*;
+
+    let recap_query = sql_query("select ca.source_name, ca.power_name, ca.control_type, count(ca.power_name) as applications from control_action ca where ca.summary_key = ? AND ca.target_name = 'Player' AND ca.source_type IN ('Mob', 'MobPet') AND ca.line_number <= ? AND julianday(ca.log_date) >= julianday(?) - (? / 86400.0) group by ca.source_name, ca.power_name, ca.control_type order by applications desc");
+    recap_query
+        .bind::<Integer, _>(death.summary_key)
+        .bind::<Integer, _>(death.line_number)
+        .bind::<Text, _>(&death.log_date)
+        .bind::<Integer, _>(seconds)
+        .load::<DeathRecapControl>(conn)
+        .unwrap_or_default()
+}
+
+pub fn get_benchmark_report(conn: &mut SqliteConnection, key: i32) -> Option<BenchmarkReport> {
+    use crate::schema::benchmark_report::dsl::*;
+    match benchmark_report
+        .filter(summary_key.eq(key))
+        .load::<BenchmarkReport>(conn)
+    {
+        Ok(mut data) => data.pop(),
+        Err(_) => None,
+    }
+}
+
+pub fn get_benchmark_reports(conn: &mut SqliteConnection) -> Vec<BenchmarkReport> {
+    use crate::schema::benchmark_report::dsl::*;
+    // Databases created before benchmarks were tracked have no benchmark_report view
+    benchmark_report
+        .order(log_date)
+        .load(conn)
+        .unwrap_or_default()
+}
+
+pub fn get_defeated_target_damage(conn: &mut SqliteConnection, key: i32) -> Vec<DefeatedTargetDamage> {
+    use crate::schema::defeated_target_damage::dsl::*;
+    // Databases created before mob ranks were inferred have no defeated_target_damage view
+    defeated_target_damage
+        .filter(summary_key.eq(key))
+        .order(line_number)
+        .load(conn)
+        .unwrap_or_default()
+}
+
+pub fn get_damage_dealt_to_mobs(conn: &mut SqliteConnection, key: i32) -> Vec<DamageDealtToMobByPower> {
+    use crate::schema::damage_dealt_to_mob_by_power::dsl::*;
+    damage_dealt_to_mob_by_power
+        .filter(summary_key.eq(key))
+        .load(conn)
+        .unwrap_or_default()
+}
+
+// Critical hits are left out, they would look like a debuff
+pub fn get_outgoing_damage_ticks(conn: &mut SqliteConnection, key: i32) -> Vec<DamageAction> {
+    use crate::schema::damage_action::dsl::*;
+    damage_action
+        .filter(summary_key.eq(key))
+        .filter(source_type.eq_any(vec!["Player", "PlayerPet"]))
+        .filter(damage_mode.ne("Critical"))
+        .order(line_number)
+        .select(DamageAction::as_select())
+        .load(conn)
+        .unwrap_or_default()
+}
+
+pub fn get_debuff_actions(conn: &mut SqliteConnection, key: i32) -> Vec<DebuffAction> {
+    use crate::schema::debuff_action::dsl::*;
+    debuff_action
+        .filter(summary_key.eq(key))
+        .order(line_number)
+        .select(DebuffAction::as_select())
+        .load(conn)
+        .unwrap_or_default()
+}
+
+pub fn get_level_ups(conn: &mut SqliteConnection, key: i32) -> Vec<LevelUp> {
+    use crate::schema::level_up::dsl::*;
+    level_up
+        .filter(summary_key.eq(key))
+        .order(line_number)
+        .select(LevelUp::as_select())
+        .load(conn)
+        .unwrap_or_default()
+}
+
+pub fn get_market_transactions(conn: &mut SqliteConnection, key: i32) -> Vec<MarketTransaction> {
+    use crate::schema::market_transaction::dsl::*;
+    market_transaction
+        .filter(summary_key.eq(key))
+        .order(line_number)
+        .select(MarketTransaction::as_select())
+        .load(conn)
+        .unwrap_or_default()
+}
+
+pub fn get_consumable_activations(conn: &mut SqliteConnection, key: i32) -> Vec<ConsumableActivation> {
+    use crate::schema::consumable_activation::dsl::*;
+    consumable_activation
+        .filter(summary_key.eq(key))
+        .order(line_number)
+        .select(ConsumableActivation::as_select())
+        .load(conn)
+        .unwrap_or_default()
+}
+
+pub fn get_incoming_damage_actions(conn: &mut SqliteConnection, key: i32) -> Vec<DamageAction> {
+    use crate::schema::damage_action::dsl::*;
+    damage_action
+        .filter(summary_key.eq(key))
+        .filter(source_type.eq_any(vec!["Mob", "MobPet"]))
+        .order(line_number)
+        .select(DamageAction::as_select())
+        .load(conn)
+        .unwrap_or_default()
+}
+
+pub fn get_location_events(conn: &mut SqliteConnection, key: i32) -> Vec<LocationEvent> {
+    use crate::schema::location_event::dsl::*;
+    location_event
+        .filter(summary_key.eq(key))
+        .order(line_number)
+        .select(LocationEvent::as_select())
+        .load(conn)
+        .unwrap_or_default()
+}
+
+pub fn get_rewards(conn: &mut SqliteConnection, key: i32) -> Vec<Reward> {
+    use crate::schema::reward::dsl::*;
+    reward
+        .filter(summary_key.eq(key))
+        .order(line_number)
+        .select(Reward::as_select())
+        .load(conn)
+        .unwrap_or_default()
+}
+
+pub fn get_player_defeat_lines(conn: &mut SqliteConnection, key: i32, player_name: &str) -> Vec<i32> {
+    use crate::schema::defeated_targets::dsl::*;
+    defeated_targets
+        .filter(summary_key.eq(key))
+        .filter(source_name.eq(player_name))
+        .order(line_number)
+        .select(line_number)
+        .load(conn)
+        .unwrap_or_default()
+}
+
+pub fn get_team_events(conn: &mut SqliteConnection, key: i32) -> Vec<TeamEvent> {
+    use crate::schema::team_event::dsl::*;
+    team_event
+        .filter(summary_key.eq(key))
+        .order(line_number)
+        .select(TeamEvent::as_select())
+        .load(conn)
+        .unwrap_or_default()
+}
+
+pub fn get_heals_endurance_received(conn: &mut SqliteConnection, key: i32) -> Vec<HealEnduranceAction> {
+    use crate::schema::heal_endurance_action::dsl::*;
+    heal_endurance_action
+        .filter(summary_key.eq(key))
+        .filter(target_name.eq("Player"))
+        .order(line_number)
+        .select(HealEnduranceAction::as_select())
+        .load(conn)
+        .unwrap_or_default()
+}
+
+pub fn get_other_defeats(conn: &mut SqliteConnection, key: i32, player_name: &str) -> Vec<DefeatedTarget> {
+    use crate::schema::defeated_targets::dsl::*;
+    defeated_targets
+        .filter(summary_key.eq(key))
+        .filter(source_name.ne(player_name))
+        .order(line_number)
+        .select(DefeatedTarget::as_select())
+        .load(conn)
+        .unwrap_or_default()
+}
+
+// Names that damaged or controlled the player
+pub fn get_hostile_names(conn: &mut SqliteConnection, key: i32) -> Vec<String> {
+    let mut names: Vec<String> = {
+        use crate::schema::damage_action::dsl::*;
+        damage_action
+            .filter(summary_key.eq(key))
+            .filter(source_type.eq_any(vec!["Mob", "MobPet"]))
+            .select(source_name)
+            .distinct()
+            .load(conn)
+            .unwrap_or_default()
+    };
+    let controls: Vec<String> = {
+        use crate::schema::control_action::dsl::*;
+        control_action
+            .filter(summary_key.eq(key))
+            .filter(source_type.eq_any(vec!["Mob", "MobPet"]))
+            .select(source_name)
+            .distinct()
+            .load(conn)
+            .unwrap_or_default()
+    };
+    names.extend(controls);
+    names.sort();
+    names.dedup();
+    names
+}
+
+pub fn get_player_pet_names(conn: &mut SqliteConnection, key: i32) -> Vec<String> {
+    use crate::schema::damage_action::dsl::*;
+    damage_action
+        .filter(summary_key.eq(key))
+        .filter(source_type.eq("PlayerPet"))
+        .select(source_name)
+        .distinct()
+        .load(conn)
+        .unwrap_or_default()
+}
+
+pub fn get_merge_sources(conn: &mut SqliteConnection) -> Vec<MergeSource> {
+    use crate::schema::merge_source::dsl::*;
+    merge_source
+        .order(summary_key)
+        .select(MergeSource::as_select())
+        .load(conn)
+        .unwrap_or_default()
+}
+
+// Defeats from every session in the report
+pub fn get_all_defeats(conn: &mut SqliteConnection) -> Vec<DefeatedTarget> {
+    use crate::schema::defeated_targets::dsl::*;
+    defeated_targets
+        .order(log_date)
+        .select(DefeatedTarget::as_select())
+        .load(conn)
+        .unwrap_or_default()
+}
+
+// Heals and endurance from every session in the report
+pub fn get_all_heals_endurance(conn: &mut SqliteConnection) -> Vec<HealEnduranceAction> {
+    use crate::schema::heal_endurance_action::dsl::*;
+    heal_endurance_action
+        .order(log_date)
+        .select(HealEnduranceAction::as_select())
+        .load(conn)
+        .unwrap_or_default()
+}
+
+pub fn get_player_damage_total(conn: &mut SqliteConnection, key: i32) -> i64 {
+    use crate::schema::damage_action::dsl::*;
+    damage_action
+        .filter(summary_key.eq(key))
+        .filter(source_type.eq_any(vec!["Player", "PlayerPet"]))
+        .select(diesel::dsl::sum(damage))
+        .first::<Option<i64>>(conn)
+        .ok()
+        .flatten()
+        .unwrap_or(0)
+}
+
+pub fn get_report_scope(conn: &mut SqliteConnection, key: i32) -> Option<ReportScope> {
+    use crate::schema::report_scope::dsl::*;
+    report_scope
+        .filter(summary_key.eq(key))
+        .select(ReportScope::as_select())
+        .first(conn)
+        .ok()
+}
+
+pub fn get_damage_timeline(
+    conn: &mut SqliteConnection,
+    key: i32,
+    bucket_seconds: i32,
+) -> Vec<DamageTimeline> {
+    use diesel::sql_query;
+    use diesel::sql_types::*;
+
+    let timeline_query = sql_query("select CAST(MAX(0, (julianday(da.log_date) - julianday(s.log_date)) * 86400) AS INTEGER) / ? as bucket, da.damage_type, CASE WHEN da.source_type IN ('Player', 'PlayerPet') THEN 1 ELSE 0 END as outgoing, CAST(sum(da.damage) AS INTEGER) as damage from damage_action da inner join summary s on s.summary_key = da.summary_key where da.summary_key = ? group by bucket, da.damage_type, outgoing order by bucket");
+    timeline_query
+        .bind::<Integer, _>(bucket_seconds)
+        .bind::<Integer, _>(key)
+        .load::<DamageTimeline>(conn)
+        .unwrap_or_default()
+}
+
+pub fn get_reward_timeline(conn: &mut SqliteConnection, key: i32) -> Vec<RewardTimeline> {
+    use diesel::sql_query;
+    use diesel::sql_types::*;
+
+    let timeline_query = sql_query("select CAST(MAX(0, (julianday(r.log_date) - julianday(s.log_date)) * 86400) AS INTEGER) as elapsed, COALESCE(r.experience, 0) as experience, COALESCE(r.influence, 0) as influence from reward r inner join summary s on s.summary_key = r.summary_key where r.summary_key = ? AND r.reward_type = 'ExpAndInf' order by r.line_number");
+    timeline_query
+        .bind::<Integer, _>(key)
+        .load::<RewardTimeline>(conn)
+        .unwrap_or_default()
+}
+
+// Log date of the closest recorded action at or before a line, used to timestamp bookmarks
+pub fn get_log_date_at_line(conn: &mut SqliteConnection, key: i32, line: i32) -> Option<String> {
+    let damage: Option<(i32, String)> = {
+        use crate::schema::damage_action::dsl::*;
+        damage_action
+            .filter(summary_key.eq(key))
+            .filter(line_number.le(line))
+            .order(line_number.desc())
+            .select((line_number, log_date))
+            .first(conn)
+            .ok()
+    };
+    let activation: Option<(i32, String)> = {
+        use crate::schema::player_activation::dsl::*;
+        player_activation
+            .filter(summary_key.eq(key))
+            .filter(line_number.le(line))
+            .order(line_number.desc())
+            .select((line_number, log_date))
+            .first(conn)
+            .ok()
+    };
+
+    damage
+        .into_iter()
+        .chain(activation)
+        .max_by_key(|(l, _)| *l)
+        .map(|(_, date)| date)
+}
@@ -0,0 +1,83 @@
+use std::fmt;
+
+use actix_web::{http::StatusCode, HttpResponse, ResponseError};
+
+// Everything a web request can fail with. The status code comes from the kind of error,
+// the message is shown on the error page so it should make sense to a player.
+#[derive(Debug)]
+pub enum CyclopsError {
+    // The query string is malformed or missing a value
+    BadRequest(String),
+    // No report, session or job matches the request
+    NotFound(String),
+    // The report database is missing tables or could not be read
+    Database(String),
+    Template(tera::Error),
+}
+
+impl fmt::Display for CyclopsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CyclopsError::BadRequest(message) => write!(f, "Invalid request. {}", message),
+            CyclopsError::NotFound(message) => write!(f, "{}", message),
+            CyclopsError::Database(message) => write!(f, "Unable to read the report. {}", message),
+            // The template name and the line are in the source errors
+            CyclopsError::Template(e) => {
+                write!(f, "Unable to render the page. {}", e)?;
+                let mut source = std::error::Error::source(e);
+                while let Some(s) = source {
+                    write!(f, " {}", s)?;
+                    source = s.source();
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl std::error::Error for CyclopsError {}
+
+impl ResponseError for CyclopsError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            CyclopsError::BadRequest(_) => StatusCode::BAD_REQUEST,
+            CyclopsError::NotFound(_) => StatusCode::NOT_FOUND,
+            CyclopsError::Database(_) | CyclopsError::Template(_) => StatusCode::INTERNAL_SERVER_ERROR,
+        }
+    }
+
+    // Plain text, the web server swaps in the error page
+    fn error_response(&self) -> HttpResponse {
+        HttpResponse::build(self.status_code()).body(self.to_string())
+    }
+}
+
+impl From<tera::Error> for CyclopsError {
+    fn from(e: tera::Error) -> Self {
+        CyclopsError::Template(e)
+    }
+}
+
+impl From<actix_web::error::QueryPayloadError> for CyclopsError {
+    fn from(e: actix_web::error::QueryPayloadError) -> Self {
+        CyclopsError::BadRequest(e.to_string())
+    }
+}
+
+impl From<serde_qs::Error> for CyclopsError {
+    fn from(e: serde_qs::Error) -> Self {
+        CyclopsError::BadRequest(e.to_string())
+    }
+}
+
+impl From<diesel::result::Error> for CyclopsError {
+    fn from(e: diesel::result::Error) -> Self {
+        CyclopsError::Database(e.to_string())
+    }
+}
+
+impl From<diesel::ConnectionError> for CyclopsError {
+    fn from(e: diesel::ConnectionError) -> Self {
+        CyclopsError::Database(e.to_string())
+    }
+}
@@ -1,18 +1,53 @@
 use core::fmt;
 
 use lazy_static::lazy_static;
-use serde::{Deserialize, Serialize};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use utoipa::ToSchema;
 
+use crate::assets;
 use crate::log_processing::parser_model::DamageType;
 
 lazy_static! {
     pub static ref MINION_HP_TABLE: Vec<MobHP> = initialize_mob_hp_tables(MobClass::Minion);
+    pub static ref LIEUTENANT_HP_TABLE: Vec<MobHP> = initialize_mob_hp_tables(MobClass::Lieutenant);
+    pub static ref BOSS_HP_TABLE: Vec<MobHP> = initialize_mob_hp_tables(MobClass::Boss);
+    pub static ref ELITE_BOSS_HP_TABLE: Vec<MobHP> = initialize_mob_hp_tables(MobClass::EliteBoss);
+    pub static ref ARCHVILLIAN_HP_TABLE: Vec<MobHP> = initialize_mob_hp_tables(MobClass::Archvillian);
+    pub static ref HERO_HP_TABLE: Vec<MobHP> = initialize_mob_hp_tables(MobClass::Hero);
+    pub static ref MOB_RANK_OVERRIDES: Vec<MobRankOverride> = initialize_mob_rank_overrides();
     pub static ref NAME_NORMALIZATION_TABLE: Vec<NameNormalization> = initialize_name_normalization_table();
     pub static ref PSEUDO_PETS_TABLE: Vec<PseudoPets> = initialize_pseudo_pet_table();
     pub static ref SIM_HIT_POWERS: Vec<SimHitPower> = initialize_sim_hit_powers();
+    pub static ref PROC_TABLE: Vec<ProcInfo> = initialize_proc_table();
+    pub static ref CONSUMABLES_TABLE: Vec<Consumable> = initialize_consumables_table();
 }
 
-#[derive(Debug)]
+// Powers that bring the player back after a defeat
+pub const SELF_RESURRECT_POWERS: [&str; 7] = [
+    "Awaken",
+    "Bounce Back",
+    "Restoration",
+    "Resurgence",
+    "Rise of the Phoenix",
+    "Soul Transfer",
+    "Revive",
+];
+
+// Level used when the mob level is not known, matches the level selectors default
+pub const DEFAULT_MOB_LEVEL: i32 = 54;
+
+// The mob level from a query, the default when it's missing or not a number
+pub fn mob_level(level: &Option<String>) -> i32 {
+    match level {
+        Some(level) => level.parse::<i32>().unwrap_or(DEFAULT_MOB_LEVEL),
+        None => DEFAULT_MOB_LEVEL,
+    }
+}
+
+// Allowance for regeneration and missed log lines when inferring a mob's rank
+const RANK_HP_TOLERANCE: f32 = 1.1;
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, ToSchema)]
 pub enum MobClass {
     Minion,
     Lieutenant,
@@ -39,6 +74,16 @@ impl fmt::Display for MobClass {
 pub struct MobHP {
     pub level: i32,
     pub hp: i32,
+    // Rows not taken from the game's data, the elite boss, archvillian and hero tables
+    #[serde(default)]
+    pub estimated: bool,
+}
+
+#[derive(Deserialize, Serialize, Debug)]
+pub struct MobRankOverride {
+    pub mob_name: String,
+    pub mob_class: MobClass,
+    pub level: Option<i32>,
 }
 
 #[derive(Deserialize, Serialize, Debug)]
@@ -60,39 +105,177 @@ pub struct SimHitPower {
     pub damage_type: DamageType,
 }
 
-pub fn initialize_mob_hp_tables(mob_class: MobClass) -> Vec<MobHP> {
-    csv::Reader::from_path(format!(".\\resources\\{}_hp_table.csv", mob_class))
-        .unwrap()
+#[derive(Deserialize, Serialize, Debug)]
+pub struct ProcInfo {
+    pub proc_name: String,
+    pub ppm: Option<f32>,
+}
+
+#[derive(Deserialize, Serialize, Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConsumableType {
+    Inspiration,
+    TempPower,
+    Incarnate,
+}
+
+// power_name is a SQL LIKE pattern matched against activations after name normalization
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Consumable {
+    pub power_name: String,
+    pub consumable_type: ConsumableType,
+}
+
+// Data tables are built into the binary, see assets
+fn load_csv<T: DeserializeOwned>(name: &str) -> Vec<T> {
+    let data = assets::resource(name).unwrap_or_else(|| panic!("Missing resource: {}", name));
+    csv::Reader::from_reader(data.as_ref())
         .deserialize()
         .map(|r| r.unwrap())
         .collect()
 }
 
-pub fn get_mob_hp(level: &String) -> i32 {
-    let l = i32::from_str_radix(level, 10).unwrap();
-    MINION_HP_TABLE.iter().find(|d| l == d.level).unwrap().hp
+pub fn initialize_mob_hp_tables(mob_class: MobClass) -> Vec<MobHP> {
+    load_csv(&format!("{}_hp_table.csv", mob_class))
+}
+
+pub fn hp_table(mob_class: MobClass) -> &'static Vec<MobHP> {
+    match mob_class {
+        MobClass::Minion => &MINION_HP_TABLE,
+        MobClass::Lieutenant => &LIEUTENANT_HP_TABLE,
+        MobClass::Boss => &BOSS_HP_TABLE,
+        MobClass::EliteBoss => &ELITE_BOSS_HP_TABLE,
+        MobClass::Archvillian => &ARCHVILLIAN_HP_TABLE,
+        MobClass::Hero => &HERO_HP_TABLE,
+    }
+}
+
+// Levels outside of the table use the closest level in the table
+pub fn find_mob_hp(mob_class: MobClass, level: i32) -> Option<&'static MobHP> {
+    hp_table(mob_class)
+        .iter()
+        .min_by_key(|d| (d.level - level).abs())
+}
+
+pub fn get_mob_class_hp(mob_class: MobClass, level: i32) -> i32 {
+    find_mob_hp(mob_class, level).map_or(1, |d| d.hp)
+}
+
+// Heroes share the archvillian numbers, they are only seen in villain content
+// so they are never inferred and must come from the overrides file
+const INFERRED_CLASSES: [MobClass; 5] = [
+    MobClass::Minion,
+    MobClass::Lieutenant,
+    MobClass::Boss,
+    MobClass::EliteBoss,
+    MobClass::Archvillian,
+];
+
+// The damage a target took before the killing blow is a lower bound of its HP,
+// the smallest rank with more HP than that is the best guess.
+// A single hit kill has no lower bound and is treated as a minion.
+pub fn infer_mob_class(damage_before_final_blow: i32, level: i32) -> MobClass {
+    INFERRED_CLASSES
+        .iter()
+        .find(|c| {
+            get_mob_class_hp(**c, level) as f32 * RANK_HP_TOLERANCE
+                > damage_before_final_blow as f32
+        })
+        .copied()
+        .unwrap_or(MobClass::Archvillian)
+}
+
+pub fn initialize_mob_rank_overrides() -> Vec<MobRankOverride> {
+    load_csv("mob_rank_overrides.csv")
+}
+
+pub fn find_mob_rank_override(mob_name: &str) -> Option<&'static MobRankOverride> {
+    MOB_RANK_OVERRIDES
+        .iter()
+        .find(|o| o.mob_name.eq_ignore_ascii_case(mob_name))
 }
 
 pub fn initialize_name_normalization_table() -> Vec<NameNormalization> {
-    csv::Reader::from_path(".\\resources\\normalization.csv")
-        .unwrap()
-        .deserialize()
-        .map(|r| r.unwrap())
-        .collect()
+    load_csv("normalization.csv")
 }
 
 pub fn initialize_pseudo_pet_table() -> Vec<PseudoPets> {
-    csv::Reader::from_path(".\\resources\\pseudo_pets.csv")
-        .unwrap()
-        .deserialize()
-        .map(|r| r.unwrap())
-        .collect()
+    load_csv("pseudo_pets.csv")
 }
 
 pub fn initialize_sim_hit_powers() -> Vec<SimHitPower> {
-    csv::Reader::from_path(".\\resources\\sim_hit_powers.csv")
-        .unwrap()
-        .deserialize()
-        .map(|r| r.unwrap())
-        .collect()
-}
\ No newline at end of file
+    load_csv("sim_hit_powers.csv")
+}
+pub fn initialize_proc_table() -> Vec<ProcInfo> {
+    load_csv("procs.csv")
+}
+
+// The longest matching entry wins so a superior proc
+// is preferred over the regular one it contains
+pub fn find_proc(power_name: &str) -> Option<&'static ProcInfo> {
+    PROC_TABLE
+        .iter()
+        .filter(|p| power_name.contains(&p.proc_name))
+        .max_by_key(|p| p.proc_name.len())
+}
+
+pub fn is_proc(power_name: &str) -> bool {
+    find_proc(power_name).is_some()
+}
+
+// PPM chance per activation for a single target power, capped at 90%.
+// The logs have no cast time so only the observed recharge is used, which reads a little low.
+// Area powers also divide by an area factor from their radius and arc, so callers skip them.
+pub fn expected_proc_chance(ppm: f32, recharge_seconds: i32) -> f32 {
+    (ppm * recharge_seconds as f32 / 60.0 * 100.0).min(90.0)
+}
+
+pub fn initialize_consumables_table() -> Vec<Consumable> {
+    load_csv("consumables.csv")
+}
+
+pub fn is_self_resurrect(power_name: &str) -> bool {
+    SELF_RESURRECT_POWERS.contains(&power_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unlisted_procs_match_the_generic_rows() {
+        let proc_info = find_proc("Unlisted Set: Chance for Fire Damage").unwrap();
+        assert_eq!(proc_info.proc_name, "Chance for");
+        assert_eq!(proc_info.ppm, None);
+        assert!(is_proc("Sting of the Manticore: Chance of Damage(Toxic)"));
+        assert!(is_proc("Radial Degenerative Interface"));
+        assert!(!is_proc("Fire Blast"));
+    }
+
+    #[test]
+    fn mob_class_changes_at_the_hp_tolerance() {
+        let level = 50;
+        let limit = get_mob_class_hp(MobClass::Minion, level) as f32 * RANK_HP_TOLERANCE;
+        let last_minion = limit.ceil() as i32 - 1;
+        assert_eq!(infer_mob_class(last_minion, level), MobClass::Minion);
+        assert_eq!(infer_mob_class(last_minion + 1, level), MobClass::Lieutenant);
+
+        let limit = get_mob_class_hp(MobClass::Boss, level) as f32 * RANK_HP_TOLERANCE;
+        assert_eq!(infer_mob_class(limit.ceil() as i32 - 1, level), MobClass::Boss);
+        assert_eq!(infer_mob_class(limit.ceil() as i32, level), MobClass::EliteBoss);
+    }
+
+    #[test]
+    fn only_the_invented_hp_rows_are_estimated() {
+        assert!(!find_mob_hp(MobClass::Boss, 50).unwrap().estimated);
+        assert!(find_mob_hp(MobClass::EliteBoss, 50).unwrap().estimated);
+        assert!(find_mob_hp(MobClass::Archvillian, 50).unwrap().estimated);
+        assert!(find_mob_hp(MobClass::Hero, 50).unwrap().estimated);
+    }
+
+    #[test]
+    fn listed_procs_win_over_the_generic_rows() {
+        let proc_info = find_proc("Superior Spider's Bite: Chance for Toxic Damage").unwrap();
+        assert_eq!(proc_info.proc_name, "Superior Spider's Bite: Chance for Toxic Damage");
+        assert_eq!(proc_info.ppm, Some(4.5));
+    }
+}
@@ -0,0 +1,227 @@
+use std::{
+    collections::VecDeque,
+    panic::{self, AssertUnwindSafe},
+    path::PathBuf,
+    sync::{Condvar, Mutex},
+    thread,
+};
+
+use chrono::Local;
+use lazy_static::lazy_static;
+use serde::Serialize;
+
+use crate::AppContext;
+
+use super::{ParserJob, ProcessingError};
+
+// Finished and cancelled jobs kept for the jobs page
+const FINISHED_JOBS_KEPT: usize = 20;
+
+lazy_static! {
+    static ref JOB_QUEUE: Mutex<JobQueue> = Mutex::new(JobQueue {
+        next_id: 1,
+        pending: VecDeque::new(),
+        statuses: Vec::new(),
+    });
+    static ref JOB_ADDED: Condvar = Condvar::new();
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq)]
+pub enum JobState {
+    Queued,
+    Running,
+    Cancelling,
+    Cancelled,
+    Finished,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobProgress {
+    pub files_done: usize,
+    pub current_file: String,
+    pub lines_per_second: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+    pub id: usize,
+    pub state: JobState,
+    pub description: String,
+    pub queued_date: String,
+    pub files: Vec<PathBuf>,
+    pub merge: bool,
+    pub progress: JobProgress,
+    pub result: Option<ParserJob>,
+}
+
+impl JobStatus {
+    pub fn is_done(&self) -> bool {
+        matches!(self.state, JobState::Cancelled | JobState::Finished)
+    }
+}
+
+struct JobQueue {
+    next_id: usize,
+    pending: VecDeque<(usize, ParserJob)>,
+    statuses: Vec<JobStatus>,
+}
+
+impl JobQueue {
+    fn status_mut(&mut self, id: usize) -> Option<&mut JobStatus> {
+        self.statuses.iter_mut().find(|s| s.id == id)
+    }
+
+    // Drop the oldest finished jobs past the limit, queued and running jobs are always kept
+    fn prune(&mut self) {
+        let mut done = self.statuses.iter().filter(|s| s.is_done()).count();
+        self.statuses.retain(|s| {
+            if done > FINISHED_JOBS_KEPT && s.is_done() {
+                done -= 1;
+                false
+            } else {
+                true
+            }
+        });
+    }
+}
+
+// Queues the job and returns its id. The same files already waiting or running are not queued twice.
+pub fn add_job(job: ParserJob) -> usize {
+    let mut queue = JOB_QUEUE.lock().unwrap();
+
+    let existing = queue.statuses.iter().find(|s| {
+        matches!(s.state, JobState::Queued | JobState::Running)
+            && s.files == job.files
+            && s.merge == job.merge
+    });
+    if let Some(status) = existing {
+        return status.id;
+    }
+
+    let id = queue.next_id;
+    queue.next_id += 1;
+    queue.statuses.push(JobStatus {
+        id,
+        state: JobState::Queued,
+        description: describe(&job),
+        queued_date: format!("{}", Local::now().format("%a %b %e %T %Y")),
+        files: job.files.clone(),
+        merge: job.merge,
+        progress: JobProgress {
+            files_done: 0,
+            current_file: String::new(),
+            lines_per_second: 0,
+        },
+        result: None,
+    });
+    queue.pending.push_back((id, job));
+    JOB_ADDED.notify_one();
+
+    id
+}
+
+fn describe(job: &ParserJob) -> String {
+    let first = job
+        .files
+        .first()
+        .map(|f| f.to_string_lossy().to_string())
+        .unwrap_or_default();
+    match (job.merge, job.files.len()) {
+        (true, count) => format!("Merge {} logs starting with {}", count, first),
+        (false, 1) => first,
+        (false, count) => format!("{} logs starting with {}", count, first),
+    }
+}
+
+pub fn get_status(id: usize) -> Option<JobStatus> {
+    let queue = JOB_QUEUE.lock().unwrap();
+    queue.statuses.iter().find(|s| s.id == id).cloned()
+}
+
+// Newest first
+pub fn get_statuses() -> Vec<JobStatus> {
+    let queue = JOB_QUEUE.lock().unwrap();
+    queue.statuses.iter().rev().cloned().collect()
+}
+
+// A queued job is dropped, a running job stops before its next file
+pub fn cancel_job(id: usize) -> bool {
+    let mut queue = JOB_QUEUE.lock().unwrap();
+
+    if let Some(index) = queue.pending.iter().position(|(pending_id, _)| *pending_id == id) {
+        queue.pending.remove(index);
+        if let Some(status) = queue.status_mut(id) {
+            status.state = JobState::Cancelled;
+        }
+        queue.prune();
+        return true;
+    }
+    match queue.status_mut(id) {
+        Some(status) if status.state == JobState::Running => {
+            status.state = JobState::Cancelling;
+            true
+        }
+        _ => false,
+    }
+}
+
+// Parses queued jobs one at a time, in the order they were added
+pub fn start_worker(context: AppContext) {
+    let _ = thread::spawn(move || loop {
+        let (id, job) = next_job();
+        let queued = job.clone();
+        let result = panic::catch_unwind(AssertUnwindSafe(|| {
+            job.process_logs_with_progress(&context, |progress| update_progress(id, progress))
+        }));
+        // A panic while parsing one job should not stop the jobs queued after it
+        let result = result.unwrap_or_else(|_| {
+            let mut failed = queued;
+            let file_name = get_status(id)
+                .map(|s| PathBuf::from(s.progress.current_file))
+                .unwrap_or_default();
+            failed.errors.push(ProcessingError {
+                file_name,
+                message: String::from("Parsing stopped unexpectedly, check the console for details"),
+            });
+            failed
+        });
+        finish_job(id, result);
+    });
+}
+
+fn next_job() -> (usize, ParserJob) {
+    let mut queue = JOB_QUEUE.lock().unwrap();
+    loop {
+        if let Some((id, job)) = queue.pending.pop_front() {
+            if let Some(status) = queue.status_mut(id) {
+                status.state = JobState::Running;
+            }
+            return (id, job);
+        }
+        queue = JOB_ADDED.wait(queue).unwrap();
+    }
+}
+
+fn update_progress(id: usize, progress: &JobProgress) -> bool {
+    let mut queue = JOB_QUEUE.lock().unwrap();
+    match queue.status_mut(id) {
+        Some(status) => {
+            status.progress = progress.clone();
+            status.state != JobState::Cancelling
+        }
+        None => false,
+    }
+}
+
+fn finish_job(id: usize, result: ParserJob) {
+    let mut queue = JOB_QUEUE.lock().unwrap();
+    if let Some(status) = queue.status_mut(id) {
+        status.state = if result.cancelled {
+            JobState::Cancelled
+        } else {
+            JobState::Finished
+        };
+        status.result = Some(result);
+    }
+    queue.prune();
+}
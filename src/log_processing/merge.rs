@@ -0,0 +1,116 @@
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, FixedOffset};
+
+use crate::{db, models::MergeSource, AppContext};
+
+use super::{create_dir, ProcessingError};
+
+const MERGED_DB: &str = "summary.db";
+
+// A session from one of the logs and the time it covers
+struct SessionRange {
+    summary_key: i32,
+    start: DateTime<FixedOffset>,
+    end: DateTime<FixedOffset>,
+}
+
+// Combines the reports of logs covering the same time into one team report.
+// Only the time covered by every log is kept so the characters can be compared directly.
+// Sessions that could not be copied are added to errors, the rest are still merged.
+pub fn merge_reports(
+    context: &AppContext,
+    report_dbs: &[PathBuf],
+    errors: &mut Vec<ProcessingError>,
+) -> Result<PathBuf, String> {
+    if report_dbs.len() < 2 {
+        return Err(String::from("At least two logs are needed for a merge"));
+    }
+
+    let sessions: Vec<(&PathBuf, Vec<SessionRange>)> = report_dbs
+        .iter()
+        .map(|db_path| (db_path, load_sessions(db_path)))
+        .collect();
+
+    let mut window: Option<(DateTime<FixedOffset>, DateTime<FixedOffset>)> = None;
+    for (db_path, ranges) in &sessions {
+        let start = ranges.iter().map(|r| r.start).min();
+        let end = ranges.iter().map(|r| r.end).max();
+        let (start, end) = match (start, end) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return Err(format!("No sessions found in {:?}", db_path)),
+        };
+        window = Some(match window {
+            Some((window_start, window_end)) => (window_start.max(start), window_end.min(end)),
+            None => (start, end),
+        });
+    }
+    let (window_start, window_end) = match window {
+        Some((start, end)) if start < end => (start, end),
+        _ => return Err(String::from("The logs do not cover the same time period")),
+    };
+
+    let conn = &mut db::establish_connection();
+    let mut merge_sources = Vec::new();
+    for (db_path, ranges) in &sessions {
+        let keys: Vec<i32> = ranges
+            .iter()
+            .filter(|r| r.start <= window_end && r.end >= window_start)
+            .map(|r| r.summary_key)
+            .collect();
+        let (merged, skipped) = db::merge_sessions(
+            conn,
+            db_path,
+            &keys,
+            &window_start.to_rfc3339(),
+            &window_end.to_rfc3339(),
+        );
+        for message in skipped {
+            errors.push(ProcessingError {
+                file_name: db_path.to_path_buf(),
+                message,
+            });
+        }
+        for key in merged {
+            merge_sources.push(MergeSource {
+                summary_key: key,
+                source_db: db_path.to_string_lossy().to_string(),
+                window_start: window_start.to_rfc3339(),
+                window_end: window_end.to_rfc3339(),
+            });
+        }
+    }
+    db::record_merge_sources(conn, &merge_sources);
+
+    let report_dir = merged_report_dir(context, &window_start);
+    create_dir(&report_dir);
+    let merged_db = report_dir.join(MERGED_DB);
+    db::copy_db(conn, merged_db.clone());
+    println!("Merged report: {:?}", merged_db);
+    Ok(merged_db)
+}
+
+fn load_sessions(db_path: &Path) -> Vec<SessionRange> {
+    let mut conn = db::get_file_conn(db_path.to_path_buf());
+    db::queries::get_summaries(&mut conn)
+        .iter()
+        .filter_map(|s| {
+            let start = DateTime::parse_from_rfc3339(&s.log_date).ok()?;
+            let end = db::queries::get_log_date_at_line(&mut conn, s.summary_key, s.last_line_number)
+                .and_then(|d| DateTime::parse_from_rfc3339(&d).ok())
+                .unwrap_or(start);
+            Some(SessionRange {
+                summary_key: s.summary_key,
+                start,
+                end,
+            })
+        })
+        .collect()
+}
+
+fn merged_report_dir(context: &AppContext, window_start: &DateTime<FixedOffset>) -> PathBuf {
+    let dir_name = format!("merged_{}", window_start.format("%Y_%m_%d_%H_%M_%S"));
+    [&context.working_dir, &context.output_dir, &PathBuf::from(dir_name)]
+        .iter()
+        .collect()
+}
@@ -2,16 +2,13 @@ use std::{
     fmt,
     fs::{self, File},
     io::{BufRead, BufReader, BufWriter, LineWriter, Lines, Write},
-    mem,
     path::{Path, PathBuf},
-    sync::Mutex,
-    thread::{self},
     time::Instant,
 };
 
 use chrono::Local;
 use diesel::SqliteConnection;
-use lazy_static::lazy_static;
+use jobs::JobProgress;
 use parser_model::FileDataPoint;
 use serde::{Deserialize, Serialize};
 
@@ -21,16 +18,14 @@ use crate::{
         event_processing::{write_to_database, write_to_monitor},
     },
     models::Summary,
-    monitoring, AppContext,
+    monitoring, web::export, AppContext,
 };
 
+pub mod jobs;
+mod merge;
 pub mod parser_model;
 mod parsers;
 
-lazy_static! {
-    static ref PARSER_JOB_QUEUE: Mutex<Option<ParserJob>> = Mutex::new(None);
-}
-
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ProcessingError {
     pub file_name: PathBuf,
@@ -51,27 +46,45 @@ pub struct ParserJob {
     pub run_time: u64,
     pub last_file: String,
     pub errors: Vec<ProcessingError>,
-}
-
-pub fn add_job(job: ParserJob) {
-    let mut queue = PARSER_JOB_QUEUE.lock().unwrap();
-
-    let _ = mem::replace(&mut *queue, Some(job));
-}
-
-pub fn get_job() -> Option<ParserJob> {
-    let mut queue = PARSER_JOB_QUEUE.lock().unwrap();
-
-    let job_option = mem::replace(&mut *queue, None);
-
-    job_option
+    // Combine the reports into one team report once every file is parsed
+    #[serde(default)]
+    pub merge: bool,
+    pub merged_report: Option<PathBuf>,
+    // Report id of the merged report for links to the team report
+    #[serde(default)]
+    pub merged_report_id: Option<String>,
+    // Stopped from the jobs page before every file was parsed
+    #[serde(default)]
+    pub cancelled: bool,
 }
 
 impl ParserJob {
-    pub fn process_logs(mut self, context: &AppContext) -> Self {
+    pub fn process_logs(self, context: &AppContext) -> Self {
+        self.process_logs_with_progress(context, |_| true)
+    }
+
+    // on_progress is called before each file and once all files are done, returning false stops the job
+    pub fn process_logs_with_progress<F>(mut self, context: &AppContext, mut on_progress: F) -> Self
+    where
+        F: FnMut(&JobProgress) -> bool,
+    {
         let start = Instant::now();
+        let mut report_dbs: Vec<PathBuf> = Vec::new();
+        let mut line_total: u64 = 0;
+        let mut files_done: usize = 0;
 
         for file in &self.files[..] {
+            let progress = JobProgress {
+                files_done,
+                current_file: file.to_string_lossy().to_string(),
+                lines_per_second: lines_per_second(line_total, &start),
+            };
+            if !on_progress(&progress) {
+                self.cancelled = true;
+                break;
+            }
+            files_done += 1;
+
             let conn = &mut db::establish_connection(); // In memory db, fresh db on each call
             let file_path = match verify_file(&file) {
                 Ok(f) => f,
@@ -91,7 +104,8 @@ impl ParserJob {
 
             let lines = reader.lines();
 
-            let (success, file_points) = process_lines(conn, file.to_path_buf(), lines);
+            let (success, file_points, line_count) = process_lines(conn, file.to_path_buf(), lines);
+            line_total += line_count as u64;
             if success {
                 let summaries = db::queries::get_summaries(conn);
 
@@ -102,6 +116,7 @@ impl ParserJob {
                     &summaries.first().unwrap().player_name.replace(" ", "_"),
                 );
                 db::copy_db(conn, report_dir.join("summary.db"));
+                report_dbs.push(report_dir.join("summary.db"));
                 Self::write_data_files(
                     conn,
                     &report_dir,
@@ -110,6 +125,9 @@ impl ParserJob {
                     &file_points,
                     &summaries,
                 );
+                if context.export_html {
+                    export::write_report_files(context, &report_dir.join("summary.db"));
+                }
             } else {
                 println!(
                     "No valid data found in {}.",
@@ -122,11 +140,33 @@ impl ParserJob {
             }
             self.processed += 1;
         }
+        on_progress(&JobProgress {
+            files_done,
+            current_file: String::new(),
+            lines_per_second: lines_per_second(line_total, &start),
+        });
+        if self.merge && !self.cancelled {
+            match merge::merge_reports(context, &report_dbs, &mut self.errors) {
+                Ok(merged_db) => {
+                    if context.export_html {
+                        export::write_report_files(context, &merged_db);
+                    }
+                    self.merged_report_id = db::report_id(&context.output_dir, &merged_db);
+                    self.merged_report = Some(merged_db);
+                }
+                Err(message) => self.errors.push(ProcessingError {
+                    file_name: context.output_dir.clone(),
+                    message,
+                }),
+            }
+        }
         self.run_time = start.elapsed().as_secs();
         let local_time = Local::now();
         self.completion_date = format!("{}", local_time.format("%a %b %e %T %Y"));
-        let last_file = self.files.last().unwrap();
-        self.last_file = String::from(last_file.as_os_str().to_str().unwrap());
+        // A cancelled job stops short of the end of the list
+        if let Some(last_file) = self.files.get(files_done.max(1) - 1) {
+            self.last_file = String::from(last_file.as_os_str().to_str().unwrap());
+        }
 
         println!("File(s) processing time took: {} second.", self.run_time);
 
@@ -346,6 +386,15 @@ impl ParserJob {
     }
 }
 
+fn lines_per_second(line_total: u64, start: &Instant) -> u64 {
+    let seconds = start.elapsed().as_secs_f64();
+    if seconds > 0.0 {
+        (line_total as f64 / seconds) as u64
+    } else {
+        0
+    }
+}
+
 pub fn create_dir(dir_path: &PathBuf) {
     if !dir_path.exists() {
         match fs::create_dir_all(dir_path) {
@@ -372,7 +421,7 @@ pub fn process_lines(
     conn: &mut SqliteConnection,
     file: PathBuf,
     lines: Lines<BufReader<File>>,
-) -> (bool, Vec<FileDataPoint>) {
+) -> (bool, Vec<FileDataPoint>, u32) {
     let mut line_count: u32 = 0;
     let parsers = parsers::MATCHER_FUNCS;
     let mut data_points: Vec<FileDataPoint> = Vec::with_capacity(50000);
@@ -404,6 +453,11 @@ pub fn process_lines(
                 has_data = true;
                 break;
             }
+            // A session spent only at the consignment house is still worth a report
+            FileDataPoint::MarketTransaction { .. } => {
+                has_data = true;
+                break;
+            }
             _ => (),
         }
     }
@@ -420,7 +474,7 @@ pub fn process_lines(
 
     data_points.shrink_to_fit();
 
-    (has_data, data_points)
+    (has_data, data_points, line_count)
 }
 
 pub fn monitor_lines(
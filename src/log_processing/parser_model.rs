@@ -42,6 +42,21 @@ impl DataPosition {
     }
 }
 
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub enum LocationEventType {
+    Zone,
+    MissionEntered,
+    MissionExited,
+    MissionComplete,
+    TaskForceComplete,
+}
+
+#[derive(Debug, Serialize, Clone, Copy, PartialEq)]
+pub enum MarketTransactionType {
+    Sold,
+    Bought,
+}
+
 #[derive(Debug, Deserialize, Serialize, Clone)]
 pub enum DamageType {
     Smashing,
@@ -142,11 +157,17 @@ impl ControlType {
     }
 }
 
+impl fmt::Display for ControlType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
 #[derive(Debug, Serialize, Clone)]
 pub struct ControlPower {
-    control_type: ControlType,
-    target: String,
-    power_name: String,
+    pub control_type: ControlType,
+    pub target: String,
+    pub power_name: String,
 }
 impl ControlPower {
     pub fn new(control_type: &str, target: &str, power_name: &str) -> Self {
@@ -160,10 +181,10 @@ impl ControlPower {
 
 #[derive(Debug, Serialize, Clone)]
 pub struct HealEnduranceAction {
-    source: String,
-    target: String,
-    power_name: String,
-    amount: f32,
+    pub source: String,
+    pub target: String,
+    pub power_name: String,
+    pub amount: f32,
 }
 
 impl HealEnduranceAction {
@@ -195,6 +216,22 @@ pub enum FileDataPoint {
         data_position: DataPosition,
         loot: String,
     },
+    LevelUp {
+        data_position: DataPosition,
+        level: u32,
+    },
+    MarketTransaction {
+        data_position: DataPosition,
+        transaction_type: MarketTransactionType,
+        item_name: String,
+        quantity: u32,
+        influence: u64,
+    },
+    LocationEvent {
+        data_position: DataPosition,
+        event_type: LocationEventType,
+        name: Option<String>,
+    },
     MobControl {
         data_position: DataPosition,
         name: String,
@@ -381,6 +418,11 @@ pub enum FileDataPoint {
         target: String,
         power_name: String,
     },
+    PlayerResistDebuff {
+        data_position: DataPosition,
+        target: String,
+        power_name: String,
+    },
     PseudoPetSleepDebuff {
         data_position: DataPosition,
         pet_name: String,
@@ -390,11 +432,21 @@ pub enum FileDataPoint {
     SessionMarker {
         data_position: DataPosition,
         player_name: String,
+        test_name: Option<String>,
     },
     PlayerVictory {
         data_position: DataPosition,
         target: String,
     },
+    PlayerDefeated {
+        data_position: DataPosition,
+        source: Option<String>,
+    },
+    PlayerResurrected {
+        data_position: DataPosition,
+        source: Option<String>,
+        awakened: bool,
+    },
     OtherVictory {
         data_position: DataPosition,
         source: String,
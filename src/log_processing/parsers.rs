@@ -7,11 +7,18 @@ lazy_static! {
     static ref TEST_MATCHER: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) ").unwrap();
     static ref TEST_HIT_MATCHER: Regex = Regex::new(r"^HIT (.+)! Your (.+) power had a (.+)% chance to hit, you rolled a (.+).").unwrap();
 
-    static ref SESSION_MARKER_MATCHER_1: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) \[Local\] (.+): (?:(STARTPARSE|ENDPARSE).*).*").unwrap();
+    static ref SESSION_MARKER_MATCHER_1: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) \[Local\] (.+?): (STARTPARSE|ENDPARSE)\s*(.*)").unwrap();
     static ref SESSION_MARKER_MATCHER_2: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) (?:Now entering the Rogue Isles|Welcome to City of Heroes), (.+)!").unwrap();
 
     //static ref EXP_INF_GAIN_MATCHER: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) You gain ([0-9,]+) experience and ([0-9,]+) inf.+").unwrap();
     static ref EXP_INF_GAIN_MATCHER: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) You gain (?:(?P<exp>[0-9,]+) experience)?(?: and )?(?:(?:(?P<inf>[0-9,]+)) (?:influence|infamy))?.").unwrap();
+    static ref LEVEL_UP_MATCHER: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) (?:Congratulations[!,.]? ?)?(?:You are now|[Yy]ou have reached|[Yy]ou have trained to) level ([0-9]+)").unwrap();
+    static ref MISSION_ENTERED_MATCHER: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) (?:You have entered|Entering) (?:the |a )?mission(?:[:,]? (.+?))?[.!]*$").unwrap();
+    static ref MISSION_EXITED_MATCHER: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) (?:You have left|Leaving|Exiting) (?:the |a )?mission(?:[:,]? (.+?))?[.!]*$").unwrap();
+    static ref MISSION_COMPLETE_MATCHER: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) (?:Mission [Cc]omplete|You have completed the mission)(?:[:!,.]? (.+?))?[.!]*$").unwrap();
+    static ref TASK_FORCE_COMPLETE_MATCHER: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) (?:Congratulations! )?(?:You have completed the (?P<completed>.+?) (?:Task Force|Strike Force|Trial)|(?:(?P<name>.+?) )?(?:Task Force|Strike Force|Trial) [Cc]omplete[d]?)[.!]*$").unwrap();
+    static ref ZONE_MATCHER: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) (?:Now entering|Entering) (.+?)[.!]*$").unwrap();
+    static ref MARKET_TRANSACTION_MATCHER: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) (?:\[[A-Za-z ]+\] )?You (sold|bought|purchased) (?:([0-9]+)x? )?(.+?) for ([0-9,]+) (?:influence|infamy|information)").unwrap();
     static ref LOOT_DROP_MATCHER: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) You received (.+)[.]").unwrap();
 
     static ref MOB_HIT_MATCHER: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) (.+) HITS you! (.+) power had a (.+)% chance to hit and rolled a (.+)[.]").unwrap();
@@ -43,6 +50,8 @@ lazy_static! {
 
     static ref PLAYER_VICTORY_MATCHER: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) You have defeated (.+)").unwrap();
     static ref OTHER_VICTORY_MATCHER: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) (.+) has defeated (.+)").unwrap();
+    static ref PLAYER_DEFEATED_MATCHER: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) (?:You have been defeated(?: by (.+?))?|(.+) has defeated you)[.!]*$").unwrap();
+    static ref PLAYER_RESURRECTED_MATCHER: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) You have been (resurrected|revived|awakened)(?: by (.+?))?[.!]*$").unwrap();
     static ref POWER_RECHARGED_MATCHER: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) (.+) is recharged.$").unwrap();
 
     static ref PLAYER_CONTROL_MATCHER: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) You (Stun|Hold|Immobilize|Confuse|Taunt|Terrify?) (.+?) with your (.+)[.]").unwrap();
@@ -67,6 +76,7 @@ lazy_static! {
     static ref PLAYER_ENDURANCE_OTHER_MATCHER: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) You hit (.+) with (.+) granting them (.*) points of endurance[.]$").unwrap();
     static ref PLAYER_ENDURANCE_BUFF_MATCHER: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) (.+) hits you with their (.+) granting you (.+) points of endurance").unwrap();
 
+    static ref PLAYER_RESIST_DEBUFF_MATCHER: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) Your (.+) reduces the resistances of (.+)[.]$").unwrap();
     static ref RESISTANCE_DEBUFF: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+).*reduc.*resistance.*").unwrap();
     static ref TARGET_UNAFFECTED: Regex = Regex::new(r"^([0-9]+-[0-9]+-[0-9]+ [0-9]+:[0-9]+:[0-9]+) Target is unaffected by.*").unwrap();
 
@@ -95,7 +105,7 @@ lazy_static! {
 
 // Order matters!!!
 // Update array size when adding new functions
-pub static MATCHER_FUNCS: [fn(u32, &String) -> Option<FileDataPoint>; 49] = [
+pub static MATCHER_FUNCS: [fn(u32, &String) -> Option<FileDataPoint>; 59] = [
         extract_session_marker_1,
         extract_session_marker_2,
         pseudo_pet_attack_damage,
@@ -104,9 +114,18 @@ pub static MATCHER_FUNCS: [fn(u32, &String) -> Option<FileDataPoint>; 49] = [
         extract_pseudo_pet_streakbreaker_hit,
         player_pet_attack_damage,
         extract_exp_inf_gain,
+        extract_level_up,
+        extract_mission_entered,
+        extract_mission_exited,
+        extract_mission_complete,
+        extract_task_force_complete,
+        extract_zone_entered,
+        extract_player_defeated,
+        extract_player_resurrected,
         extract_other_victory,
         extract_player_activation,
         extract_power_recharged,
+        extract_market_transaction,
         extract_chat_message,
         extract_mob_pseudopet_hit,
         extract_mob_pseudopet_miss,
@@ -123,6 +142,7 @@ pub static MATCHER_FUNCS: [fn(u32, &String) -> Option<FileDataPoint>; 49] = [
         extract_autohit_pseudo_pet_two,
         extract_pseudo_pet_knockback,
         extract_pseudo_pet_resist_debuff,
+        extract_player_resist_debuff,
         extract_pseudo_pet_sleep_debuff,
         extract_player_knockback,
         extract_player_readying_power,
@@ -170,11 +190,22 @@ pub fn extract_session_marker_1(line_number: u32, line: &String) -> Option<FileD
         Some(data) => Some(FileDataPoint::SessionMarker {
             data_position: DataPosition::new(line_number, &data[1]),
             player_name: String::from(&data[2]),
+            test_name: extract_test_name(&data[3], &data[4]),
         }),
         None => None,
     }
 }
 
+// STARTPARSE followed by a name marks a benchmark run, like "STARTPARSE pylon"
+fn extract_test_name(marker: &str, name: &str) -> Option<String> {
+    let name = name.trim().to_lowercase();
+    if marker == "STARTPARSE" && !name.is_empty() {
+        Some(name)
+    } else {
+        None
+    }
+}
+
 pub fn extract_session_marker_2(line_number: u32, line: &String) -> Option<FileDataPoint> {
     let caps = SESSION_MARKER_MATCHER_2.captures(line);
 
@@ -182,6 +213,7 @@ pub fn extract_session_marker_2(line_number: u32, line: &String) -> Option<FileD
         Some(data) => Some(FileDataPoint::SessionMarker {
             data_position: DataPosition::new(line_number, &data[1]),
             player_name: String::from(&data[2]),
+            test_name: None,
         }),
         None => None,
     }
@@ -282,6 +314,106 @@ pub fn extract_exp_inf_gain(line_number: u32, line: &String) -> Option<FileDataP
     }
 }
 
+pub fn extract_level_up(line_number: u32, line: &String) -> Option<FileDataPoint> {
+    let caps = LEVEL_UP_MATCHER.captures(line);
+
+    match caps {
+        Some(data) => Some(FileDataPoint::LevelUp {
+            data_position: DataPosition::new(line_number, &data[1]),
+            level: data[2].parse().unwrap(),
+        }),
+        None => None,
+    }
+}
+
+pub fn extract_mission_entered(line_number: u32, line: &String) -> Option<FileDataPoint> {
+    let caps = MISSION_ENTERED_MATCHER.captures(line);
+
+    match caps {
+        Some(data) => Some(FileDataPoint::LocationEvent {
+            data_position: DataPosition::new(line_number, &data[1]),
+            event_type: LocationEventType::MissionEntered,
+            name: data.get(2).map(|m| m.as_str().to_string()),
+        }),
+        None => None,
+    }
+}
+
+pub fn extract_mission_exited(line_number: u32, line: &String) -> Option<FileDataPoint> {
+    let caps = MISSION_EXITED_MATCHER.captures(line);
+
+    match caps {
+        Some(data) => Some(FileDataPoint::LocationEvent {
+            data_position: DataPosition::new(line_number, &data[1]),
+            event_type: LocationEventType::MissionExited,
+            name: data.get(2).map(|m| m.as_str().to_string()),
+        }),
+        None => None,
+    }
+}
+
+pub fn extract_mission_complete(line_number: u32, line: &String) -> Option<FileDataPoint> {
+    let caps = MISSION_COMPLETE_MATCHER.captures(line);
+
+    match caps {
+        Some(data) => Some(FileDataPoint::LocationEvent {
+            data_position: DataPosition::new(line_number, &data[1]),
+            event_type: LocationEventType::MissionComplete,
+            name: data.get(2).map(|m| m.as_str().to_string()),
+        }),
+        None => None,
+    }
+}
+
+pub fn extract_task_force_complete(line_number: u32, line: &String) -> Option<FileDataPoint> {
+    let caps = TASK_FORCE_COMPLETE_MATCHER.captures(line);
+
+    match caps {
+        Some(data) => Some(FileDataPoint::LocationEvent {
+            data_position: DataPosition::new(line_number, &data[1]),
+            event_type: LocationEventType::TaskForceComplete,
+            name: data
+                .name("completed")
+                .or(data.name("name"))
+                .map(|m| m.as_str().to_string()),
+        }),
+        None => None,
+    }
+}
+
+pub fn extract_zone_entered(line_number: u32, line: &String) -> Option<FileDataPoint> {
+    let caps = ZONE_MATCHER.captures(line);
+
+    match caps {
+        Some(data) => Some(FileDataPoint::LocationEvent {
+            data_position: DataPosition::new(line_number, &data[1]),
+            event_type: LocationEventType::Zone,
+            name: Some(String::from(&data[2])),
+        }),
+        None => None,
+    }
+}
+
+// Consignment house sales and purchases, they can arrive in a chat channel so this runs before the chat matcher
+pub fn extract_market_transaction(line_number: u32, line: &String) -> Option<FileDataPoint> {
+    let caps = MARKET_TRANSACTION_MATCHER.captures(line);
+
+    match caps {
+        Some(data) => Some(FileDataPoint::MarketTransaction {
+            data_position: DataPosition::new(line_number, &data[1]),
+            transaction_type: if &data[2] == "sold" {
+                MarketTransactionType::Sold
+            } else {
+                MarketTransactionType::Bought
+            },
+            item_name: String::from(&data[4]),
+            quantity: data.get(3).map_or(1, |q| q.as_str().parse().unwrap_or(1)),
+            influence: data[5].replace(',', "").parse().unwrap_or(0),
+        }),
+        None => None,
+    }
+}
+
 pub fn extract_loot_drop(line_number: u32, line: &String) -> Option<FileDataPoint> {
     let caps = LOOT_DROP_MATCHER.captures(line);
 
@@ -611,6 +743,19 @@ fn extract_pseudo_pet_resist_debuff(line_number: u32, line: &String) -> Option<F
     }
 }
 
+fn extract_player_resist_debuff(line_number: u32, line: &String) -> Option<FileDataPoint> {
+    let caps = PLAYER_RESIST_DEBUFF_MATCHER.captures(line);
+
+    match caps {
+        Some(data) => Some(FileDataPoint::PlayerResistDebuff {
+            data_position: DataPosition::new(line_number, &data[1]),
+            target: String::from(&data[3]),
+            power_name: String::from(&data[2]),
+        }),
+        None => None,
+    }
+}
+
 fn extract_pseudo_pet_sleep_debuff(line_number: u32, line: &String) -> Option<FileDataPoint> {
     let caps = PSEDUO_PET_SLEEP_DEBUFF.captures(line);
 
@@ -637,6 +782,31 @@ pub fn extract_player_victory(line_number: u32, line: &String) -> Option<FileDat
     }
 }
 
+pub fn extract_player_defeated(line_number: u32, line: &String) -> Option<FileDataPoint> {
+    let caps = PLAYER_DEFEATED_MATCHER.captures(line);
+
+    match caps {
+        Some(data) => Some(FileDataPoint::PlayerDefeated {
+            data_position: DataPosition::new(line_number, &data[1]),
+            source: data.get(2).or(data.get(3)).map(|m| String::from(m.as_str())),
+        }),
+        None => None,
+    }
+}
+
+pub fn extract_player_resurrected(line_number: u32, line: &String) -> Option<FileDataPoint> {
+    let caps = PLAYER_RESURRECTED_MATCHER.captures(line);
+
+    match caps {
+        Some(data) => Some(FileDataPoint::PlayerResurrected {
+            data_position: DataPosition::new(line_number, &data[1]),
+            source: data.get(3).map(|m| String::from(m.as_str())),
+            awakened: &data[2] == "awakened",
+        }),
+        None => None,
+    }
+}
+
 pub fn extract_other_victory(line_number: u32, line: &String) -> Option<FileDataPoint> {
     let caps = OTHER_VICTORY_MATCHER.captures(line);
 
@@ -867,3 +1037,61 @@ pub fn extract_unparsed(line_number: u32, line: &String) -> Option<FileDataPoint
         None => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::MATCHER_FUNCS;
+    use crate::log_processing::parser_model::FileDataPoint;
+
+    // Runs the matchers in order like process_lines, the first match wins
+    fn parse(line: &str) -> FileDataPoint {
+        let line = String::from(line);
+        MATCHER_FUNCS
+            .iter()
+            .find_map(|p| p(1, &line))
+            .expect("extract_unparsed matches everything")
+    }
+
+    // PlayerDefeated and PlayerResurrected go to player_death,
+    // PlayerVictory and OtherVictory go to defeated_targets
+    #[test]
+    fn defeat_lines_land_in_their_own_variant() {
+        match parse("2024-05-01 10:00:00 You have been defeated by Hellfoo Blaster.") {
+            FileDataPoint::PlayerDefeated { source, .. } => assert_eq!(source.as_deref(), Some("Hellfoo Blaster")),
+            other => panic!("unexpected {:?}", other),
+        }
+        match parse("2024-05-01 10:00:00 You have been defeated.") {
+            FileDataPoint::PlayerDefeated { source, .. } => assert_eq!(source, None),
+            other => panic!("unexpected {:?}", other),
+        }
+        match parse("2024-05-01 10:00:00 Hellfoo Blaster has defeated you") {
+            FileDataPoint::PlayerDefeated { source, .. } => assert_eq!(source.as_deref(), Some("Hellfoo Blaster")),
+            other => panic!("unexpected {:?}", other),
+        }
+        match parse("2024-05-01 10:00:00 You have been resurrected by Medic.") {
+            FileDataPoint::PlayerResurrected { source, awakened, .. } => {
+                assert_eq!(source.as_deref(), Some("Medic"));
+                assert!(!awakened);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        match parse("2024-05-01 10:00:00 You have been awakened.") {
+            FileDataPoint::PlayerResurrected { source, awakened, .. } => {
+                assert_eq!(source, None);
+                assert!(awakened);
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        match parse("2024-05-01 10:00:00 Teammate has defeated Young Thug") {
+            FileDataPoint::OtherVictory { source, target, .. } => {
+                assert_eq!(source, "Teammate");
+                assert_eq!(target, "Young Thug");
+            }
+            other => panic!("unexpected {:?}", other),
+        }
+        match parse("2024-05-01 10:00:00 You have defeated Hellfoo Blaster") {
+            FileDataPoint::PlayerVictory { target, .. } => assert_eq!(target, "Hellfoo Blaster"),
+            other => panic!("unexpected {:?}", other),
+        }
+    }
+}
@@ -12,7 +12,9 @@ use std::{env, fs};
 use tera::Tera;
 
 mod args;
+mod assets;
 pub mod db;
+mod error;
 mod game_data;
 mod log_processing;
 mod models;
@@ -22,22 +24,24 @@ mod web;
 mod overlay;
 
 const OUTPUT_DIR: &str = "output";
-const TEMPLATES: &str = "templates";
+const INBOX_DIR: &str = "inbox";
 const VERSION: &str = "1.3";
 
 #[derive(Clone, Debug)]
 struct AppContext {
     working_dir: PathBuf,
-    resources_dir: PathBuf,
     output_dir: PathBuf,
+    // Logs uploaded from the browser are saved here before parsing
+    inbox_dir: PathBuf,
     dps_interval: usize,
+    // Write an HTML export of every summary when a log is parsed
+    export_html: bool,
     web_address: String,
     web_port: u16,
     tera: Tera,
 }
 
 // Todos
-// charts
 // windows dialog projects exist
 fn main() {
     println!("################################");
@@ -45,7 +49,7 @@ fn main() {
     println!("################################");
     let start = Instant::now();
 
-    let (app_context, log_file_names, monitor_job) = initialize();
+    let (app_context, log_file_names, monitor_job, merge_logs) = initialize();
 
     if let Some(job) = monitor_job {
         println!("Starting monitor job on directory {:?}.", job.config.dir);
@@ -61,6 +65,10 @@ fn main() {
         errors: Vec::new(),
         completion_date: "".to_string(),
         last_file: "".to_string(),
+        merge: merge_logs,
+        merged_report: None,
+        merged_report_id: None,
+        cancelled: false,
     };
     if !parser_job.files.is_empty() {
         parser_job.process_logs(&app_context);
@@ -78,7 +86,7 @@ fn main() {
 }
 
 fn setup_tera() -> Tera {
-    match Tera::new(&format!("{}{}*.html", TEMPLATES, std::path::MAIN_SEPARATOR)) {
+    match assets::load_templates() {
         Ok(mut t) => {
             t.autoescape_on(vec![]);
             t
@@ -87,14 +95,14 @@ fn setup_tera() -> Tera {
     }
 }
 
-fn get_last_modified_file_in_dir<D: AsRef<Path>>(dir: D) -> PathBuf {
+// None when the directory can't be read or has no files
+fn get_last_modified_file_in_dir<D: AsRef<Path>>(dir: D) -> Option<PathBuf> {
     std::fs::read_dir(dir)
-        .expect("Couldn't access local directory")
+        .ok()?
         .flatten() // Remove failed
-        .filter(|f| f.metadata().unwrap().is_file()) // Filter out directories (only consider files)
-        .max_by_key(|x| x.metadata().unwrap().modified().unwrap())
-        .map(|r| dunce::canonicalize(r.path()).unwrap())
-        .unwrap()
+        .filter(|f| f.metadata().is_ok_and(|m| m.is_file())) // Filter out directories (only consider files)
+        .max_by_key(|x| x.metadata().and_then(|m| m.modified()).ok())
+        .and_then(|r| dunce::canonicalize(r.path()).ok())
 }
 
 fn read_log_file_dir<D: AsRef<Path>>(dir: D) -> Vec<PathBuf> {
@@ -107,7 +115,7 @@ fn read_log_file_dir<D: AsRef<Path>>(dir: D) -> Vec<PathBuf> {
                     .map(|r| r.unwrap().path())
                     .filter(|r| r.is_file())
                     .map(|r| dunce::canonicalize(r).unwrap())
-                    .filter(|r| r.extension().unwrap() == "txt")
+                    .filter(|r| r.extension().is_some_and(|e| e == "txt"))
                     .collect();
 
                 file_list
@@ -126,7 +134,7 @@ fn read_log_file_dir<D: AsRef<Path>>(dir: D) -> Vec<PathBuf> {
     }
 }
 
-fn initialize() -> (AppContext, Vec<PathBuf>, Option<MonitorJob>) {
+fn initialize() -> (AppContext, Vec<PathBuf>, Option<MonitorJob>, bool) {
     let working_dir = env::current_dir().unwrap().clone();
     println!(
         "Cyclops was compiled on {}:{}.",
@@ -179,9 +187,14 @@ fn initialize() -> (AppContext, Vec<PathBuf>, Option<MonitorJob>) {
     log_processing::create_dir(&output_dir);
     println!("Output directory: {}", output_dir.display());
 
-    let tera = setup_tera();
+    let inbox_dir = working_dir.join(INBOX_DIR);
+    log_processing::create_dir(&inbox_dir);
 
-    let res_dir = working_dir.clone().join("resources");
+    if let Some(assets_dir) = args.assetsdir {
+        println!("Value for assets dir: {:?}", assets_dir);
+        assets::set_override_dir(assets_dir);
+    }
+    let tera = setup_tera();
 
     let mut monitor_job: Option<MonitorJob> = None;
     if let Some(path) = args.monitorconfig {
@@ -205,14 +218,16 @@ fn initialize() -> (AppContext, Vec<PathBuf>, Option<MonitorJob>) {
     (
         AppContext {
             working_dir,
-            resources_dir: res_dir,
             output_dir,
+            inbox_dir,
             dps_interval,
+            export_html: args.export,
             web_address: String::from(webserver_address),
             web_port: webserver_port as u16,
             tera,
         },
         log_file_names,
         monitor_job,
+        args.merge,
     )
 }
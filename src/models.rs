@@ -3,6 +3,7 @@ use super::schema::*;
 use diesel::prelude::*;
 use diesel::sql_types::{Integer, Nullable};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 //#![allow(unused)]
 //#![allow(clippy::all)]
@@ -23,7 +24,7 @@ pub struct DamageAction {
     pub source_name: String,
 }
 
-#[derive(Queryable, Debug, Identifiable)]
+#[derive(Queryable, Debug, Clone, Identifiable, Insertable, Selectable)]
 #[diesel(primary_key(summary_key, line_number, log_date))]
 #[diesel(table_name = debuff_action)]
 pub struct DebuffAction {
@@ -99,7 +100,7 @@ pub struct Reward {
     pub reward_type: String,
 }
 
-#[derive(Queryable, Debug, Clone, Identifiable, Insertable, Selectable, Serialize, Deserialize)]
+#[derive(Queryable, Debug, Clone, Identifiable, Insertable, Selectable, Serialize, Deserialize, ToSchema)]
 #[diesel(primary_key(summary_key))]
 #[diesel(table_name = summary)]
 pub struct Summary {
@@ -111,6 +112,131 @@ pub struct Summary {
     pub log_file_name: String,
 }
 
+#[derive(Queryable, Debug, Clone, Identifiable, Insertable, Selectable, Serialize, Deserialize, ToSchema)]
+#[diesel(primary_key(summary_key, line_number, log_date))]
+#[diesel(table_name = player_death)]
+pub struct PlayerDeath {
+    pub summary_key: i32,
+    pub line_number: i32,
+    pub log_date: String,
+    pub event_type: String,
+    pub source_name: Option<String>,
+}
+
+#[derive(Queryable, Debug, Clone, Identifiable, Insertable, Selectable, Serialize, Deserialize)]
+#[diesel(primary_key(summary_key, line_number, log_date))]
+#[diesel(table_name = level_up)]
+pub struct LevelUp {
+    pub summary_key: i32,
+    pub line_number: i32,
+    pub log_date: String,
+    pub level: i32,
+}
+
+#[derive(Queryable, Debug, Clone, Identifiable, Selectable, Serialize, Deserialize)]
+#[diesel(primary_key(summary_key, line_number, log_date))]
+#[diesel(table_name = consumable_activation)]
+pub struct ConsumableActivation {
+    pub summary_key: i32,
+    pub line_number: i32,
+    pub log_date: String,
+    pub power_name: String,
+    pub consumable_type: String,
+}
+
+#[derive(Queryable, Debug, Clone, Identifiable, Insertable, Selectable, Serialize, Deserialize)]
+#[diesel(primary_key(summary_key, line_number, log_date))]
+#[diesel(table_name = location_event)]
+pub struct LocationEvent {
+    pub summary_key: i32,
+    pub line_number: i32,
+    pub log_date: String,
+    pub event_type: String,
+    pub name: Option<String>,
+}
+
+#[derive(Queryable, Debug, Clone, Identifiable, Insertable, Selectable)]
+#[diesel(primary_key(summary_key, line_number, log_date))]
+#[diesel(table_name = team_event)]
+pub struct TeamEvent {
+    pub summary_key: i32,
+    pub line_number: i32,
+    pub log_date: String,
+    pub event_type: String,
+    pub member_name: String,
+    pub power_name: Option<String>,
+}
+
+#[derive(Queryable, Debug, Clone, Identifiable, Insertable, Selectable, Serialize)]
+#[diesel(primary_key(summary_key, line_number, log_date))]
+#[diesel(table_name = market_transaction)]
+pub struct MarketTransaction {
+    pub summary_key: i32,
+    pub line_number: i32,
+    pub log_date: String,
+    pub transaction_type: String,
+    pub item_name: String,
+    pub quantity: i32,
+    pub influence: i64,
+}
+
+#[derive(Queryable, Debug, Clone, Identifiable, Insertable, Selectable, Serialize, Deserialize)]
+#[diesel(primary_key(summary_key))]
+#[diesel(table_name = merge_source)]
+pub struct MergeSource {
+    pub summary_key: i32,
+    pub source_db: String,
+    pub window_start: String,
+    pub window_end: String,
+}
+
+#[derive(Queryable, Debug, Clone, Identifiable, Insertable, Selectable, Serialize, Deserialize)]
+#[diesel(primary_key(summary_key))]
+#[diesel(table_name = report_scope)]
+pub struct ReportScope {
+    pub summary_key: i32,
+    pub scope_name: String,
+    pub first_line_number: i32,
+    pub last_line_number: i32,
+    pub source_db: String,
+}
+
+#[derive(Queryable, Debug, Clone, Identifiable, Insertable, Selectable)]
+#[diesel(primary_key(summary_key))]
+#[diesel(table_name = benchmark_run)]
+pub struct BenchmarkRun {
+    pub summary_key: i32,
+    pub test_name: String,
+}
+
+#[derive(Queryable, Debug, Clone, Identifiable, Insertable, Selectable)]
+#[diesel(primary_key(summary_key, line_number, log_date))]
+#[diesel(table_name = heal_endurance_action)]
+pub struct HealEnduranceAction {
+    pub summary_key: i32,
+    pub line_number: i32,
+    pub log_date: String,
+    pub action_type: String,
+    pub source_name: String,
+    pub target_name: String,
+    pub power_name: String,
+    pub amount: i32,
+}
+
+#[derive(Queryable, Debug, Clone, Identifiable, Insertable, Selectable)]
+#[diesel(primary_key(summary_key, line_number, log_date))]
+#[diesel(table_name = control_action)]
+pub struct ControlAction {
+    pub summary_key: i32,
+    pub line_number: i32,
+    pub log_date: String,
+    pub source_type: String,
+    pub source_name: String,
+    pub target_name: String,
+    pub power_name: String,
+    pub control_type: String,
+}
+
 #[derive(
     Queryable,
     Debug,
@@ -121,6 +247,7 @@ pub struct Summary {
     QueryableByName,
     Serialize,
     Deserialize,
+    ToSchema,
 )]
 #[diesel(primary_key(log_date))]
 #[diesel(table_name = index_details)]
@@ -142,6 +269,7 @@ pub struct IndexDetails {
     QueryableByName,
     Serialize,
     Deserialize,
+    ToSchema,
 )]
 #[diesel(primary_key(summary_key))]
 #[diesel(table_name = total_damage_report)]
@@ -242,6 +370,7 @@ pub struct DamageReportByPower {
     QueryableByName,
     Serialize,
     Deserialize,
+    ToSchema,
 )]
 #[diesel(primary_key(summary_key))]
 #[diesel(table_name = rewards_defeats)]
@@ -262,6 +391,7 @@ pub struct RewardsDefeats {
     QueryableByName,
     Serialize,
     Deserialize,
+    ToSchema,
 )]
 #[diesel(primary_key(summary_key))]
 #[diesel(table_name = damage_taken)]
@@ -284,6 +414,7 @@ pub struct DamageTaken {
     QueryableByName,
     Serialize,
     Deserialize,
+    ToSchema,
 )]
 #[diesel(primary_key(summary_key))]
 #[diesel(table_name = damage_dealt_by_type)]
@@ -304,6 +435,7 @@ pub struct DamageDealtByType {
     QueryableByName,
     Serialize,
     Deserialize,
+    ToSchema,
 )]
 #[diesel(primary_key(summary_key))]
 #[diesel(table_name = damage_taken_by_type)]
@@ -323,6 +455,7 @@ pub struct DamageTakenByType {
     QueryableByName,
     Serialize,
     Deserialize,
+    ToSchema,
 )]
 #[diesel(primary_key(summary_key))]
 #[diesel(table_name = damage_taken_by_mob)]
@@ -345,6 +478,7 @@ pub struct DamageTakenByMob {
     QueryableByName,
     Serialize,
     Deserialize,
+    ToSchema,
 )]
 #[diesel(primary_key(summary_key))]
 #[diesel(table_name = damage_taken_by_mob_power)]
@@ -369,6 +503,7 @@ pub struct DamageTakenByMobPower {
     QueryableByName,
     Serialize,
     Deserialize,
+    ToSchema,
 )]
 #[diesel(primary_key(summary_key))]
 #[diesel(table_name = damage_dealt_to_mob_by_power)]
@@ -414,6 +549,7 @@ pub struct LastInterestingDate {
     QueryableByName,
     Serialize,
     Deserialize,
+    ToSchema,
 )]
 #[diesel(primary_key(summary_key))]
 #[diesel(table_name = session_stats)]
@@ -425,4 +561,154 @@ pub struct SessionStats {
     pub exp_5: i32,
     pub total_inf: i32,
     pub inf_5: i32,
-}
\ No newline at end of file
+}
+
+#[derive(
+    Queryable,
+    Debug,
+    Clone,
+    Identifiable,
+    Insertable,
+    Selectable,
+    QueryableByName,
+    Serialize,
+    Deserialize,
+)]
+#[diesel(primary_key(summary_key))]
+#[diesel(table_name = proc_rates)]
+pub struct ProcRate {
+    pub summary_key: i32,
+    pub proc_name: String,
+    pub power_name: String,
+    pub fires: i32,
+    pub activations: i32,
+    pub average_recharge: Option<i32>,
+}
+
+#[derive(
+    Queryable,
+    Debug,
+    Clone,
+    Identifiable,
+    Insertable,
+    Selectable,
+    QueryableByName,
+    Serialize,
+    Deserialize,
+    ToSchema,
+)]
+#[diesel(primary_key(source_name, power_name))]
+#[diesel(table_name = death_recap_damage)]
+pub struct DeathRecapDamage {
+    pub source_name: String,
+    pub power_name: String,
+    pub hits: i32,
+    pub total_damage: i32,
+}
+
+#[derive(
+    Queryable,
+    Debug,
+    Clone,
+    Identifiable,
+    Insertable,
+    Selectable,
+    QueryableByName,
+    Serialize,
+    Deserialize,
+    ToSchema,
+)]
+#[diesel(primary_key(source_name, power_name))]
+#[diesel(table_name = death_recap_heal)]
+pub struct DeathRecapHeal {
+    pub source_name: String,
+    pub power_name: String,
+    pub heals: i32,
+    pub total_healed: i32,
+}
+
+#[derive(
+    Queryable,
+    Debug,
+    Clone,
+    Identifiable,
+    Insertable,
+    Selectable,
+    QueryableByName,
+    Serialize,
+    Deserialize,
+    ToSchema,
+)]
+#[diesel(primary_key(source_name, power_name, control_type))]
+#[diesel(table_name = death_recap_control)]
+pub struct DeathRecapControl {
+    pub source_name: String,
+    pub power_name: String,
+    pub control_type: String,
+    pub applications: i32,
+}
+
+#[derive(
+    Queryable,
+    Debug,
+    Clone,
+    Identifiable,
+    Insertable,
+    Selectable,
+    QueryableByName,
+    Serialize,
+    Deserialize,
+    ToSchema,
+)]
+#[diesel(primary_key(summary_key))]
+#[diesel(table_name = benchmark_report)]
+pub struct BenchmarkReport {
+    pub summary_key: i32,
+    pub test_name: String,
+    pub player_name: String,
+    pub log_date: String,
+    pub total_damage: i32,
+    pub combat_seconds: i32,
+    pub time_to_kill: Option<i32>,
+    pub sustained_dps: i32,
+    pub activations: i32,
+    pub activations_per_minute: f64,
+}
+
+#[derive(
+    Queryable,
+    Debug,
+    Clone,
+    Identifiable,
+    Selectable,
+    QueryableByName,
+    Serialize,
+    Deserialize,
+)]
+#[diesel(primary_key(summary_key, line_number))]
+#[diesel(table_name = defeated_target_damage)]
+pub struct DefeatedTargetDamage {
+    pub summary_key: i32,
+    pub line_number: i32,
+    pub target_name: String,
+    pub total_damage: i32,
+    pub damage_before_final_blow: i32,
+    pub time_to_kill: i32,
+}
+
+#[derive(QueryableByName, Debug, Clone, Serialize, Deserialize)]
+#[diesel(table_name = damage_timeline)]
+pub struct DamageTimeline {
+    pub bucket: i32,
+    pub damage_type: String,
+    pub outgoing: i32,
+    pub damage: i32,
+}
+
+#[derive(QueryableByName, Debug, Clone, Serialize, Deserialize)]
+#[diesel(table_name = reward_timeline)]
+pub struct RewardTimeline {
+    pub elapsed: i32,
+    pub experience: i32,
+    pub influence: i32,
+}
@@ -15,6 +15,7 @@ use std::{
     thread,
     time::{self, Instant},
 };
+use tokio::sync::watch;
 
 use crate::log_processing::monitor_lines;
 use crate::models::SessionStats;
@@ -38,6 +39,8 @@ lazy_static! {
         total_inf: 0,
         inf_5: 0,
     });
+    // Bumped after every pass over the log so the web streams can push the new state
+    static ref MONITOR_UPDATES: watch::Sender<u64> = watch::channel(0).0;
 }
 
 pub struct MonitorJob {
@@ -79,7 +82,16 @@ impl MonitorJob {
         )
         .expect("Unable to write to monitor log.");
         loop {
-            let dir_path = get_last_modified_file_in_dir(self.config.dir.clone());
+            let dir_path = match get_last_modified_file_in_dir(&self.config.dir) {
+                Some(f) => f,
+                None => {
+                    self.errors.push(ProcessingError {
+                        file_name: self.config.dir.clone(),
+                        message: String::from("No log files found in the monitor directory"),
+                    });
+                    return self;
+                }
+            };
             let file_path = match verify_file(&dir_path) {
                 Ok(f) => f,
                 Err(e) => {
@@ -227,6 +239,9 @@ impl MonitorJob {
                     writeln!(self.log_file, "###  ###").expect("Unable to write to monitor log.");
                 }
 
+                drop(display_map);
+                MONITOR_UPDATES.send_modify(|pass| *pass += 1);
+
                 self.last_run_time = start.elapsed().as_millis();
                 writeln!(
                     self.log_file,
@@ -317,6 +332,14 @@ fn create_display_message(
     }
 }
 
+// Changes every time the monitor has new stats and messages, read them with get_messages
+pub fn subscribe() -> watch::Receiver<u64> {
+    let mut updates = MONITOR_UPDATES.subscribe();
+    // New subscribers get the current state straight away
+    updates.mark_changed();
+    updates
+}
+
 pub fn get_messages() -> (DateTime<Local>, SessionStats, Vec<MonitorMessage>) {
     let details = DISPLAY_MESSAGES.lock().unwrap();
     let now = Local::now();
@@ -3,6 +3,7 @@ use std::path::PathBuf;
 
 use chrono::{DateTime, Local};
 use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
 
 #[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Default, Hash)]
 pub enum TriggerType {
@@ -45,7 +46,7 @@ pub struct MessageDetails {
     pub end_time: DateTime<Local>,
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Default, Hash)]
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, Default, Hash, ToSchema)]
 pub struct MonitorMessage {
    pub output_text: String,
    pub color: String,
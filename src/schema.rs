@@ -96,6 +96,120 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    player_death (summary_key, line_number, log_date) {
+        summary_key -> Integer,
+        line_number -> Integer,
+        log_date -> Text,
+        event_type -> Text,
+        source_name -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    consumable_activation (summary_key, line_number, log_date) {
+        summary_key -> Integer,
+        line_number -> Integer,
+        log_date -> Text,
+        power_name -> Text,
+        consumable_type -> Text,
+    }
+}
+
+diesel::table! {
+    location_event (summary_key, line_number, log_date) {
+        summary_key -> Integer,
+        line_number -> Integer,
+        log_date -> Text,
+        event_type -> Text,
+        name -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    team_event (summary_key, line_number, log_date) {
+        summary_key -> Integer,
+        line_number -> Integer,
+        log_date -> Text,
+        event_type -> Text,
+        member_name -> Text,
+        power_name -> Nullable<Text>,
+    }
+}
+
+diesel::table! {
+    market_transaction (summary_key, line_number, log_date) {
+        summary_key -> Integer,
+        line_number -> Integer,
+        log_date -> Text,
+        transaction_type -> Text,
+        item_name -> Text,
+        quantity -> Integer,
+        influence -> BigInt,
+    }
+}
+
+diesel::table! {
+    merge_source (summary_key) {
+        summary_key -> Integer,
+        source_db -> Text,
+        window_start -> Text,
+        window_end -> Text,
+    }
+}
+
+diesel::table! {
+    report_scope (summary_key) {
+        summary_key -> Integer,
+        scope_name -> Text,
+        first_line_number -> Integer,
+        last_line_number -> Integer,
+        source_db -> Text,
+    }
+}
+
+diesel::table! {
+    level_up (summary_key, line_number, log_date) {
+        summary_key -> Integer,
+        line_number -> Integer,
+        log_date -> Text,
+        level -> Integer,
+    }
+}
+
+diesel::table! {
+    heal_endurance_action (summary_key, line_number, log_date) {
+        summary_key -> Integer,
+        line_number -> Integer,
+        log_date -> Text,
+        action_type -> Text,
+        source_name -> Text,
+        target_name -> Text,
+        power_name -> Text,
+        amount -> Integer,
+    }
+}
+
+diesel::table! {
+    control_action (summary_key, line_number, log_date) {
+        summary_key -> Integer,
+        line_number -> Integer,
+        log_date -> Text,
+        source_type -> Text,
+        source_name -> Text,
+        target_name -> Text,
+        power_name -> Text,
+        control_type -> Text,
+    }
+}
+
+diesel::table! {
+    benchmark_run (summary_key) {
+        summary_key -> Integer,
+        test_name -> Text,
+    }
+}
+
 diesel::table! {
     index_details (log_date) {
         summary_key -> Integer,
@@ -243,6 +357,87 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    proc_rates (summary_key) {
+        summary_key -> Integer,
+        proc_name -> Text,
+        power_name -> Text,
+        fires -> Integer,
+        activations -> Integer,
+        average_recharge -> Nullable<Integer>,
+    }
+}
+
+diesel::table! {
+    death_recap_damage (source_name, power_name) {
+        source_name -> Text,
+        power_name -> Text,
+        hits -> Integer,
+        total_damage -> Integer,
+    }
+}
+
+diesel::table! {
+    death_recap_heal (source_name, power_name) {
+        source_name -> Text,
+        power_name -> Text,
+        heals -> Integer,
+        total_healed -> Integer,
+    }
+}
+
+diesel::table! {
+    death_recap_control (source_name, power_name, control_type) {
+        source_name -> Text,
+        power_name -> Text,
+        control_type -> Text,
+        applications -> Integer,
+    }
+}
+
+diesel::table! {
+    benchmark_report (summary_key) {
+        summary_key -> Integer,
+        test_name -> Text,
+        player_name -> Text,
+        log_date -> Text,
+        total_damage -> Integer,
+        combat_seconds -> Integer,
+        time_to_kill -> Nullable<Integer>,
+        sustained_dps -> Integer,
+        activations -> Integer,
+        activations_per_minute -> Double,
+    }
+}
+
+diesel::table! {
+    defeated_target_damage (summary_key, line_number) {
+        summary_key -> Integer,
+        line_number -> Integer,
+        target_name -> Text,
+        total_damage -> Integer,
+        damage_before_final_blow -> Integer,
+        time_to_kill -> Integer,
+    }
+}
+
+diesel::table! {
+    damage_timeline (bucket, damage_type, outgoing) {
+        bucket -> Integer,
+        damage_type -> Text,
+        outgoing -> Integer,
+        damage -> Integer,
+    }
+}
+
+diesel::table! {
+    reward_timeline (elapsed) {
+        elapsed -> Integer,
+        experience -> Integer,
+        influence -> Integer,
+    }
+}
+
 diesel::table! {
     last_interesting_date (log_date) {
         log_date -> Text
@@ -270,6 +465,18 @@ diesel::joinable!(hit_or_miss -> summary (summary_key));
 diesel::joinable!(player_activation -> summary (summary_key));
 diesel::joinable!(player_power_recharged -> summary (summary_key));
 diesel::joinable!(reward -> summary (summary_key));
+diesel::joinable!(player_death -> summary (summary_key));
+diesel::joinable!(level_up -> summary (summary_key));
+diesel::joinable!(consumable_activation -> summary (summary_key));
+diesel::joinable!(location_event -> summary (summary_key));
+diesel::joinable!(team_event -> summary (summary_key));
+diesel::joinable!(market_transaction -> summary (summary_key));
+diesel::joinable!(merge_source -> summary (summary_key));
+diesel::joinable!(report_scope -> summary (summary_key));
+diesel::joinable!(heal_endurance_action -> summary (summary_key));
+diesel::joinable!(control_action -> summary (summary_key));
+diesel::joinable!(benchmark_run -> summary (summary_key));
+diesel::joinable!(benchmark_report -> summary (summary_key));
 diesel::joinable!(total_damage_report -> summary (summary_key));
 diesel::joinable!(damage_taken -> summary (summary_key));
 diesel::joinable!(damage_dealt_by_type -> summary (summary_key));
@@ -277,6 +484,8 @@ diesel::joinable!(damage_taken_by_type -> summary (summary_key));
 diesel::joinable!(damage_taken_by_mob -> summary (summary_key));
 diesel::joinable!(damage_taken_by_mob_power -> summary (summary_key));
 diesel::joinable!(damage_dealt_to_mob_by_power -> summary (summary_key));
+diesel::joinable!(proc_rates -> summary (summary_key));
+diesel::joinable!(defeated_target_damage -> summary (summary_key));
 
 diesel::allow_tables_to_appear_in_same_query!(
     damage_action,
@@ -287,6 +496,17 @@ diesel::allow_tables_to_appear_in_same_query!(
     player_power_recharged,
     reward,
     summary,
+    player_death,
+    level_up,
+    consumable_activation,
+    location_event,
+    team_event,
+    market_transaction,
+    merge_source,
+    report_scope,
+    heal_endurance_action,
+    control_action,
+    benchmark_run,
     total_damage_report,
     damage_report_by_power,
     index_details,
@@ -296,5 +516,8 @@ diesel::allow_tables_to_appear_in_same_query!(
     damage_taken_by_mob,
     damage_taken_by_mob_power,
     damage_dealt_to_mob_by_power,
+    proc_rates,
+    benchmark_report,
+    defeated_target_damage,
     session_stats,
 );
@@ -0,0 +1,163 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use tera::Context;
+
+use crate::{
+    db,
+    web::web_structs_enums::{AnnotationAction, AnnotationQuery},
+};
+
+// Stored next to summary.db so a re-parse, which replaces the db, keeps the annotations
+const ANNOTATIONS_FILE: &str = "annotations.json";
+const ANNOTATIONS_TEMP_FILE: &str = "annotations.json.tmp";
+
+lazy_static! {
+    static ref ANNOTATIONS_LOCK: Mutex<()> = Mutex::new(());
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, ToSchema)]
+pub struct Bookmark {
+    pub line_number: i32,
+    pub log_date: Option<String>,
+    pub label: String,
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone, ToSchema)]
+pub struct Annotation {
+    pub notes: String,
+    pub tags: Vec<String>,
+    pub bookmarks: Vec<Bookmark>,
+}
+
+impl Annotation {
+    pub fn is_empty(&self) -> bool {
+        self.notes.is_empty() && self.tags.is_empty() && self.bookmarks.is_empty()
+    }
+
+    pub fn has_tag(&self, tag: &str) -> bool {
+        self.tags.iter().any(|t| t.eq_ignore_ascii_case(tag))
+    }
+
+    // Case insensitive match against tags, notes and bookmark labels
+    pub fn matches(&self, text: &str) -> bool {
+        let text = text.to_lowercase();
+        self.tags.iter().any(|t| t.to_lowercase().contains(&text))
+            || self.notes.to_lowercase().contains(&text)
+            || self
+                .bookmarks
+                .iter()
+                .any(|b| b.label.to_lowercase().contains(&text))
+    }
+}
+
+fn annotations_path(db_path: &Path) -> PathBuf {
+    report_file(db_path, ANNOTATIONS_FILE)
+}
+
+fn report_file(db_path: &Path, file_name: &str) -> PathBuf {
+    match db_path.parent() {
+        Some(dir) => dir.join(file_name),
+        None => PathBuf::from(file_name),
+    }
+}
+
+// All annotations for a report directory keyed by summary key
+pub fn load_all(db_path: &Path) -> BTreeMap<String, Annotation> {
+    match fs::read_to_string(annotations_path(db_path)) {
+        Ok(data) => serde_json::from_str(&data).unwrap_or_default(),
+        Err(_) => BTreeMap::new(),
+    }
+}
+
+pub fn load(db_path: &Path, key: i32) -> Annotation {
+    load_all(db_path)
+        .remove(&key.to_string())
+        .unwrap_or_default()
+}
+
+// Written to a temp file then renamed over the old one, a crash mid write can't truncate it
+fn save_all(db_path: &Path, annotations: &BTreeMap<String, Annotation>) {
+    match serde_json::to_string_pretty(annotations) {
+        Ok(data) => {
+            let temp_path = report_file(db_path, ANNOTATIONS_TEMP_FILE);
+            let result = fs::write(&temp_path, data).and_then(|_| fs::rename(&temp_path, annotations_path(db_path)));
+            if let Err(e) = result {
+                println!("Unable to save annotations: {:?}", e);
+                let _ = fs::remove_file(&temp_path);
+            }
+        }
+        Err(e) => println!("Unable to serialize annotations: {:?}", e),
+    }
+}
+
+pub fn process(context: &mut Context, query: &AnnotationQuery) {
+    let db_path: PathBuf = query.db_path.clone().into();
+    context.insert("annotation", &load(&db_path, query.key));
+}
+
+// Only called from the POST handler, reading annotations never writes the file
+pub fn process_action(context: &mut Context, query: &AnnotationQuery) {
+    let db_path: PathBuf = query.db_path.clone().into();
+
+    let annotation = match &query.action {
+        Some(action) => update(&db_path, query, action),
+        None => load(&db_path, query.key),
+    };
+    context.insert("annotation", &annotation);
+}
+
+fn update(db_path: &Path, query: &AnnotationQuery, action: &AnnotationAction) -> Annotation {
+    let _guard = ANNOTATIONS_LOCK.lock().unwrap();
+    let mut annotations = load_all(db_path);
+    let annotation = annotations.entry(query.key.to_string()).or_default();
+
+    match action {
+        AnnotationAction::SaveNotes => {
+            annotation.notes = query.notes.clone().unwrap_or_default().trim().to_string();
+        }
+        AnnotationAction::AddTag => {
+            if let Some(tag) = query.tag.as_deref().map(str::trim) {
+                if !tag.is_empty() && !annotation.has_tag(tag) {
+                    annotation.tags.push(tag.to_string());
+                    annotation.tags.sort();
+                }
+            }
+        }
+        AnnotationAction::RemoveTag => {
+            if let Some(tag) = query.tag.as_deref() {
+                annotation.tags.retain(|t| !t.eq_ignore_ascii_case(tag));
+            }
+        }
+        AnnotationAction::AddBookmark => {
+            if let Some(line) = query.line_number {
+                let mut conn = db::get_file_conn(db_path.to_path_buf());
+                annotation.bookmarks.push(Bookmark {
+                    line_number: line,
+                    log_date: db::queries::get_log_date_at_line(&mut conn, query.key, line),
+                    label: query.label.clone().unwrap_or_default().trim().to_string(),
+                });
+                annotation.bookmarks.sort_by_key(|b| b.line_number);
+            }
+        }
+        AnnotationAction::RemoveBookmark => {
+            if let Some(line) = query.line_number {
+                annotation.bookmarks.retain(|b| b.line_number != line);
+            }
+        }
+    }
+
+    let result = annotation.clone();
+    if result.is_empty() {
+        annotations.remove(&query.key.to_string());
+    }
+    save_all(db_path, &annotations);
+    result
+}
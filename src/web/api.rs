@@ -0,0 +1,673 @@
+use std::path::PathBuf;
+
+use actix_web::{get, web, HttpRequest, HttpResponse, Responder, ResponseError};
+use serde::{de::DeserializeOwned, Serialize};
+use utoipa::{OpenApi, ToSchema};
+
+use crate::{
+    db,
+    error::CyclopsError,
+    game_data,
+    models::{
+        BenchmarkReport, DamageDealtByType, DamageDealtToMobByPower, DamageTaken,
+        DamageTakenByMob, DamageTakenByMobPower, DamageTakenByType, RewardsDefeats,
+        SessionStats, Summary, TotalDamageReport,
+    },
+    monitoring::{self, monitor_structs::MonitorMessage},
+    AppContext,
+};
+
+use super::{
+    benchmark_handler::{self, BenchmarkBoard},
+    compare_summaries::{self, CompareReport},
+    consumables::{self, ConsumablesReport},
+    damage_by_power_table::{self, PowerRow},
+    damage_dealt_by_type_table, damage_taken_by_mob_power_table, damage_taken_by_mob_table,
+    damage_taken_by_type_table,
+    death_recap::{self, DeathRecapReport},
+    debuff_effectiveness::{self, DebuffEffectivenessReport},
+    defeated_targets_table::{self, DefeatedTargetRow},
+    dps_interval_table::{self, Interval},
+    index_handler::{self, IndexFilters, SessionEntry},
+    location_timeline::{self, LocationTimelineReport},
+    market::{self, MarketReport},
+    mob_ranks::{self, MobRankRow, MobRanks},
+    monitor_stream,
+    player_summary_table::SummaryQuery,
+    powers_and_mobs_table,
+    proc_rate_table::{self, ProcRateRow},
+    progression::{self, ProgressionReport},
+    team_roster::{self, TeamRosterReport},
+    web_structs_enums::{
+        BenchmarkQuery, CompareQuery, ConsumablesQuery, DamageByPowerQuery, DeathRecapQuery,
+        DebuffEffectivenessQuery, LocationTimelineQuery, MarketQuery, PowerTableActions, PowersMobsData,
+        ProgressionQuery, SessionsQuery, SortDirection, TableNames, TableQuery, TeamRosterQuery,
+    },
+};
+
+pub const API_ROOT: &str = "/api/v1";
+
+#[derive(OpenApi)]
+#[openapi(
+    info(
+        title = "Cyclops",
+        description = "Reports for parsed City of Heroes logs. Pass the report_id of a session from /api/v1/sessions as report and its summary_key as key."
+    ),
+    paths(
+        sessions,
+        summary,
+        damage_by_power,
+        damage_table,
+        powers_and_mobs,
+        mob_ranks_report,
+        death_recap_report,
+        debuff_effectiveness_report,
+        consumables_report,
+        location_timeline_report,
+        progression_report,
+        market_report,
+        team_roster_report,
+        compare,
+        benchmarks,
+        monitor_messages,
+        monitor_events,
+        openapi_document
+    ),
+    // Only referenced from query parameters, which are not collected on their own
+    components(schemas(PowerTableActions, SortDirection))
+)]
+struct ApiDoc;
+
+#[derive(Serialize, ToSchema)]
+struct ApiError {
+    error: String,
+}
+
+// Everything on the top of the summary page
+#[derive(Serialize, ToSchema)]
+struct SummaryReport {
+    summary: Summary,
+    rewards_defeats: RewardsDefeats,
+    total_damage: Option<TotalDamageReport>,
+    damage_taken: Option<DamageTaken>,
+    session_stats: Option<SessionStats>,
+    deaths: Option<i64>,
+    benchmark: Option<BenchmarkReport>,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(untagged)]
+enum TableRows {
+    DamageDealtByType(Vec<DamageDealtByType>),
+    DamageTakenByType(Vec<DamageTakenByType>),
+    DamageTakenByMob(Vec<DamageTakenByMob>),
+    DamageTakenByMobPower(Vec<DamageTakenByMobPower>),
+    DPSIntervals(Vec<Interval>),
+    ProcRates(Vec<ProcRateRow>),
+    DefeatedTargets(Vec<DefeatedTargetRow>),
+}
+
+#[derive(Serialize, ToSchema)]
+struct TableReport {
+    table_name: TableNames,
+    // Only set for DPSIntervals, the interval in seconds used
+    interval: Option<usize>,
+    rows: TableRows,
+}
+
+#[derive(Serialize, ToSchema)]
+struct MonitorReport {
+    now: i64,
+    stats: SessionStats,
+    messages: Vec<MonitorMessage>,
+}
+
+pub fn scope() -> actix_web::Scope {
+    web::scope(API_ROOT)
+        .service(sessions)
+        .service(summary)
+        .service(damage_by_power)
+        .service(damage_table)
+        .service(powers_and_mobs)
+        .service(mob_ranks_report)
+        .service(death_recap_report)
+        .service(debuff_effectiveness_report)
+        .service(consumables_report)
+        .service(location_timeline_report)
+        .service(progression_report)
+        .service(market_report)
+        .service(team_roster_report)
+        .service(compare)
+        .service(benchmarks)
+        .service(monitor_messages)
+        .service(monitor_events)
+        .service(openapi_document)
+}
+
+/// Parsed sessions, newest first
+#[utoipa::path(
+    get,
+    path = "/api/v1/sessions",
+    params(SessionsQuery),
+    responses(
+        (status = 200, description = "Sessions matching every filter", body = Vec<SessionEntry>),
+        (status = 400, description = "Invalid query", body = ApiError)
+    )
+)]
+#[get("/sessions")]
+async fn sessions(req: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
+    let query: SessionsQuery = match parse_query(&req) {
+        Ok(q) => q,
+        Err(e) => return e,
+    };
+    let log_dir = query.log_dir.as_ref().map(PathBuf::from);
+    let filters = IndexFilters {
+        player_name: query.player_name.as_ref(),
+        log_dir: log_dir.as_ref(),
+        log_file: None,
+        tag: query.tag.as_ref(),
+        text: query.text.as_ref(),
+        teammate: query.teammate.as_ref(),
+        from_date: query.from_date.as_ref(),
+        to_date: query.to_date.as_ref(),
+        min_data_points: query.min_data_points,
+    };
+    if let Err(e) = filters.validate() {
+        return api_error(&e);
+    }
+    let cache = index_handler::find_all_summaries(&context.output_dir);
+    HttpResponse::Ok().json(index_handler::filter_sessions(&cache, &filters))
+}
+
+/// Totals for one session
+#[utoipa::path(
+    get,
+    path = "/api/v1/summary",
+    params(SummaryQuery),
+    responses(
+        (status = 200, description = "Session totals", body = SummaryReport),
+        (status = 400, description = "Invalid query", body = ApiError),
+        (status = 404, description = "No such report or session", body = ApiError)
+    )
+)]
+#[get("/summary")]
+async fn summary(req: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
+    let mut query: SummaryQuery = match parse_query(&req) {
+        Ok(q) => q,
+        Err(e) => return e,
+    };
+    query.db_path = match find_report(&context, &query.report) {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let mut conn = db::get_file_conn(query.db_path.clone().into());
+    let summary = match db::queries::get_summary(&mut conn, query.key).pop() {
+        Some(s) => s,
+        None => return not_found(format!("No session {} in report {}", query.key, query.report)),
+    };
+    let report = SummaryReport {
+        rewards_defeats: db::queries::get_rewards_defeats(&mut conn, summary.summary_key, &summary.player_name),
+        total_damage: db::queries::get_total_damage_report(&mut conn, summary.summary_key),
+        damage_taken: db::queries::get_damage_taken_report(&mut conn, summary.summary_key),
+        session_stats: db::queries::get_session_stats(&mut conn, summary.summary_key),
+        deaths: db::queries::get_death_count(&mut conn, summary.summary_key),
+        benchmark: db::queries::get_benchmark_report(&mut conn, summary.summary_key),
+        summary,
+    };
+    HttpResponse::Ok().json(report)
+}
+
+/// Attack summary by power, takes the same sort and table actions as the summary page
+#[utoipa::path(
+    get,
+    path = "/api/v1/damage_by_power",
+    params(DamageByPowerQuery),
+    responses(
+        (status = 200, description = "One row per power", body = Vec<PowerRow>),
+        (status = 400, description = "Invalid query", body = ApiError),
+        (status = 404, description = "No such report", body = ApiError)
+    )
+)]
+#[get("/damage_by_power")]
+async fn damage_by_power(req: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
+    let qs_non_strict = serde_qs::Config::new(5, false);
+    let mut query: DamageByPowerQuery = match qs_non_strict.deserialize_str(req.query_string()) {
+        Ok(q) => q,
+        Err(e) => return bad_request(e.to_string()),
+    };
+    query.db_path = match find_report(&context, &query.report) {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    match damage_by_power_table::rows(&context, &query) {
+        Ok(rows) => HttpResponse::Ok().json(rows),
+        Err(e) => api_error(&e),
+    }
+}
+
+/// One of the damage tables from the summary page, picked with table_name
+#[utoipa::path(
+    get,
+    path = "/api/v1/damage_table",
+    params(TableQuery),
+    responses(
+        (status = 200, description = "Rows of the table", body = TableReport),
+        (status = 400, description = "Invalid query", body = ApiError),
+        (status = 404, description = "No such report or no data", body = ApiError)
+    )
+)]
+#[get("/damage_table")]
+async fn damage_table(req: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
+    let mut query: TableQuery = match parse_query(&req) {
+        Ok(q) => q,
+        Err(e) => return e,
+    };
+    query.db_path = match find_report(&context, &query.report) {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let table_name = match &query.table_name {
+        Some(t) => t,
+        None => return bad_request(String::from("table_name is required")),
+    };
+
+    let mut interval = None;
+    let rows = match table_name {
+        TableNames::DamageDealtByType => damage_dealt_by_type_table::rows(&query).map(TableRows::DamageDealtByType),
+        TableNames::DamageTakenByType => damage_taken_by_type_table::rows(&query).map(TableRows::DamageTakenByType),
+        TableNames::DamageTakenByMob => {
+            let mut conn = db::get_file_conn(query.db_path.clone().into());
+            let ranks = MobRanks::load(&mut conn, query.key, game_data::mob_level(&query.mob_level));
+            damage_taken_by_mob_table::rows(&query, &ranks).map(TableRows::DamageTakenByMob)
+        }
+        TableNames::DamageTakenByMobPower => {
+            damage_taken_by_mob_power_table::rows(&query).map(TableRows::DamageTakenByMobPower)
+        }
+        TableNames::DPSIntervals => dps_interval_table::rows(&context, &query).map(|(seconds, rows)| {
+            interval = Some(seconds);
+            TableRows::DPSIntervals(rows)
+        }),
+        TableNames::ProcRates => proc_rate_table::rows(&query).map(TableRows::ProcRates),
+        TableNames::DefeatedTargets => defeated_targets_table::rows(&query).map(TableRows::DefeatedTargets),
+    };
+
+    match rows {
+        Some(rows) => HttpResponse::Ok().json(TableReport {
+            table_name: query.table_name.unwrap(),
+            interval,
+            rows,
+        }),
+        None => not_found(format!("No {:?} data for session {}", table_name, query.key)),
+    }
+}
+
+/// Damage dealt to each mob by each power, filtered to one power_name or mob_name
+#[utoipa::path(
+    get,
+    path = "/api/v1/powers_and_mobs",
+    params(PowersMobsData),
+    responses(
+        (status = 200, description = "One row per power and mob", body = Vec<DamageDealtToMobByPower>),
+        (status = 400, description = "Invalid query", body = ApiError),
+        (status = 404, description = "No such report", body = ApiError)
+    )
+)]
+#[get("/powers_and_mobs")]
+async fn powers_and_mobs(req: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
+    let mut query: PowersMobsData = match parse_query(&req) {
+        Ok(q) => q,
+        Err(e) => return e,
+    };
+    query.db_path = match find_report(&context, &query.report) {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    let rows = powers_and_mobs_table::rows(&query).map_or(Vec::new(), |(rows, _)| rows);
+    HttpResponse::Ok().json(rows)
+}
+
+/// Rank, level and HP used for each mob in the reports, at the mob_level given
+#[utoipa::path(
+    get,
+    path = "/api/v1/mob_ranks",
+    params(TableQuery),
+    responses(
+        (status = 200, description = "One row per mob", body = Vec<MobRankRow>),
+        (status = 400, description = "Invalid query", body = ApiError),
+        (status = 404, description = "No such report", body = ApiError)
+    )
+)]
+#[get("/mob_ranks")]
+async fn mob_ranks_report(req: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
+    let mut query: TableQuery = match parse_query(&req) {
+        Ok(q) => q,
+        Err(e) => return e,
+    };
+    query.db_path = match find_report(&context, &query.report) {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    HttpResponse::Ok().json(mob_ranks::rows(&query))
+}
+
+/// Each defeat of the player with the damage, heals and controls in the seconds before it
+#[utoipa::path(
+    get,
+    path = "/api/v1/death_recap",
+    params(DeathRecapQuery),
+    responses(
+        (status = 200, description = "One recap per defeat", body = DeathRecapReport),
+        (status = 400, description = "Invalid query", body = ApiError),
+        (status = 404, description = "No such report", body = ApiError)
+    )
+)]
+#[get("/death_recap")]
+async fn death_recap_report(req: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
+    let mut query: DeathRecapQuery = match parse_query(&req) {
+        Ok(q) => q,
+        Err(e) => return e,
+    };
+    query.db_path = match find_report(&context, &query.report) {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    HttpResponse::Ok().json(death_recap::report(&query))
+}
+
+/// Damage change while each debuff was on the target
+#[utoipa::path(
+    get,
+    path = "/api/v1/debuff_effectiveness",
+    params(DebuffEffectivenessQuery),
+    responses(
+        (status = 200, description = "Debuff and timeline rows", body = DebuffEffectivenessReport),
+        (status = 400, description = "Invalid query", body = ApiError),
+        (status = 404, description = "No such report, or no data", body = ApiError)
+    )
+)]
+#[get("/debuff_effectiveness")]
+async fn debuff_effectiveness_report(req: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
+    let mut query: DebuffEffectivenessQuery = match parse_query(&req) {
+        Ok(q) => q,
+        Err(e) => return e,
+    };
+    query.db_path = match find_report(&context, &query.report) {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    report_or_not_found(
+        debuff_effectiveness::report(&query),
+        format!("No damage or debuffs for session {}", query.key),
+    )
+}
+
+/// Inspirations, temp powers and incarnate powers used, with the damage taken before each use
+#[utoipa::path(
+    get,
+    path = "/api/v1/consumables",
+    params(ConsumablesQuery),
+    responses(
+        (status = 200, description = "Consumable uses", body = ConsumablesReport),
+        (status = 400, description = "Invalid query", body = ApiError),
+        (status = 404, description = "No such report, or no data", body = ApiError)
+    )
+)]
+#[get("/consumables")]
+async fn consumables_report(req: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
+    let mut query: ConsumablesQuery = match parse_query(&req) {
+        Ok(q) => q,
+        Err(e) => return e,
+    };
+    query.db_path = match find_report(&context, &query.report) {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    report_or_not_found(
+        consumables::report(&query),
+        format!("No consumables used for session {}", query.key),
+    )
+}
+
+/// Zones and missions visited, with the rewards and defeats in each
+#[utoipa::path(
+    get,
+    path = "/api/v1/location_timeline",
+    params(LocationTimelineQuery),
+    responses(
+        (status = 200, description = "Zone and mission segments", body = LocationTimelineReport),
+        (status = 400, description = "Invalid query", body = ApiError),
+        (status = 404, description = "No such report, or no data", body = ApiError)
+    )
+)]
+#[get("/location_timeline")]
+async fn location_timeline_report(req: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
+    let mut query: LocationTimelineQuery = match parse_query(&req) {
+        Ok(q) => q,
+        Err(e) => return e,
+    };
+    query.db_path = match find_report(&context, &query.report) {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    report_or_not_found(
+        location_timeline::report(&query),
+        format!("No zone or mission changes for session {}", query.key),
+    )
+}
+
+/// Experience and influence per hour and per level, and the history of the character
+#[utoipa::path(
+    get,
+    path = "/api/v1/progression",
+    params(ProgressionQuery),
+    responses(
+        (status = 200, description = "Progression of the session", body = ProgressionReport),
+        (status = 400, description = "Invalid query", body = ApiError),
+        (status = 404, description = "No such report, or no data", body = ApiError)
+    )
+)]
+#[get("/progression")]
+async fn progression_report(req: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
+    let mut query: ProgressionQuery = match parse_query(&req) {
+        Ok(q) => q,
+        Err(e) => return e,
+    };
+    query.db_path = match find_report(&context, &query.report) {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    report_or_not_found(
+        progression::report(&context, &query),
+        format!("No session {} in report {}", query.key, query.report),
+    )
+}
+
+/// Market income and spending, with reward influence and the history of the character
+#[utoipa::path(
+    get,
+    path = "/api/v1/market",
+    params(MarketQuery),
+    responses(
+        (status = 200, description = "Market activity of the session", body = MarketReport),
+        (status = 400, description = "Invalid query", body = ApiError),
+        (status = 404, description = "No such report, or no data", body = ApiError)
+    )
+)]
+#[get("/market")]
+async fn market_report(req: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
+    let mut query: MarketQuery = match parse_query(&req) {
+        Ok(q) => q,
+        Err(e) => return e,
+    };
+    query.db_path = match find_report(&context, &query.report) {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    report_or_not_found(
+        market::report(&context, &query),
+        format!("No session {} in report {}", query.key, query.report),
+    )
+}
+
+/// Teammates, pets and allies seen and what they did for the player
+#[utoipa::path(
+    get,
+    path = "/api/v1/team_roster",
+    params(TeamRosterQuery),
+    responses(
+        (status = 200, description = "Roster of the session", body = TeamRosterReport),
+        (status = 400, description = "Invalid query", body = ApiError),
+        (status = 404, description = "No such report, or no data", body = ApiError)
+    )
+)]
+#[get("/team_roster")]
+async fn team_roster_report(req: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
+    let mut query: TeamRosterQuery = match parse_query(&req) {
+        Ok(q) => q,
+        Err(e) => return e,
+    };
+    query.db_path = match find_report(&context, &query.report) {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    report_or_not_found(
+        team_roster::report(&context, &query),
+        format!("No teammates for session {}", query.key),
+    )
+}
+
+/// Two sessions side by side
+#[utoipa::path(
+    get,
+    path = "/api/v1/compare",
+    params(CompareQuery),
+    responses(
+        (status = 200, description = "Totals and tables of both sessions", body = CompareReport),
+        (status = 400, description = "Invalid query", body = ApiError),
+        (status = 404, description = "No such report or session", body = ApiError)
+    )
+)]
+#[get("/compare")]
+async fn compare(req: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
+    let mut query: CompareQuery = match parse_query(&req) {
+        Ok(q) => q,
+        Err(e) => return e,
+    };
+    query.left_db_path = match find_report(&context, &query.left_report) {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    query.right_db_path = match find_report(&context, &query.right_report) {
+        Ok(p) => p,
+        Err(e) => return e,
+    };
+    report_or_not_found(
+        compare_summaries::report(&query),
+        String::from("Unable to find one or both summaries"),
+    )
+}
+
+/// Leaderboard and history of one benchmark test, the first test by name when none is given
+#[utoipa::path(
+    get,
+    path = "/api/v1/benchmarks",
+    params(BenchmarkQuery),
+    responses(
+        (status = 200, description = "Leaderboard and trends", body = BenchmarkBoard),
+        (status = 400, description = "Invalid query", body = ApiError),
+        (status = 404, description = "No benchmarks have been run", body = ApiError)
+    )
+)]
+#[get("/benchmarks")]
+async fn benchmarks(req: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
+    let query: BenchmarkQuery = match parse_query(&req) {
+        Ok(q) => q,
+        Err(e) => return e,
+    };
+    report_or_not_found(
+        benchmark_handler::board(&context, &query),
+        String::from("No benchmarks have been run"),
+    )
+}
+
+/// Live stats and messages from the log monitor
+#[utoipa::path(
+    get,
+    path = "/api/v1/monitor_messages",
+    responses(
+        (status = 200, description = "Current monitor state", body = MonitorReport)
+    )
+)]
+#[get("/monitor_messages")]
+async fn monitor_messages() -> impl Responder {
+    HttpResponse::Ok().json(monitor_report())
+}
+
+/// Server-sent events pushing the monitor state each time the monitor reads the log, for overlays and other tools
+#[utoipa::path(
+    get,
+    path = "/api/v1/monitor_events",
+    responses(
+        (status = 200, description = "A monitor event with the current state when the stream opens and after every update",
+            body = MonitorReport, content_type = "text/event-stream")
+    )
+)]
+#[get("/monitor_events")]
+async fn monitor_events() -> impl Responder {
+    monitor_stream::event_stream("monitor", || {
+        serde_json::to_string(&monitor_report()).unwrap_or_default()
+    })
+}
+
+fn monitor_report() -> MonitorReport {
+    let (now, stats, messages) = monitoring::get_messages();
+    MonitorReport {
+        now: now.timestamp(),
+        stats,
+        messages,
+    }
+}
+
+/// This document
+#[utoipa::path(
+    get,
+    path = "/api/v1/openapi.json",
+    responses(
+        (status = 200, description = "OpenAPI document for the api")
+    )
+)]
+#[get("/openapi.json")]
+async fn openapi_document() -> impl Responder {
+    HttpResponse::Ok().json(ApiDoc::openapi())
+}
+
+fn parse_query<T: DeserializeOwned>(req: &HttpRequest) -> Result<T, HttpResponse> {
+    web::Query::<T>::from_query(req.query_string())
+        .map(|q| q.into_inner())
+        .map_err(|e| bad_request(e.to_string()))
+}
+
+// Only reports in the index can be opened
+fn find_report(context: &AppContext, report: &str) -> Result<String, HttpResponse> {
+    super::find_report(context, report).map_err(|e| api_error(&e))
+}
+
+// Same status as the web pages, as json
+fn api_error(e: &CyclopsError) -> HttpResponse {
+    HttpResponse::build(e.status_code()).json(ApiError { error: e.to_string() })
+}
+
+fn bad_request(error: String) -> HttpResponse {
+    HttpResponse::BadRequest().json(ApiError { error })
+}
+
+fn not_found(error: String) -> HttpResponse {
+    HttpResponse::NotFound().json(ApiError { error })
+}
+
+fn report_or_not_found<T: Serialize>(report: Option<T>, error: String) -> HttpResponse {
+    match report {
+        Some(report) => HttpResponse::Ok().json(report),
+        None => not_found(error),
+    }
+}
@@ -0,0 +1,136 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use tera::Context;
+use utoipa::ToSchema;
+
+use crate::AppContext;
+
+use super::index_handler::{self, BenchmarkEntry};
+use super::web_structs_enums::BenchmarkQuery;
+
+#[derive(Serialize, Debug, ToSchema)]
+struct LeaderboardRow {
+    rank: usize,
+    player_name: String,
+    runs: usize,
+    best: BenchmarkEntry,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+struct TrendRow {
+    entry: BenchmarkEntry,
+    dps_change: Option<i32>,
+    dps_change_percent: Option<String>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+struct PlayerTrend {
+    player_name: String,
+    runs: Vec<TrendRow>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct BenchmarkBoard {
+    test_names: Vec<String>,
+    test_name: String,
+    leaderboard: Vec<LeaderboardRow>,
+    // Trends are only for this player when one is picked
+    #[serde(skip_serializing_if = "Option::is_none")]
+    player_name: Option<String>,
+    trends: Vec<PlayerTrend>,
+}
+
+pub fn process(app_context: &AppContext, context: &mut Context, query: &BenchmarkQuery) {
+    if let Some(board) = board(app_context, query) {
+        super::insert_report(context, &board);
+    }
+}
+
+// None when no benchmarks have been run
+pub fn board(app_context: &AppContext, query: &BenchmarkQuery) -> Option<BenchmarkBoard> {
+    let cache = index_handler::find_all_summaries(&app_context.output_dir);
+
+    let mut test_names: Vec<String> = cache
+        .benchmarks
+        .iter()
+        .map(|b| b.report.test_name.clone())
+        .collect();
+    test_names.sort();
+    test_names.dedup();
+
+    let test_name = match &query.test_name {
+        Some(name) => name.clone(),
+        None => test_names.first()?.clone(),
+    };
+
+    // Runs are already in date order from the index cache
+    let mut by_player = BTreeMap::<String, Vec<BenchmarkEntry>>::new();
+    for b in cache
+        .benchmarks
+        .into_iter()
+        .filter(|b| b.report.test_name == test_name)
+    {
+        by_player
+            .entry(b.report.player_name.clone())
+            .or_default()
+            .push(b);
+    }
+
+    let leaderboard = leaderboard(&by_player);
+    if let Some(player_name) = &query.player_name {
+        by_player.retain(|p, _| p == player_name);
+    }
+    Some(BenchmarkBoard {
+        test_names,
+        test_name,
+        leaderboard,
+        player_name: query.player_name.clone(),
+        trends: trends(by_player),
+    })
+}
+
+fn leaderboard(by_player: &BTreeMap<String, Vec<BenchmarkEntry>>) -> Vec<LeaderboardRow> {
+    let mut rows: Vec<LeaderboardRow> = by_player
+        .iter()
+        .filter_map(|(player_name, runs)| {
+            runs.iter()
+                .max_by_key(|r| r.report.sustained_dps)
+                .map(|best| LeaderboardRow {
+                    rank: 0,
+                    player_name: player_name.clone(),
+                    runs: runs.len(),
+                    best: best.clone(),
+                })
+        })
+        .collect();
+
+    rows.sort_by_key(|r| Reverse(r.best.report.sustained_dps));
+    for (i, row) in rows.iter_mut().enumerate() {
+        row.rank = i + 1;
+    }
+    rows
+}
+
+fn trends(by_player: BTreeMap<String, Vec<BenchmarkEntry>>) -> Vec<PlayerTrend> {
+    let mut result = Vec::<PlayerTrend>::new();
+
+    for (player_name, runs) in by_player {
+        let mut previous: Option<i32> = None;
+        let mut rows = Vec::<TrendRow>::new();
+        for entry in runs {
+            let dps = entry.report.sustained_dps;
+            rows.push(TrendRow {
+                dps_change: previous.map(|p| dps - p),
+                dps_change_percent: previous
+                    .filter(|p| *p != 0)
+                    .map(|p| format!("{:+.1}%", (dps - p) as f64 / p as f64 * 100.0)),
+                entry,
+            });
+            previous = Some(dps);
+        }
+        result.push(PlayerTrend { player_name, runs: rows });
+    }
+    result
+}
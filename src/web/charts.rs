@@ -0,0 +1,377 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::path::PathBuf;
+
+use crate::db;
+use crate::models::DamageTimeline;
+
+use super::web_structs_enums::{ChartNames, ChartQuery};
+
+const WIDTH: f64 = 800.0;
+const HEIGHT: f64 = 320.0;
+const MARGIN_LEFT: f64 = 70.0;
+const MARGIN_RIGHT: f64 = 20.0;
+const MARGIN_TOP: f64 = 40.0;
+const MARGIN_BOTTOM: f64 = 75.0;
+const TEXT_COLOR: &str = "#e67e22";
+const AXIS_COLOR: &str = "#dddddd";
+const BACKGROUND_COLOR: &str = "#1b283b";
+const PALETTE: [&str; 8] = [
+    "#1abc9c", "#3498db", "#9b59b6", "#f1c40f", "#e67e22", "#e74c3c", "#95a5a6", "#2ecc71",
+];
+
+const DPS_BUCKET_SECONDS: i32 = 10;
+const MINUTE_BUCKET_SECONDS: i32 = 60;
+
+struct Series {
+    name: String,
+    color: String,
+    values: Vec<f64>,
+}
+
+pub fn file_name(query: &ChartQuery) -> String {
+    format!("{:?}_{}.svg", query.chart, query.key)
+}
+
+pub fn render(query: &ChartQuery) -> String {
+    let db_path: PathBuf = query.db_path.clone().into();
+    let mut conn = db::get_file_conn(db_path);
+    // The id lets the summary page close an inline chart like the tables
+    let id = format!("{:?}_chart", query.chart);
+
+    match query.chart {
+        ChartNames::DpsOverTime => {
+            let data = db::queries::get_damage_timeline(&mut conn, query.key, DPS_BUCKET_SECONDS);
+            let mut totals = BTreeMap::<i32, f64>::new();
+            for d in data.iter().filter(|d| d.outgoing == 1) {
+                *totals.entry(d.bucket).or_default() += d.damage as f64;
+            }
+            let values = fill_buckets(&totals)
+                .into_iter()
+                .map(|v| v / DPS_BUCKET_SECONDS as f64)
+                .collect();
+            let series = vec![Series {
+                name: String::from("DPS"),
+                color: String::from(PALETTE[0]),
+                values,
+            }];
+            line_chart(
+                &id,
+                &format!("DPS Over Time ({} second buckets)", DPS_BUCKET_SECONDS),
+                "DPS",
+                DPS_BUCKET_SECONDS as f64 / 60.0,
+                &series,
+            )
+        }
+        ChartNames::DamageByType => {
+            let data =
+                db::queries::get_damage_timeline(&mut conn, query.key, MINUTE_BUCKET_SECONDS);
+            stacked_bar_chart(
+                &id,
+                "Damage Dealt By Type Per Minute",
+                "Damage",
+                &damage_type_series(&data, 1),
+            )
+        }
+        ChartNames::IncomingDamage => {
+            let data =
+                db::queries::get_damage_timeline(&mut conn, query.key, MINUTE_BUCKET_SECONDS);
+            stacked_bar_chart(
+                &id,
+                "Damage Taken Per Minute",
+                "Damage",
+                &damage_type_series(&data, 0),
+            )
+        }
+        ChartNames::Rewards => {
+            let data = db::queries::get_reward_timeline(&mut conn, query.key);
+            let mut experience = BTreeMap::<i32, f64>::new();
+            let mut influence = BTreeMap::<i32, f64>::new();
+            for r in data {
+                let minute = r.elapsed / MINUTE_BUCKET_SECONDS;
+                *experience.entry(minute).or_default() += r.experience as f64;
+                *influence.entry(minute).or_default() += r.influence as f64;
+            }
+            let series = vec![
+                Series {
+                    name: String::from("Experience"),
+                    color: String::from(PALETTE[1]),
+                    values: cumulative(fill_buckets(&experience)),
+                },
+                Series {
+                    name: String::from("Influence"),
+                    color: String::from(PALETTE[3]),
+                    values: cumulative(fill_buckets(&influence)),
+                },
+            ];
+            line_chart(
+                &id,
+                "Cumulative Experience And Influence",
+                "Total",
+                1.0,
+                &series,
+            )
+        }
+    }
+}
+
+// Buckets with no data become zero so gaps show up in the chart
+fn fill_buckets(data: &BTreeMap<i32, f64>) -> Vec<f64> {
+    match data.keys().last() {
+        Some(last) => (0..=*last).map(|b| *data.get(&b).unwrap_or(&0.0)).collect(),
+        None => Vec::new(),
+    }
+}
+
+fn cumulative(values: Vec<f64>) -> Vec<f64> {
+    values
+        .into_iter()
+        .scan(0.0, |total, v| {
+            *total += v;
+            Some(*total)
+        })
+        .collect()
+}
+
+fn damage_type_series(data: &[DamageTimeline], outgoing: i32) -> Vec<Series> {
+    let mut by_type = BTreeMap::<String, BTreeMap<i32, f64>>::new();
+    let mut last_bucket = -1;
+    for d in data.iter().filter(|d| d.outgoing == outgoing) {
+        *by_type
+            .entry(d.damage_type.clone())
+            .or_default()
+            .entry(d.bucket)
+            .or_default() += d.damage as f64;
+        last_bucket = last_bucket.max(d.bucket);
+    }
+
+    by_type
+        .into_iter()
+        .enumerate()
+        .map(|(i, (name, buckets))| Series {
+            color: damage_type_color(&name, i),
+            values: (0..=last_bucket)
+                .map(|b| *buckets.get(&b).unwrap_or(&0.0))
+                .collect(),
+            name,
+        })
+        .collect()
+}
+
+fn damage_type_color(damage_type: &str, index: usize) -> String {
+    let color = match damage_type {
+        "Smashing" => "#bdc3c7",
+        "Lethal" => "#7f8c8d",
+        "Fire" => "#e74c3c",
+        "Cold" => "#5dade2",
+        "Energy" => "#f4d03f",
+        "NegativeEnergy" => "#8e44ad",
+        "Psionic" => "#ff66cc",
+        "Toxic" => "#58d68d",
+        _ => PALETTE[index % PALETTE.len()],
+    };
+    String::from(color)
+}
+
+fn plot_width() -> f64 {
+    WIDTH - MARGIN_LEFT - MARGIN_RIGHT
+}
+
+fn plot_height() -> f64 {
+    HEIGHT - MARGIN_TOP - MARGIN_BOTTOM
+}
+
+// Rounds up to 1, 2 or 5 times a power of ten so the axis ticks are readable
+fn nice_max(value: f64) -> f64 {
+    if value <= 0.0 {
+        return 1.0;
+    }
+    let magnitude = 10f64.powf(value.log10().floor());
+    let normalized = value / magnitude;
+    let nice = if normalized <= 1.0 {
+        1.0
+    } else if normalized <= 2.0 {
+        2.0
+    } else if normalized <= 5.0 {
+        5.0
+    } else {
+        10.0
+    };
+    nice * magnitude
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn chart_frame(
+    id: &str,
+    title: &str,
+    y_label: &str,
+    x_max: f64,
+    y_max: f64,
+    series: &[Series],
+    body: &str,
+) -> String {
+    let mut svg = String::new();
+    let bottom = MARGIN_TOP + plot_height();
+    let right = MARGIN_LEFT + plot_width();
+
+    let _ = write!(
+        svg,
+        r#"<svg xmlns="http://www.w3.org/2000/svg" id="{id}" width="{WIDTH}" height="{HEIGHT}" viewBox="0 0 {WIDTH} {HEIGHT}" font-family="Arial" font-size="12">"#
+    );
+    let _ = write!(
+        svg,
+        r#"<rect width="100%" height="100%" fill="{BACKGROUND_COLOR}"/>"#
+    );
+    let _ = write!(
+        svg,
+        r#"<text x="{}" y="22" fill="{TEXT_COLOR}" font-size="16" font-weight="bold" text-anchor="middle">{}</text>"#,
+        WIDTH / 2.0,
+        escape(title)
+    );
+
+    if series.is_empty() || x_max <= 0.0 {
+        let _ = write!(
+            svg,
+            r#"<text x="{}" y="{}" fill="{TEXT_COLOR}" text-anchor="middle">NO DATA</text></svg>"#,
+            WIDTH / 2.0,
+            HEIGHT / 2.0
+        );
+        return svg;
+    }
+
+    for i in 0..=5 {
+        let value = y_max * i as f64 / 5.0;
+        let y = bottom - plot_height() * i as f64 / 5.0;
+        let _ = write!(
+            svg,
+            r#"<line x1="{MARGIN_LEFT}" y1="{y:.1}" x2="{right}" y2="{y:.1}" stroke="{AXIS_COLOR}" stroke-opacity="0.2"/><text x="{}" y="{:.1}" fill="{TEXT_COLOR}" text-anchor="end">{}</text>"#,
+            MARGIN_LEFT - 6.0,
+            y + 4.0,
+            value.round()
+        );
+    }
+    for i in 0..=6 {
+        let value = x_max * i as f64 / 6.0;
+        let x = MARGIN_LEFT + plot_width() * i as f64 / 6.0;
+        let _ = write!(
+            svg,
+            r#"<text x="{x:.1}" y="{}" fill="{TEXT_COLOR}" text-anchor="middle">{:.1}</text>"#,
+            bottom + 16.0,
+            value
+        );
+    }
+
+    svg.push_str(body);
+
+    let _ = write!(
+        svg,
+        r#"<line x1="{MARGIN_LEFT}" y1="{bottom}" x2="{right}" y2="{bottom}" stroke="{AXIS_COLOR}"/><line x1="{MARGIN_LEFT}" y1="{MARGIN_TOP}" x2="{MARGIN_LEFT}" y2="{bottom}" stroke="{AXIS_COLOR}"/>"#
+    );
+    let _ = write!(
+        svg,
+        r#"<text x="{}" y="{}" fill="{TEXT_COLOR}" text-anchor="middle">Minutes</text>"#,
+        MARGIN_LEFT + plot_width() / 2.0,
+        bottom + 34.0
+    );
+    let _ = write!(
+        svg,
+        r#"<text x="16" y="{}" fill="{TEXT_COLOR}" text-anchor="middle" transform="rotate(-90 16 {})">{}</text>"#,
+        MARGIN_TOP + plot_height() / 2.0,
+        MARGIN_TOP + plot_height() / 2.0,
+        escape(y_label)
+    );
+
+    let mut legend_x = MARGIN_LEFT;
+    for s in series {
+        let _ = write!(
+            svg,
+            r#"<rect x="{legend_x:.1}" y="{}" width="12" height="12" fill="{}"/><text x="{:.1}" y="{}" fill="{TEXT_COLOR}">{}</text>"#,
+            HEIGHT - 22.0,
+            s.color,
+            legend_x + 16.0,
+            HEIGHT - 12.0,
+            escape(&s.name)
+        );
+        legend_x += 30.0 + 7.0 * s.name.len() as f64;
+    }
+
+    svg.push_str("</svg>");
+    svg
+}
+
+fn line_chart(id: &str, title: &str, y_label: &str, x_step: f64, series: &[Series]) -> String {
+    let points = series.iter().map(|s| s.values.len()).max().unwrap_or(0);
+    let x_max = (points.max(2) - 1) as f64 * x_step;
+    let y_max = nice_max(
+        series
+            .iter()
+            .flat_map(|s| s.values.iter())
+            .cloned()
+            .fold(0.0, f64::max),
+    );
+    let bottom = MARGIN_TOP + plot_height();
+
+    let mut body = String::new();
+    for s in series {
+        let coordinates: Vec<String> = s
+            .values
+            .iter()
+            .enumerate()
+            .map(|(i, v)| {
+                let x = MARGIN_LEFT + plot_width() * (i as f64 * x_step) / x_max;
+                let y = bottom - plot_height() * v / y_max;
+                format!("{:.1},{:.1}", x, y)
+            })
+            .collect();
+        let _ = write!(
+            body,
+            r#"<polyline fill="none" stroke="{}" stroke-width="2" points="{}"/>"#,
+            s.color,
+            coordinates.join(" ")
+        );
+    }
+
+    let series = if points == 0 { &[] } else { series };
+    chart_frame(id, title, y_label, x_max, y_max, series, &body)
+}
+
+fn stacked_bar_chart(id: &str, title: &str, y_label: &str, series: &[Series]) -> String {
+    let buckets = series.iter().map(|s| s.values.len()).max().unwrap_or(0);
+    let totals: Vec<f64> = (0..buckets)
+        .map(|b| series.iter().map(|s| s.values.get(b).unwrap_or(&0.0)).sum())
+        .collect();
+    let y_max = nice_max(totals.iter().cloned().fold(0.0, f64::max));
+    let bar_width = plot_width() / buckets.max(1) as f64;
+    let bottom = MARGIN_TOP + plot_height();
+
+    let mut body = String::new();
+    for b in 0..buckets {
+        let mut y = bottom;
+        for s in series {
+            let value = *s.values.get(b).unwrap_or(&0.0);
+            if value <= 0.0 {
+                continue;
+            }
+            let height = plot_height() * value / y_max;
+            y -= height;
+            let _ = write!(
+                body,
+                r#"<rect x="{:.1}" y="{y:.1}" width="{:.1}" height="{height:.1}" fill="{}"><title>{} minute {}: {}</title></rect>"#,
+                MARGIN_LEFT + bar_width * b as f64,
+                (bar_width * 0.9).max(1.0),
+                s.color,
+                escape(&s.name),
+                b,
+                value
+            );
+        }
+    }
+
+    chart_frame(id, title, y_label, buckets as f64, y_max, series, &body)
+}
@@ -0,0 +1,284 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
+use serde::Serialize;
+use tera::Context;
+use utoipa::ToSchema;
+
+use crate::db;
+use crate::models::Summary;
+
+use super::web_structs_enums::{CompareQuery, DamageByPowerQuery, TableQuery};
+
+#[derive(Serialize, Debug, ToSchema)]
+struct CompareCell {
+    left: String,
+    right: String,
+    delta: String,
+    delta_percent: String,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+struct CompareRow {
+    name: String,
+    // Both, Left or Right, used to highlight rows found in only one summary
+    present_in: &'static str,
+    cells: Vec<CompareCell>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+struct CompareTable {
+    title: &'static str,
+    headers: Vec<&'static str>,
+    rows: Vec<CompareRow>,
+}
+
+struct Side<'a> {
+    key: i32,
+    report: &'a str,
+    db_path: &'a str,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct CompareReport {
+    left: Summary,
+    right: Summary,
+    left_report: String,
+    right_report: String,
+    tables: Vec<CompareTable>,
+}
+
+pub fn process(context: &mut Context, query: &CompareQuery) -> bool {
+    match report(query) {
+        Some(report) => {
+            super::insert_report(context, &report);
+            true
+        }
+        None => false,
+    }
+}
+
+// None when either summary is missing
+pub fn report(query: &CompareQuery) -> Option<CompareReport> {
+    let left = Side {
+        key: query.left_key,
+        report: &query.left_report,
+        db_path: &query.left_db_path,
+    };
+    let right = Side {
+        key: query.right_key,
+        report: &query.right_report,
+        db_path: &query.right_db_path,
+    };
+
+    let left_summary = load_summary(&left)?;
+    let right_summary = load_summary(&right)?;
+
+    let tables = vec![
+        totals_table(&left, &right, &left_summary, &right_summary),
+        damage_by_power_table(&left, &right),
+        damage_taken_by_type_table(&left, &right),
+        damage_taken_by_mob_table(&left, &right),
+    ];
+    Some(CompareReport {
+        left: left_summary,
+        right: right_summary,
+        left_report: left.report.to_string(),
+        right_report: right.report.to_string(),
+        tables,
+    })
+}
+
+fn load_summary(side: &Side) -> Option<Summary> {
+    let mut conn = db::get_file_conn(PathBuf::from(side.db_path));
+    db::queries::get_summary(&mut conn, side.key).pop()
+}
+
+fn totals(side: &Side, summary: &Summary) -> Vec<(&'static str, i64)> {
+    let mut conn = db::get_file_conn(PathBuf::from(side.db_path));
+    let rewards = db::queries::get_rewards_defeats(&mut conn, side.key, &summary.player_name);
+
+    let mut result = Vec::new();
+    if let Some(damage) = db::queries::get_total_damage_report(&mut conn, side.key) {
+        result.extend([
+            ("Total Attacks", damage.activations as i64),
+            ("Total Hits", damage.hits as i64),
+            ("Total Misses", damage.misses as i64),
+            ("Total Damage", damage.total_damage as i64),
+            ("Direct Damage", damage.direct_damage as i64),
+            ("DoT Damage", damage.dot_damage as i64),
+            ("Critical Damage", damage.critical_damage as i64),
+        ]);
+    }
+    result.extend([
+        ("Experience", rewards.experience as i64),
+        ("Influence", rewards.influence as i64),
+        ("Mobs Defeated", rewards.mobs_defeated as i64),
+    ]);
+    if let Some(taken) = db::queries::get_damage_taken_report(&mut conn, side.key) {
+        result.push(("Total Damage Taken", taken.total_damage_taken as i64));
+        result.push(("Damage Taken Per Hit", taken.damage_per_hit as i64));
+    }
+    result
+}
+
+fn totals_table(left: &Side, right: &Side, left_summary: &Summary, right_summary: &Summary) -> CompareTable {
+    let mut left_values = BTreeMap::<String, Vec<i64>>::new();
+    let mut right_values = BTreeMap::<String, Vec<i64>>::new();
+    let mut order = Vec::<String>::new();
+
+    for (name, value) in totals(left, left_summary) {
+        order.push(name.to_string());
+        left_values.insert(name.to_string(), vec![value]);
+    }
+    for (name, value) in totals(right, right_summary) {
+        if !order.iter().any(|n| n == name) {
+            order.push(name.to_string());
+        }
+        right_values.insert(name.to_string(), vec![value]);
+    }
+
+    CompareTable {
+        title: "Totals",
+        headers: vec!["Value"],
+        rows: order
+            .iter()
+            .map(|name| compare_row(name, left_values.get(name), right_values.get(name), 1))
+            .collect(),
+    }
+}
+
+fn damage_by_power(side: &Side) -> BTreeMap<String, Vec<i64>> {
+    let query = DamageByPowerQuery {
+        key: side.key,
+        report: side.report.to_string(),
+        db_path: side.db_path.to_string(),
+        sort_field: None,
+        sort_dir: None,
+        action: None,
+        power_row: None,
+        mob_level: None,
+        view_name: None,
+        rule: None,
+    };
+    db::queries::get_damage_by_power_report(&query)
+        .into_iter()
+        .map(|p| {
+            (
+                p.power_name,
+                vec![
+                    p.activations as i64,
+                    p.hits as i64,
+                    p.power_total_damage as i64,
+                    p.dpa.unwrap_or(0) as i64,
+                ],
+            )
+        })
+        .collect()
+}
+
+fn damage_by_power_table(left: &Side, right: &Side) -> CompareTable {
+    CompareTable {
+        title: "Damage By Power",
+        headers: vec!["Activations", "Hits", "Total Damage", "DPA"],
+        rows: merge_rows(&damage_by_power(left), &damage_by_power(right), 4),
+    }
+}
+
+fn table_query(side: &Side) -> TableQuery {
+    TableQuery {
+        key: side.key,
+        report: side.report.to_string(),
+        db_path: side.db_path.to_string(),
+        table_name: None,
+        sort_field: None,
+        sort_dir: None,
+        interval: None,
+        mob_level: None,
+    }
+}
+
+fn damage_taken_by_type(side: &Side) -> BTreeMap<String, Vec<i64>> {
+    db::queries::get_damage_taken_by_type_query(&table_query(side))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|d| (d.damage_type, vec![d.total_damage as i64]))
+        .collect()
+}
+
+fn damage_taken_by_type_table(left: &Side, right: &Side) -> CompareTable {
+    CompareTable {
+        title: "Damage Taken By Type",
+        headers: vec!["Total Damage"],
+        rows: merge_rows(&damage_taken_by_type(left), &damage_taken_by_type(right), 1),
+    }
+}
+
+fn damage_taken_by_mob(side: &Side) -> BTreeMap<String, Vec<i64>> {
+    db::queries::get_damage_taken_by_mob_query(&table_query(side))
+        .unwrap_or_default()
+        .into_iter()
+        .map(|d| (d.source_name, vec![d.hits as i64, d.total_damage as i64]))
+        .collect()
+}
+
+fn damage_taken_by_mob_table(left: &Side, right: &Side) -> CompareTable {
+    CompareTable {
+        title: "Damage Taken By Mob",
+        headers: vec!["Hits", "Total Damage"],
+        rows: merge_rows(&damage_taken_by_mob(left), &damage_taken_by_mob(right), 2),
+    }
+}
+
+// Rows are matched by name, a row missing from one side is flagged
+fn merge_rows(
+    left: &BTreeMap<String, Vec<i64>>,
+    right: &BTreeMap<String, Vec<i64>>,
+    width: usize,
+) -> Vec<CompareRow> {
+    let mut names: Vec<&String> = left.keys().chain(right.keys()).collect();
+    names.sort();
+    names.dedup();
+
+    names
+        .into_iter()
+        .map(|name| compare_row(name, left.get(name), right.get(name), width))
+        .collect()
+}
+
+fn compare_row(name: &str, left: Option<&Vec<i64>>, right: Option<&Vec<i64>>, width: usize) -> CompareRow {
+    let present_in = match (left, right) {
+        (Some(_), Some(_)) => "Both",
+        (Some(_), None) => "Left",
+        _ => "Right",
+    };
+
+    let cells = (0..width)
+        .map(|i| {
+            let l = left.and_then(|v| v.get(i)).copied();
+            let r = right.and_then(|v| v.get(i)).copied();
+            compare_cell(l, r)
+        })
+        .collect();
+
+    CompareRow {
+        name: name.to_string(),
+        present_in,
+        cells,
+    }
+}
+
+fn compare_cell(left: Option<i64>, right: Option<i64>) -> CompareCell {
+    let delta = right.unwrap_or(0) - left.unwrap_or(0);
+    let delta_percent = match left {
+        Some(l) if l != 0 => format!("{:+.1}%", delta as f64 / l as f64 * 100.0),
+        _ => String::from("n/a"),
+    };
+
+    CompareCell {
+        left: left.map_or(String::from("-"), |v| v.to_string()),
+        right: right.map_or(String::from("-"), |v| v.to_string()),
+        delta: format!("{:+}", delta),
+        delta_percent,
+    }
+}
@@ -0,0 +1,173 @@
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+
+use chrono::{DateTime, FixedOffset};
+use serde::Serialize;
+use tera::Context;
+use utoipa::ToSchema;
+
+use crate::{db, web::web_structs_enums::ConsumablesQuery};
+
+use super::dates::{format_minutes, parse_date};
+
+const DEFAULT_SPIKE_SECONDS: i64 = 10;
+// Damage taken before a use is a spike when it is at least this many times the session's average
+const SPIKE_FACTOR: f64 = 2.0;
+
+#[derive(Serialize, Debug, ToSchema)]
+struct TypeRow {
+    consumable_type: String,
+    uses: usize,
+    powers: usize,
+    after_spike: usize,
+    after_spike_percentage: String,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+struct PowerRow {
+    power_name: String,
+    consumable_type: String,
+    uses: usize,
+    after_spike: usize,
+    first_used: String,
+    last_used: String,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+struct UseRow {
+    elapsed: String,
+    power_name: String,
+    consumable_type: String,
+    damage_taken: i32,
+    spike: bool,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ConsumablesReport {
+    // Damage taken in this many seconds before a use is checked for a spike
+    seconds: i64,
+    average_window_damage: i64,
+    type_rows: Vec<TypeRow>,
+    power_rows: Vec<PowerRow>,
+    use_rows: Vec<UseRow>,
+}
+
+pub fn process(context: &mut Context, query: &ConsumablesQuery) {
+    context.insert("seconds", &spike_seconds(query));
+    if let Some(report) = report(query) {
+        super::insert_report(context, &report);
+    }
+}
+
+fn spike_seconds(query: &ConsumablesQuery) -> i64 {
+    query.seconds.map_or(DEFAULT_SPIKE_SECONDS, |s| s as i64).max(1)
+}
+
+// None when the session is missing or nothing was used
+pub fn report(query: &ConsumablesQuery) -> Option<ConsumablesReport> {
+    let seconds = spike_seconds(query);
+    let db_path: PathBuf = query.db_path.clone().into();
+    let mut conn = db::get_file_conn(db_path);
+
+    let binding = db::queries::get_summary(&mut conn, query.key);
+    let summary = binding.first()?;
+    let consumables = db::queries::get_consumable_activations(&mut conn, query.key);
+    if consumables.is_empty() {
+        return None;
+    }
+
+    let start = parse_date(&summary.log_date);
+    let incoming: Vec<(DateTime<FixedOffset>, i32)> = db::queries::get_incoming_damage_actions(&mut conn, query.key)
+        .iter()
+        .map(|d| (parse_date(&d.log_date), d.damage))
+        .collect();
+
+    // Average damage taken per window, counting only windows where damage was taken
+    let mut windows = HashMap::<i64, i64>::new();
+    for (date, damage) in &incoming {
+        *windows.entry((*date - start).num_seconds() / seconds).or_default() += *damage as i64;
+    }
+    let average_window = if windows.is_empty() {
+        0.0
+    } else {
+        windows.values().sum::<i64>() as f64 / windows.len() as f64
+    };
+
+    let use_rows: Vec<UseRow> = consumables
+        .iter()
+        .map(|c| {
+            let used = parse_date(&c.log_date);
+            let damage_taken: i32 = incoming
+                .iter()
+                .filter(|(date, _)| {
+                    let age = (used - *date).num_seconds();
+                    (0..=seconds).contains(&age)
+                })
+                .map(|(_, damage)| damage)
+                .sum();
+            UseRow {
+                elapsed: format_minutes((used - start).num_seconds()),
+                power_name: c.power_name.clone(),
+                consumable_type: c.consumable_type.clone(),
+                damage_taken,
+                spike: average_window > 0.0 && damage_taken as f64 >= average_window * SPIKE_FACTOR,
+            }
+        })
+        .collect();
+
+    Some(ConsumablesReport {
+        seconds,
+        average_window_damage: average_window.round() as i64,
+        type_rows: type_rows(&use_rows),
+        power_rows: power_rows(&use_rows),
+        use_rows,
+    })
+}
+
+fn type_rows(uses: &[UseRow]) -> Vec<TypeRow> {
+    let mut groups = BTreeMap::<&str, Vec<&UseRow>>::new();
+    for u in uses {
+        groups.entry(u.consumable_type.as_str()).or_default().push(u);
+    }
+
+    groups
+        .into_iter()
+        .map(|(consumable_type, rows)| {
+            let mut powers: Vec<&str> = rows.iter().map(|r| r.power_name.as_str()).collect();
+            powers.sort();
+            powers.dedup();
+            let after_spike = rows.iter().filter(|r| r.spike).count();
+            TypeRow {
+                consumable_type: consumable_type.to_string(),
+                uses: rows.len(),
+                powers: powers.len(),
+                after_spike,
+                after_spike_percentage: format!("{:.1}", after_spike as f64 / rows.len() as f64 * 100.0),
+            }
+        })
+        .collect()
+}
+
+fn power_rows(uses: &[UseRow]) -> Vec<PowerRow> {
+    let mut groups = BTreeMap::<(&str, &str), Vec<&UseRow>>::new();
+    for u in uses {
+        groups
+            .entry((u.power_name.as_str(), u.consumable_type.as_str()))
+            .or_default()
+            .push(u);
+    }
+
+    let mut rows: Vec<PowerRow> = groups
+        .into_iter()
+        .map(|((power_name, consumable_type), rows)| PowerRow {
+            power_name: power_name.to_string(),
+            consumable_type: consumable_type.to_string(),
+            uses: rows.len(),
+            after_spike: rows.iter().filter(|r| r.spike).count(),
+            first_used: rows.first().map(|r| r.elapsed.clone()).unwrap_or_default(),
+            last_used: rows.last().map(|r| r.elapsed.clone()).unwrap_or_default(),
+        })
+        .collect();
+    rows.sort_by(|a, b| b.uses.cmp(&a.uses).then(a.power_name.cmp(&b.power_name)));
+    rows
+}
@@ -5,23 +5,36 @@ use serde::Deserialize;
 use serde::Serialize;
 use strsim::jaro_winkler;
 use tera::Context;
+use utoipa::ToSchema;
 
 use crate::db;
+use crate::error::CyclopsError;
 use crate::game_data;
-use crate::game_data::get_mob_hp;
+use crate::AppContext;
 
 use lazy_static::lazy_static;
 
+use super::mob_ranks::MobRanks;
+use super::power_views::{self, PowerEdit};
+use super::preferences;
 use super::web_structs_enums::DamageByPowerQuery;
 use super::web_structs_enums::PowerTableActions;
 use super::web_structs_enums::SortDirection;
 
 lazy_static! {
-    static ref ROW_STATE: Mutex<HashMap<i32, Vec<PowerRow>>> = Mutex::new(HashMap::new());
+    // Keyed by report id and summary key
+    static ref ROW_STATE: Mutex<HashMap<(String, i32), TableState>> = Mutex::new(HashMap::new());
 }
 
-#[derive(Clone, Serialize, Deserialize, Debug)]
-struct PowerRow {
+// The rows shown and the edits made since the table was generated, the edits are what a view saves
+#[derive(Clone, Debug, Default)]
+struct TableState {
+    rows: Vec<PowerRow>,
+    edits: Vec<PowerEdit>,
+}
+
+#[derive(Clone, Serialize, Deserialize, Debug, ToSchema)]
+pub struct PowerRow {
     power_name: String,
     activations: i32,
     proc_fires: i32, 
@@ -71,7 +84,12 @@ impl PowerRow {
     }
 }
 
-pub fn process(tera_context: &mut Context, query: &DamageByPowerQuery) {
+pub fn process(
+    app_context: &AppContext,
+    tera_context: &mut Context,
+    query: &DamageByPowerQuery,
+) -> Result<(), CyclopsError> {
+    let rows = rows(app_context, query)?;
     match &query.sort_dir {
         Some(dir) => match dir {
             SortDirection::ASC => tera_context.insert("sort_dir", &SortDirection::DESC),
@@ -82,38 +100,48 @@ pub fn process(tera_context: &mut Context, query: &DamageByPowerQuery) {
 
     tera_context.insert("table_title", &"Attack Summary By Power");
     tera_context.insert("headers", &headers());
-    if query.mob_level.is_some() {
-        tera_context.insert(
-            "mob_level",
-            &i32::from_str_radix(&query.mob_level.as_ref().unwrap(), 10).unwrap(),
-        );
-    } else {
-        tera_context.insert("mob_level", &54);
-    }
+    tera_context.insert("mob_level", &mob_level(query));
     tera_context.insert("mob_levels", &game_data::MINION_HP_TABLE.as_slice());
+    tera_context.insert("table_rows", &rows);
+    let views: Vec<String> = power_views::load(query.db_path.as_ref(), query.key)
+        .into_keys()
+        .collect();
+    tera_context.insert("views", &views);
+    tera_context.insert("merge_rules", &merge_rules(app_context, query));
+    Ok(())
+}
 
-    let mut rows = retrieve_copy(query);
+fn mob_level(query: &DamageByPowerQuery) -> i32 {
+    game_data::mob_level(&query.mob_level)
+}
 
-    match &query.mob_level {
-        Some(level) => {
-            rows.iter_mut()
-                .for_each(|r| r.overkill = calc_overkill(r.dph, get_mob_hp(&level).into()));
-        }
-        None => {
-            rows.iter_mut().for_each(|r| {
-                r.overkill = calc_overkill(r.dph, get_mob_hp(&"54".to_string()).into())
-            });
-        }
+// Applies the table action and sort, the result is kept as the table state for the summary
+pub fn rows(app_context: &AppContext, query: &DamageByPowerQuery) -> Result<Vec<PowerRow>, CyclopsError> {
+    // Checked before any state is built or cached for the key
+    if db::queries::get_total_damage(query).is_none() {
+        return Err(CyclopsError::NotFound(format!(
+            "No session {} in report {}",
+            query.key, query.report
+        )));
     }
+    let mob_level = mob_level(query);
+    let mut state = retrieve_copy(app_context, query);
 
-    if query.action.is_some() {
-        rows = handle_action(query, rows);
+    if let Some(action) = &query.action {
+        state = handle_action(app_context, query, action, state);
     }
+    let mut rows = state.rows.clone();
+
+    // Overkill uses the HP of the targets each power hit, by rank and level
+    let target_hp = power_target_hp(query, mob_level);
+    rows.iter_mut().for_each(|r| {
+        r.overkill = calc_overkill(r.dph, average_target_hp(&target_hp, &r.power_name, mob_level))
+    });
 
     match &query.sort_field {
         Some(field) => sort(
             field,
-            query.sort_dir.clone().unwrap(),
+            query.sort_dir.clone().unwrap_or(SortDirection::DESC),
             &mut rows,
         ),
         None => sort(
@@ -123,32 +151,37 @@ pub fn process(tera_context: &mut Context, query: &DamageByPowerQuery) {
         ) 
     }
 
-    update_cache(query.key, rows.clone());
-
-    tera_context.insert("table_rows", &rows);
+    state.rows = rows.clone();
+    update_cache(query, state);
+    Ok(rows)
 }
 
-fn retrieve_copy(query: &DamageByPowerQuery) -> Vec<PowerRow> {
-    match ROW_STATE.lock() {
-        Ok(mut row_map) => match row_map.get_mut(&query.key) {
-            Some(rows) => rows.clone(),
-            None => {
-                let powers = generate_power_rows(query);
-                row_map.insert(query.key, powers.clone());
-                powers
-            }
-        },
+// The lock is let go while a new state is read from the db, so one slow report doesn't hold up the others
+fn retrieve_copy(app_context: &AppContext, query: &DamageByPowerQuery) -> TableState {
+    let cache_key = (query.report.clone(), query.key);
+    let cached = match ROW_STATE.lock() {
+        Ok(row_map) => row_map.get(&cache_key).cloned(),
         Err(_) => {
             println!("Unable to lock row cache. Very bad! Return empty list.");
-            return Vec::<PowerRow>::new();
+            return TableState::default();
+        }
+    };
+    match cached {
+        Some(state) => state,
+        None => {
+            let state = generate_state(app_context, query);
+            match ROW_STATE.lock() {
+                Ok(mut row_map) => row_map.entry(cache_key).or_insert(state).clone(),
+                Err(_) => state,
+            }
         }
     }
 }
 
-fn update_cache(key: i32, rows: Vec<PowerRow>) {
+fn update_cache(query: &DamageByPowerQuery, state: TableState) {
     match ROW_STATE.lock() {
         Ok(mut row_map) => {
-            row_map.insert(key, rows);
+            row_map.insert((query.report.clone(), query.key), state);
         }
         Err(_) => {
             println!("Unable to lock row cache. Very bad! Return empty list.");
@@ -156,42 +189,150 @@ fn update_cache(key: i32, rows: Vec<PowerRow>) {
     }
 }
 
-fn handle_action(query: &DamageByPowerQuery, rows: Vec<PowerRow>) -> Vec<PowerRow> {
-    match query.action.as_ref().unwrap() {
-        PowerTableActions::Revert => generate_power_rows(query),
-        PowerTableActions::RemoveNonDamaging => {
-            rows.into_iter().filter(|r| r.total_damage > 0).collect()
-        }
-        PowerTableActions::Merge => match &query.power_row {
-            Some(indexes) => {
-                let mut final_list = rows.clone();
-                let merged_rows: Vec<PowerRow> = indexes
-                    .into_iter()
-                    .map(|i| final_list.get(*i as usize).unwrap().clone())
-                    .collect();
+// The table as parsed with the merge rules of the character applied
+fn generate_state(app_context: &AppContext, query: &DamageByPowerQuery) -> TableState {
+    let mut rows = generate_power_rows(query);
+    for rule in merge_rules(app_context, query) {
+        rows = apply_merge_rule(&rule, rows);
+    }
+    TableState {
+        rows,
+        edits: Vec::new(),
+    }
+}
 
-                let mut retain_list: Vec<bool> = vec![true; rows.len()];
-                for i in indexes {
-                    let _ = std::mem::replace(&mut retain_list[*i as usize], false);
-                }
-                let mut index_iter = retain_list.iter();
-                final_list.retain(|_| *index_iter.next().unwrap());
+fn player_name(query: &DamageByPowerQuery) -> Option<String> {
+    let mut conn = db::get_file_conn(query.db_path.clone().into());
+    db::queries::get_summary(&mut conn, query.key)
+        .first()
+        .map(|s| s.player_name.clone())
+}
+
+fn merge_rules(app_context: &AppContext, query: &DamageByPowerQuery) -> Vec<Vec<String>> {
+    match player_name(query) {
+        Some(player_name) => preferences::get_merge_rules(app_context, &player_name),
+        None => Vec::new(),
+    }
+}
 
-                let mut new_row = PowerRow::new();
-                for r in merged_rows {
-                    merge_rows(&mut new_row, &r, &query.mob_level.as_ref().unwrap());
+fn handle_action(
+    app_context: &AppContext,
+    query: &DamageByPowerQuery,
+    action: &PowerTableActions,
+    mut state: TableState,
+) -> TableState {
+    let edit = match action {
+        PowerTableActions::Revert => return generate_state(app_context, query),
+        PowerTableActions::RemoveNonDamaging => PowerEdit::RemoveNonDamaging,
+        PowerTableActions::MergeGuess => PowerEdit::MergeGuess,
+        PowerTableActions::Merge => match selected_names(query, &state.rows) {
+            Some(names) => PowerEdit::Merge(names),
+            None => return state,
+        },
+        PowerTableActions::Delete => match selected_names(query, &state.rows) {
+            Some(names) => PowerEdit::Delete(names),
+            None => return state,
+        },
+        PowerTableActions::SaveView => {
+            if let Some(name) = view_name(query) {
+                power_views::save(query.db_path.as_ref(), query.key, &name, state.edits.clone());
+            }
+            return state;
+        }
+        PowerTableActions::LoadView => {
+            let edits = view_name(query)
+                .and_then(|name| power_views::load(query.db_path.as_ref(), query.key).remove(&name));
+            return match edits {
+                Some(edits) => {
+                    let mut view = generate_state(app_context, query);
+                    for edit in &edits {
+                        view.rows = apply_edit(edit, view.rows);
+                    }
+                    view.edits = edits;
+                    view
+                }
+                None => state,
+            };
+        }
+        PowerTableActions::DeleteView => {
+            if let Some(name) = view_name(query) {
+                power_views::delete(query.db_path.as_ref(), query.key, &name);
+            }
+            return state;
+        }
+        // The rule keeps the powers that make up the selected rows, so it also matches
+        // sessions where the powers are listed in a different order
+        PowerTableActions::SaveMergeRule => match selected_names(query, &state.rows) {
+            Some(names) => {
+                let mut powers: Vec<String> = names
+                    .iter()
+                    .flat_map(|n| n.split(','))
+                    .map(String::from)
+                    .collect();
+                powers.sort();
+                powers.dedup();
+                if powers.len() > 1 {
+                    if let Some(player_name) = player_name(query) {
+                        preferences::add_merge_rule(app_context, &player_name, powers);
+                    }
                 }
-                final_list.insert(0, new_row);
-                final_list
+                PowerEdit::Merge(names)
             }
-            None => rows.clone(),
+            None => return state,
         },
-        PowerTableActions::MergeGuess => {
+        PowerTableActions::DeleteMergeRule => {
+            if let (Some(rule), Some(player_name)) = (query.rule, player_name(query)) {
+                preferences::remove_merge_rule(app_context, &player_name, rule);
+            }
+            return state;
+        }
+    };
+
+    state.rows = apply_edit(&edit, state.rows);
+    state.edits.push(edit);
+    state
+}
+
+fn view_name(query: &DamageByPowerQuery) -> Option<String> {
+    query
+        .view_name
+        .as_deref()
+        .map(str::trim)
+        .filter(|name| !name.is_empty())
+        .map(String::from)
+}
+
+// Names of the checked rows from the form, rows no longer in the table are ignored
+fn selected_names(query: &DamageByPowerQuery, rows: &[PowerRow]) -> Option<Vec<String>> {
+    let names: Vec<String> = query
+        .power_row
+        .as_ref()?
+        .iter()
+        .filter_map(|i| rows.get(*i as usize))
+        .map(|r| r.power_name.clone())
+        .collect();
+    if names.is_empty() {
+        None
+    } else {
+        Some(names)
+    }
+}
+
+fn apply_edit(edit: &PowerEdit, rows: Vec<PowerRow>) -> Vec<PowerRow> {
+    match edit {
+        PowerEdit::RemoveNonDamaging => rows.into_iter().filter(|r| r.total_damage > 0).collect(),
+        PowerEdit::Merge(names) => {
+            let indexes = row_indexes(&rows, |r| names.contains(&r.power_name));
+            merge_indexes(rows, indexes)
+        }
+        PowerEdit::Delete(names) => rows
+            .into_iter()
+            .filter(|r| !names.contains(&r.power_name))
+            .collect(),
+        PowerEdit::MergeGuess => {
             let first_list = rows.clone();
             let mut second_list = rows.clone();
             let mut final_list = Vec::<PowerRow>::new();
-            let mob_level = query.mob_level.as_ref().unwrap();
-
             for r in first_list {
                 let matches: Vec<PowerRow> = second_list
                     .iter()
@@ -203,7 +344,7 @@ fn handle_action(query: &DamageByPowerQuery, rows: Vec<PowerRow>) -> Vec<PowerRo
                     let mut new_row = PowerRow::new();
                     for m in matches {
                         second_list.retain(|r| r.power_name != m.power_name);
-                        merge_rows(&mut new_row, &m, mob_level);
+                        merge_rows(&mut new_row, &m);
                     }
                     final_list.push(new_row);
                 }
@@ -213,23 +354,55 @@ fn handle_action(query: &DamageByPowerQuery, rows: Vec<PowerRow>) -> Vec<PowerRo
 
             final_list
         }
-        PowerTableActions::Delete => match &query.power_row {
-            Some(indexes) => {
-                let mut final_list = rows.clone();
-                let mut retain_list: Vec<bool> = vec![true; rows.len()];
-                for i in indexes {
-                    let _ = std::mem::replace(&mut retain_list[*i as usize], false);
-                }
-                let mut index_iter = retain_list.iter();
-                final_list.retain(|_| *index_iter.next().unwrap());
-                final_list
-            }
-            None => rows.clone(),
-        },
     }
 }
 
-fn merge_rows(first_row: &mut PowerRow, second_row: &PowerRow, mob_level: &String) {
+// Rows made only of powers in the rule are merged, merged rows join power names with a comma
+fn apply_merge_rule(rule: &[String], rows: Vec<PowerRow>) -> Vec<PowerRow> {
+    let indexes = row_indexes(&rows, |r| {
+        r.power_name.split(',').all(|p| rule.iter().any(|name| name == p))
+    });
+    if indexes.len() > 1 {
+        merge_indexes(rows, indexes)
+    } else {
+        rows
+    }
+}
+
+fn row_indexes(rows: &[PowerRow], matches: impl Fn(&PowerRow) -> bool) -> Vec<usize> {
+    rows.iter()
+        .enumerate()
+        .filter(|(_, r)| matches(r))
+        .map(|(i, _)| i)
+        .collect()
+}
+
+fn merge_indexes(rows: Vec<PowerRow>, indexes: Vec<usize>) -> Vec<PowerRow> {
+    if indexes.is_empty() {
+        return rows;
+    }
+    let mut final_list = rows.clone();
+    let merged_rows: Vec<PowerRow> = indexes
+        .iter()
+        .map(|i| final_list[*i].clone())
+        .collect();
+
+    let mut retain_list: Vec<bool> = vec![true; rows.len()];
+    for i in indexes {
+        let _ = std::mem::replace(&mut retain_list[i], false);
+    }
+    let mut index_iter = retain_list.iter();
+    final_list.retain(|_| *index_iter.next().unwrap());
+
+    let mut new_row = PowerRow::new();
+    for r in merged_rows {
+        merge_rows(&mut new_row, &r);
+    }
+    final_list.insert(0, new_row);
+    final_list
+}
+
+fn merge_rows(first_row: &mut PowerRow, second_row: &PowerRow) {
     // Careful here, make sure the new row data is updated before using it
     // in a later calculations
     if first_row.power_name == "" {
@@ -247,7 +420,6 @@ fn merge_rows(first_row: &mut PowerRow, second_row: &PowerRow, mob_level: &Strin
     first_row.total_damage_percent += second_row.total_damage_percent;
     first_row.dpa = calc_dpa(first_row.activations, first_row.total_damage);
     first_row.dph = calc_dph(first_row.hits, first_row.proc_fires, first_row.total_damage);
-    first_row.overkill = calc_overkill(first_row.dph, get_mob_hp(mob_level));
     first_row.ate = avg_ate(first_row.ate, second_row.ate);
     first_row.direct_damage += second_row.direct_damage;
     first_row.dot_damage += second_row.dot_damage;
@@ -291,6 +463,34 @@ fn calc_dph(hits: i32, procs: i32, total_damage: i32) -> Option<i32> {
     }
 }
 
+// Hits and summed target HP per power
+fn power_target_hp(query: &DamageByPowerQuery, mob_level: i32) -> HashMap<String, (i64, i64)> {
+    let mut conn = db::get_file_conn(query.db_path.clone().into());
+    let ranks = MobRanks::load(&mut conn, query.key, mob_level);
+    let mut result = HashMap::<String, (i64, i64)>::new();
+
+    for d in db::queries::get_damage_dealt_to_mobs(&mut conn, query.key) {
+        let hits = (d.hits + d.proc_fires.unwrap_or(0)) as i64;
+        let entry = result.entry(d.power_name).or_default();
+        entry.0 += hits;
+        entry.1 += hits * ranks.get(&d.target_name).hp as i64;
+    }
+    result
+}
+
+// Merged rows join power names with a comma
+fn average_target_hp(target_hp: &HashMap<String, (i64, i64)>, power_name: &str, mob_level: i32) -> i32 {
+    let (hits, hp) = power_name
+        .split(',')
+        .filter_map(|p| target_hp.get(p))
+        .fold((0, 0), |acc, (hits, hp)| (acc.0 + hits, acc.1 + hp));
+    if hits > 0 {
+        (hp / hits) as i32
+    } else {
+        game_data::get_mob_class_hp(game_data::MobClass::Minion, mob_level)
+    }
+}
+
 fn calc_overkill(dph: Option<i32>, mob_hp: i32) -> Option<i32> {
     if dph.is_some() && dph.unwrap() > 0 {
         Some(((dph.unwrap() as f32 / mob_hp as f32) * 100.0).round() as i32)
@@ -341,7 +541,7 @@ fn calc_average_recharge(first: Option<i32>, second: Option<i32>) -> Option<i32>
 
 fn generate_power_rows(query: &DamageByPowerQuery) -> Vec<PowerRow> {
     let powers = db::queries::get_damage_by_power_report(query);
-    let total_damage = db::queries::get_total_damage(query);
+    let total_damage = db::queries::get_total_damage(query).unwrap_or(0);
     let mut rows = Vec::<PowerRow>::new();
 
     for p in powers {
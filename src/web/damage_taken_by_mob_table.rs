@@ -1,10 +1,13 @@
 use tera::Context;
 
 use crate::db;
+use crate::game_data;
 use crate::models::DamageTakenByMob;
 use crate::web::TableQuery;
 use crate::web::SortDirection;
 
+use super::mob_ranks::MobRanks;
+
 pub fn process(context: &mut Context, query: &TableQuery) {
     match &query.sort_dir {
         Some(dir) => match dir {
@@ -13,27 +16,38 @@ pub fn process(context: &mut Context, query: &TableQuery) {
         },
         None => context.insert("sort_dir", &SortDirection::DESC),
     };
-    match db::queries::get_damage_taken_by_mob_query(&query) {
-        Some(mut data) => {
+    let mut conn = db::get_file_conn(query.db_path.clone().into());
+    let mob_level = game_data::mob_level(&query.mob_level);
+    let ranks = MobRanks::load(&mut conn, query.key, mob_level);
+    match rows(query, &ranks) {
+        Some(data) => {
             context.insert("table_title", "Damage Taken By Mob");
+            context.insert("mob_level", &mob_level);
             context.insert("table_name", &query.table_name);
             context.insert("headers", &headers());
-            if query.sort_field.is_some() {
-                sort(
-                    query.sort_field.clone().unwrap(),
-                    query.sort_dir.clone().unwrap(),
-                    &mut data,
-                );
-            }
-            context.insert("table_rows", &flatten(data));
+            context.insert("table_rows", &flatten(data, &ranks));
         }
         None => println!("Damage taken by type returned no data"),
     };
 }
 
+pub fn rows(query: &TableQuery, ranks: &MobRanks) -> Option<Vec<DamageTakenByMob>> {
+    let mut data = db::queries::get_damage_taken_by_mob_query(query)?;
+    if let Some(sort_field) = &query.sort_field {
+        sort(
+            sort_field.clone(),
+            query.sort_dir.clone().unwrap_or(SortDirection::DESC),
+            &mut data,
+            ranks,
+        );
+    }
+    Some(data)
+}
+
 pub fn headers() -> Vec<(&'static str, &'static str)> {
     let mut headers = Vec::<(&'static str, &'static str)>::new();
     headers.push(("source_name", "Mob"));
+    headers.push(("rank", "Rank"));
     headers.push(("hits", "Hits"));
     headers.push(("avg_hit_chance", "Average Hit Chance"));
     headers.push(("total_damage", "Total Damage"));
@@ -42,12 +56,14 @@ pub fn headers() -> Vec<(&'static str, &'static str)> {
     headers
 }
 
-pub fn flatten(data: Vec<DamageTakenByMob>) -> Vec<Vec<String>> {
+pub fn flatten(data: Vec<DamageTakenByMob>, ranks: &MobRanks) -> Vec<Vec<String>> {
     let mut result = Vec::<Vec<String>>::new();
 
     for d in data {
         let mut row = Vec::<String>::new();
+        let rank = ranks.get(&d.source_name).describe();
         row.push(d.source_name);
+        row.push(rank);
         row.push(d.hits.to_string());
         row.push(d.avg_hit_chance.to_string());
         row.push(d.total_damage.to_string());
@@ -57,12 +73,16 @@ pub fn flatten(data: Vec<DamageTakenByMob>) -> Vec<Vec<String>> {
     result
 }
 
-pub fn sort(sort_field: String, sort_dir: SortDirection, data: &mut Vec<DamageTakenByMob>) {
+pub fn sort(sort_field: String, sort_dir: SortDirection, data: &mut [DamageTakenByMob], ranks: &MobRanks) {
     match sort_field.as_str() {
         "source_name" => match sort_dir {
             SortDirection::DESC => data.sort_by(|a, b| b.source_name.cmp(&a.source_name)),
             SortDirection::ASC => data.sort_by(|a, b| a.source_name.cmp(&b.source_name)),
         },
+        "rank" => match sort_dir {
+            SortDirection::DESC => data.sort_by_key(|a| std::cmp::Reverse(ranks.get(&a.source_name).hp)),
+            SortDirection::ASC => data.sort_by_key(|a| ranks.get(&a.source_name).hp),
+        },
         "hits" => match sort_dir {
             SortDirection::DESC => data.sort_by(|a, b| b.hits.cmp(&a.hits)),
             SortDirection::ASC => data.sort_by(|a, b| a.hits.cmp(&b.hits)),
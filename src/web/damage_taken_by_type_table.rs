@@ -12,24 +12,29 @@ pub fn process(context: &mut Context, query: &TableQuery) {
         },
         None => context.insert("sort_dir", &SortDirection::DESC),
     };
-    match db::queries::get_damage_taken_by_type_query(&query) {
-        Some(mut data) => {
+    match rows(query) {
+        Some(data) => {
             context.insert("table_title", "Damage Taken By Type");
             context.insert("table_name", &query.table_name);
             context.insert("headers", &headers());
-            if query.sort_field.is_some() {
-                sort(
-                    query.sort_field.clone().unwrap(),
-                    query.sort_dir.clone().unwrap(),
-                    &mut data,
-                );
-            }
             context.insert("table_rows", &flatten(data));
         }
         None => println!("Damage taken by type returned no data"),
     };
 }
 
+pub fn rows(query: &TableQuery) -> Option<Vec<DamageTakenByType>> {
+    let mut data = db::queries::get_damage_taken_by_type_query(query)?;
+    if let Some(sort_field) = &query.sort_field {
+        sort(
+            sort_field.clone(),
+            query.sort_dir.clone().unwrap_or(SortDirection::DESC),
+            &mut data,
+        );
+    }
+    Some(data)
+}
+
 pub fn headers() -> Vec<(&'static str, &'static str)> {
     let mut headers = Vec::<(&'static str, &'static str)>::new();
     headers.push(("damage_type", "Type"));
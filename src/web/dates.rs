@@ -0,0 +1,18 @@
+use chrono::{DateTime, FixedOffset};
+
+// Log dates are stored as rfc3339 strings
+pub fn parse_date(date: &str) -> DateTime<FixedOffset> {
+    DateTime::parse_from_rfc3339(date).unwrap()
+}
+
+// h:mm:ss, for session lengths and times into a session
+pub fn format_elapsed(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    format!("{}:{:02}:{:02}", seconds / 3600, (seconds % 3600) / 60, seconds % 60)
+}
+
+// m:ss, for short timelines
+pub fn format_minutes(seconds: i64) -> String {
+    let seconds = seconds.max(0);
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
@@ -0,0 +1,76 @@
+use std::path::PathBuf;
+
+use chrono::DateTime;
+use serde::Serialize;
+use tera::Context;
+use utoipa::ToSchema;
+
+use crate::{
+    db,
+    models::{DeathRecapControl, DeathRecapDamage, DeathRecapHeal, PlayerDeath},
+    web::web_structs_enums::DeathRecapQuery,
+};
+
+const DEFAULT_RECAP_SECONDS: i32 = 15;
+
+#[derive(Serialize, Debug, ToSchema)]
+struct DeathRecap {
+    death: PlayerDeath,
+    recovered_by: Option<String>,
+    seconds_defeated: Option<i64>,
+    total_damage: i32,
+    damage: Vec<DeathRecapDamage>,
+    heals: Vec<DeathRecapHeal>,
+    controls: Vec<DeathRecapControl>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct DeathRecapReport {
+    // Damage, heals and controls are from this many seconds before each defeat
+    seconds: i32,
+    recaps: Vec<DeathRecap>,
+}
+
+pub fn process(context: &mut Context, query: &DeathRecapQuery) {
+    super::insert_report(context, &report(query));
+}
+
+pub fn report(query: &DeathRecapQuery) -> DeathRecapReport {
+    let seconds = query.seconds.unwrap_or(DEFAULT_RECAP_SECONDS).max(1);
+    let db_path: PathBuf = query.db_path.clone().into();
+    let mut conn = db::get_file_conn(db_path);
+
+    let events = db::queries::get_player_deaths(&mut conn, query.key);
+    let mut recaps = Vec::<DeathRecap>::new();
+
+    for (i, event) in events.iter().enumerate() {
+        if event.event_type != "Defeated" {
+            continue;
+        }
+        // The next resurrection or awaken ends this death
+        let recovery = events
+            .get(i + 1)
+            .filter(|e| e.event_type != "Defeated");
+        let seconds_defeated = recovery.map(|r| {
+            let start = DateTime::parse_from_rfc3339(&event.log_date).unwrap();
+            let end = DateTime::parse_from_rfc3339(&r.log_date).unwrap();
+            (end - start).num_seconds()
+        });
+
+        let damage = db::queries::get_death_recap_damage(&mut conn, event, seconds);
+        recaps.push(DeathRecap {
+            death: event.clone(),
+            recovered_by: recovery.map(|r| match &r.source_name {
+                Some(name) => format!("{} ({})", r.event_type, name),
+                None => r.event_type.clone(),
+            }),
+            seconds_defeated,
+            total_damage: damage.iter().map(|d| d.total_damage).sum(),
+            damage,
+            heals: db::queries::get_death_recap_heals(&mut conn, event, seconds),
+            controls: db::queries::get_death_recap_controls(&mut conn, event, seconds),
+        });
+    }
+
+    DeathRecapReport { seconds, recaps }
+}
@@ -0,0 +1,280 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::PathBuf;
+
+use chrono::{DateTime, FixedOffset};
+use serde::Serialize;
+use tera::Context;
+use utoipa::ToSchema;
+
+use crate::{
+    db,
+    models::{DamageAction, DebuffAction},
+    web::web_structs_enums::DebuffEffectivenessQuery,
+};
+
+use super::dates::{format_minutes, parse_date};
+
+// Most resistance debuffs last 10 seconds or more
+const DEFAULT_DEBUFF_SECONDS: i64 = 10;
+const TIMELINE_BUCKET_SECONDS: i64 = 10;
+
+#[derive(Serialize, Debug, ToSchema)]
+struct DebuffRow {
+    power_name: String,
+    source_name: String,
+    debuff_type: String,
+    applications: usize,
+    ticks: usize,
+    multiplier: String,
+    damage_change: String,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+struct TimelineRow {
+    target_name: String,
+    elapsed: String,
+    ticks: usize,
+    multiplier: String,
+    debuffs: String,
+}
+
+struct Tick<'a> {
+    action: &'a DamageAction,
+    date: DateTime<FixedOffset>,
+    multiplier: f64,
+    // Indexes into the debuff list of debuffs active on the target for this tick
+    active: Vec<usize>,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct DebuffEffectivenessReport {
+    // How long a debuff is counted as active after it lands
+    seconds: i64,
+    undebuffed_ticks: usize,
+    debuff_rows: Vec<DebuffRow>,
+    timeline_rows: Vec<TimelineRow>,
+}
+
+pub fn process(context: &mut Context, query: &DebuffEffectivenessQuery) {
+    context.insert("seconds", &debuff_seconds(query));
+    if let Some(report) = report(query) {
+        super::insert_report(context, &report);
+    }
+}
+
+fn debuff_seconds(query: &DebuffEffectivenessQuery) -> i64 {
+    query.seconds.map_or(DEFAULT_DEBUFF_SECONDS, |s| s as i64).max(1)
+}
+
+// None when the session has no damage or no debuffs
+pub fn report(query: &DebuffEffectivenessQuery) -> Option<DebuffEffectivenessReport> {
+    let seconds = debuff_seconds(query);
+    let db_path: PathBuf = query.db_path.clone().into();
+    let mut conn = db::get_file_conn(db_path);
+
+    let actions = db::queries::get_outgoing_damage_ticks(&mut conn, query.key);
+    let debuffs = db::queries::get_debuff_actions(&mut conn, query.key);
+    if actions.is_empty() || debuffs.is_empty() {
+        return None;
+    }
+
+    let debuff_dates: Vec<DateTime<FixedOffset>> = debuffs.iter().map(|d| parse_date(&d.log_date)).collect();
+    let by_target = debuffs_by_target(&debuffs);
+    let mut ticks: Vec<Tick> = actions
+        .iter()
+        .map(|action| {
+            let date = parse_date(&action.log_date);
+            let active = match by_target.get(action.target_name.as_str()) {
+                Some(indexes) => active_debuffs(indexes, &debuffs, &debuff_dates, action, date, seconds),
+                None => Vec::new(),
+            };
+            Tick {
+                action,
+                date,
+                multiplier: 1.0,
+                active,
+            }
+        })
+        .collect();
+
+    let baselines = baselines(&ticks);
+    for t in ticks.iter_mut() {
+        if let Some(baseline) = baselines.get(&baseline_key(t.action)) {
+            if *baseline > 0.0 {
+                t.multiplier = t.action.damage as f64 / baseline;
+            }
+        }
+    }
+
+    let undebuffed: Vec<f64> = ticks
+        .iter()
+        .filter(|t| t.active.is_empty())
+        .map(|t| t.multiplier)
+        .collect();
+    let undebuffed_average = average(&undebuffed);
+
+    Some(DebuffEffectivenessReport {
+        seconds,
+        undebuffed_ticks: undebuffed.len(),
+        debuff_rows: debuff_rows(&ticks, &debuffs, undebuffed_average),
+        timeline_rows: timeline_rows(&ticks, &debuffs),
+    })
+}
+
+// Indexes into the debuff list for each target, in log order
+fn debuffs_by_target(debuffs: &[DebuffAction]) -> HashMap<&str, Vec<usize>> {
+    let mut by_target = HashMap::<&str, Vec<usize>>::new();
+    for (i, d) in debuffs.iter().enumerate() {
+        if let Some(target_name) = d.target_name.as_deref() {
+            by_target.entry(target_name).or_default().push(i);
+        }
+    }
+    for indexes in by_target.values_mut() {
+        indexes.sort_by_key(|i| debuffs[*i].line_number);
+    }
+    by_target
+}
+
+// Debuffs on the target logged before the action and no older than seconds.
+// Finds the last debuff before the action, then walks back until one is too old.
+fn active_debuffs(
+    indexes: &[usize],
+    debuffs: &[DebuffAction],
+    debuff_dates: &[DateTime<FixedOffset>],
+    action: &DamageAction,
+    date: DateTime<FixedOffset>,
+    seconds: i64,
+) -> Vec<usize> {
+    let end = indexes.partition_point(|i| debuffs[*i].line_number <= action.line_number);
+    let mut active: Vec<usize> = indexes[..end]
+        .iter()
+        .rev()
+        .take_while(|i| (date - debuff_dates[**i]).num_seconds() <= seconds)
+        .copied()
+        .collect();
+    active.reverse();
+    active
+}
+
+fn baseline_key(action: &DamageAction) -> (String, String, String) {
+    (
+        action.power_name.clone(),
+        action.damage_type.clone(),
+        action.damage_mode.clone(),
+    )
+}
+
+// The median tick with no debuff active is the baseline for a power and damage type.
+// Powers only used on debuffed targets fall back to their smallest tick.
+fn baselines(ticks: &[Tick]) -> HashMap<(String, String, String), f64> {
+    let mut clean = HashMap::<(String, String, String), Vec<i32>>::new();
+    let mut all = HashMap::<(String, String, String), Vec<i32>>::new();
+    for t in ticks {
+        let key = baseline_key(t.action);
+        if t.active.is_empty() {
+            clean.entry(key.clone()).or_default().push(t.action.damage);
+        }
+        all.entry(key).or_default().push(t.action.damage);
+    }
+
+    all.into_iter()
+        .map(|(key, values)| {
+            let baseline = match clean.get_mut(&key) {
+                Some(clean_values) => {
+                    clean_values.sort();
+                    clean_values[clean_values.len() / 2]
+                }
+                None => values.into_iter().min().unwrap_or(0),
+            };
+            (key, baseline as f64)
+        })
+        .collect()
+}
+
+fn average(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        None
+    } else {
+        Some(values.iter().sum::<f64>() / values.len() as f64)
+    }
+}
+
+fn debuff_rows(ticks: &[Tick], debuffs: &[DebuffAction], undebuffed_average: Option<f64>) -> Vec<DebuffRow> {
+    let mut groups = BTreeMap::<(String, String, String), (usize, Vec<f64>)>::new();
+    for d in debuffs {
+        groups
+            .entry(debuff_key(d))
+            .or_default()
+            .0 += 1;
+    }
+    for t in ticks {
+        let keys: BTreeSet<(String, String, String)> = t.active.iter().map(|i| debuff_key(&debuffs[*i])).collect();
+        for key in keys {
+            groups.entry(key).or_default().1.push(t.multiplier);
+        }
+    }
+
+    groups
+        .into_iter()
+        .map(|((power_name, source_name, debuff_type), (applications, multipliers))| {
+            let active_average = average(&multipliers);
+            DebuffRow {
+                power_name,
+                source_name,
+                debuff_type,
+                applications,
+                ticks: multipliers.len(),
+                multiplier: active_average.map_or(String::from("n/a"), |m| format!("{:.2}", m)),
+                damage_change: match (active_average, undebuffed_average) {
+                    (Some(active), Some(clean)) if clean > 0.0 => {
+                        format!("{:+.1}%", (active / clean - 1.0) * 100.0)
+                    }
+                    _ => String::from("n/a"),
+                },
+            }
+        })
+        .collect()
+}
+
+fn debuff_key(debuff: &DebuffAction) -> (String, String, String) {
+    (
+        debuff.power_name.clone().unwrap_or_default(),
+        debuff.source_name.clone(),
+        debuff.debuff_type.clone().unwrap_or_default(),
+    )
+}
+
+// Only targets that were debuffed are shown
+fn timeline_rows(ticks: &[Tick], debuffs: &[DebuffAction]) -> Vec<TimelineRow> {
+    let debuffed_targets: BTreeSet<&str> = debuffs.iter().filter_map(|d| d.target_name.as_deref()).collect();
+    let start = match ticks.first() {
+        Some(t) => t.date,
+        None => return Vec::new(),
+    };
+
+    let mut buckets = BTreeMap::<(&str, i64), (Vec<f64>, BTreeSet<&str>)>::new();
+    for t in ticks.iter().filter(|t| debuffed_targets.contains(t.action.target_name.as_str())) {
+        let bucket = (t.date - start).num_seconds() / TIMELINE_BUCKET_SECONDS;
+        let entry = buckets.entry((t.action.target_name.as_str(), bucket)).or_default();
+        entry.0.push(t.multiplier);
+        for i in &t.active {
+            if let Some(name) = debuffs[*i].power_name.as_deref() {
+                entry.1.insert(name);
+            }
+        }
+    }
+
+    buckets
+        .into_iter()
+        .map(|((target_name, bucket), (multipliers, active))| {
+            let seconds = bucket * TIMELINE_BUCKET_SECONDS;
+            TimelineRow {
+                target_name: target_name.to_string(),
+                elapsed: format_minutes(seconds),
+                ticks: multipliers.len(),
+                multiplier: average(&multipliers).map_or(String::from("n/a"), |m| format!("{:.2}", m)),
+                debuffs: active.into_iter().collect::<Vec<&str>>().join(", "),
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,149 @@
+use std::cmp::Reverse;
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use tera::Context;
+use utoipa::ToSchema;
+
+use crate::{
+    db, game_data,
+    web::{SortDirection, TableQuery},
+};
+
+use super::mob_ranks::MobRanks;
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct DefeatedTargetRow {
+    target_name: String,
+    rank: String,
+    hp: i32,
+    defeats: i32,
+    average_damage: i32,
+    average_ttk: i32,
+    fastest_ttk: i32,
+}
+
+pub fn process(context: &mut Context, query: &TableQuery) {
+    match &query.sort_dir {
+        Some(dir) => match dir {
+            SortDirection::ASC => context.insert("sort_dir", &SortDirection::DESC),
+            SortDirection::DESC => context.insert("sort_dir", &SortDirection::ASC),
+        },
+        None => context.insert("sort_dir", &SortDirection::DESC),
+    };
+
+    let rows = match rows(query) {
+        Some(rows) => rows,
+        None => {
+            println!("Defeated targets returned no data");
+            return;
+        }
+    };
+    context.insert("table_title", "Defeated Targets");
+    context.insert("table_name", &query.table_name);
+    context.insert("headers", &headers());
+    context.insert("table_rows", &flatten(rows));
+}
+
+pub fn rows(query: &TableQuery) -> Option<Vec<DefeatedTargetRow>> {
+    let mut conn = db::get_file_conn(query.db_path.clone().into());
+    let defeats = db::queries::get_defeated_target_damage(&mut conn, query.key);
+    if defeats.is_empty() {
+        return None;
+    }
+    let ranks = MobRanks::from_defeats(&defeats, game_data::mob_level(&query.mob_level));
+
+    let mut by_target = BTreeMap::<String, Vec<(i32, i32)>>::new();
+    for d in defeats {
+        by_target
+            .entry(d.target_name)
+            .or_default()
+            .push((d.total_damage, d.time_to_kill));
+    }
+
+    let mut rows: Vec<DefeatedTargetRow> = by_target
+        .into_iter()
+        .map(|(target_name, kills)| {
+            let rank = ranks.get(&target_name);
+            let defeats = kills.len() as i32;
+            DefeatedTargetRow {
+                rank: rank.describe(),
+                hp: rank.hp,
+                defeats,
+                average_damage: kills.iter().map(|k| k.0).sum::<i32>() / defeats,
+                average_ttk: kills.iter().map(|k| k.1).sum::<i32>() / defeats,
+                fastest_ttk: kills.iter().map(|k| k.1).min().unwrap_or(0),
+                target_name,
+            }
+        })
+        .collect();
+
+    if let Some(sort_field) = &query.sort_field {
+        sort(
+            sort_field.clone(),
+            query.sort_dir.clone().unwrap_or(SortDirection::DESC),
+            &mut rows,
+        );
+    }
+    Some(rows)
+}
+
+fn headers() -> Vec<(&'static str, &'static str)> {
+    vec![
+        ("target_name", "Target"),
+        ("rank", "Rank"),
+        ("hp", "HP"),
+        ("defeats", "Defeats"),
+        ("average_damage", "Average Damage To Defeat"),
+        ("average_ttk", "Average Time To Kill"),
+        ("fastest_ttk", "Fastest Time To Kill"),
+    ]
+}
+
+fn flatten(data: Vec<DefeatedTargetRow>) -> Vec<Vec<String>> {
+    let mut result = Vec::<Vec<String>>::new();
+
+    for d in data {
+        result.push(vec![
+            d.target_name,
+            d.rank,
+            d.hp.to_string(),
+            d.defeats.to_string(),
+            d.average_damage.to_string(),
+            d.average_ttk.to_string(),
+            d.fastest_ttk.to_string(),
+        ]);
+    }
+    result
+}
+
+fn sort(sort_field: String, sort_dir: SortDirection, data: &mut [DefeatedTargetRow]) {
+    match sort_field.as_str() {
+        "target_name" => match sort_dir {
+            SortDirection::DESC => data.sort_by(|a, b| b.target_name.cmp(&a.target_name)),
+            SortDirection::ASC => data.sort_by(|a, b| a.target_name.cmp(&b.target_name)),
+        },
+        // Rank sorts by HP so tougher ranks sort together
+        "rank" | "hp" => match sort_dir {
+            SortDirection::DESC => data.sort_by_key(|a| Reverse(a.hp)),
+            SortDirection::ASC => data.sort_by_key(|a| a.hp),
+        },
+        "defeats" => match sort_dir {
+            SortDirection::DESC => data.sort_by_key(|a| Reverse(a.defeats)),
+            SortDirection::ASC => data.sort_by_key(|a| a.defeats),
+        },
+        "average_damage" => match sort_dir {
+            SortDirection::DESC => data.sort_by_key(|a| Reverse(a.average_damage)),
+            SortDirection::ASC => data.sort_by_key(|a| a.average_damage),
+        },
+        "average_ttk" => match sort_dir {
+            SortDirection::DESC => data.sort_by_key(|a| Reverse(a.average_ttk)),
+            SortDirection::ASC => data.sort_by_key(|a| a.average_ttk),
+        },
+        "fastest_ttk" => match sort_dir {
+            SortDirection::DESC => data.sort_by_key(|a| Reverse(a.fastest_ttk)),
+            SortDirection::ASC => data.sort_by_key(|a| a.fastest_ttk),
+        },
+        _ => println!("Unknown sort field provided: {}", sort_field),
+    }
+}
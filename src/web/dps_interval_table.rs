@@ -1,13 +1,19 @@
 use std::time::Duration;
 
 use chrono::DateTime;
+use serde::Serialize;
 use tera::Context;
+use utoipa::ToSchema;
 
 use crate::db;
+use crate::models::{DamageIntervals, Summary};
+use crate::web::preferences;
 use crate::web::SortDirection;
 use crate::web::TableQuery;
 use crate::AppContext;
-struct Interval {
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct Interval {
     start_line: i32,
     end_line: i32,
     total_lines: i32,
@@ -25,37 +31,62 @@ pub fn process(app_context: &AppContext, tera_context: &mut Context, query: &Tab
         },
         None => tera_context.insert("sort_dir", &SortDirection::DESC),
     };
-    let mut dps_interval = generate_dps_report(&app_context, &query);
+    let (interval, dps_interval) = match rows(app_context, query) {
+        Some(r) => r,
+        None => return,
+    };
     tera_context.insert(
         "table_title",
-        &format!("DPS Using Interval Of {}", app_context.dps_interval),
+        &format!("DPS Using Interval Of {}", interval),
     );
     tera_context.insert("table_name", &query.table_name);
     tera_context.insert("headers", &headers());
-    if query.sort_field.is_some() {
+    tera_context.insert("table_rows", &flatten(dps_interval));
+}
+
+// The interval used and the damage in each interval
+pub fn rows(app_context: &AppContext, query: &TableQuery) -> Option<(usize, Vec<Interval>)> {
+    let mut conn = db::get_file_conn(query.db_path.clone().into());
+    let binding = db::queries::get_summary(&mut conn, query.key);
+    let summary = binding.first()?;
+
+    let interval = preferences::dps_interval(app_context, &summary.player_name, query.interval);
+
+    let damage_intervals =
+        db::queries::get_damage_intervals_query(&mut conn, query.key, interval as i32);
+    let mut dps_interval = generate_dps_report(summary, damage_intervals);
+    if let Some(sort_field) = &query.sort_field {
         sort(
-            query.sort_field.clone().unwrap(),
-            query.sort_dir.clone().unwrap(),
+            sort_field.clone(),
+            query.sort_dir.clone().unwrap_or(SortDirection::DESC),
             &mut dps_interval,
         );
     }
-    tera_context.insert("table_rows", &flatten(dps_interval));
+    Some((interval, dps_interval))
 }
 
-fn generate_dps_report(context: &AppContext, query: &TableQuery) -> Vec<Interval> {
-    let mut conn1 = db::get_file_conn(query.db_path.clone().into());
-    let binding = db::queries::get_summary(&mut conn1, query.key);
-    let summary = binding.get(0).unwrap();
-    let mut conn2 = db::get_file_conn(query.db_path.clone().into());
-    let damage_intervals =
-        db::queries::get_damage_intervals_query(&mut conn2, query.key, context.dps_interval as i32);
+// Only the summary page's interval selector saves its choice, every other request just reads it
+pub fn remember_interval(app_context: &AppContext, query: &TableQuery) {
+    let interval = match query.interval {
+        Some(interval) if interval > 0 => interval,
+        _ => return,
+    };
+    let mut conn = db::get_file_conn(query.db_path.clone().into());
+    if let Some(summary) = db::queries::get_summary(&mut conn, query.key).first() {
+        preferences::set_dps_interval(app_context, &summary.player_name, interval);
+    }
+}
+
+fn generate_dps_report(summary: &Summary, damage_intervals: Vec<Vec<DamageIntervals>>) -> Vec<Interval> {
     let line_count = summary.last_line_number - summary.first_line_number;
 
     let mut result = Vec::<Interval>::new();
 
     for intervals in damage_intervals {
-        let first_interval = intervals.first().unwrap();
-        let last_interval = intervals.last().unwrap();
+        let (first_interval, last_interval) = match (intervals.first(), intervals.last()) {
+            (Some(first), Some(last)) => (first, last),
+            _ => continue,
+        };
 
         let mut end_line: i32 = 0;
         if end_line < line_count {
@@ -66,12 +97,13 @@ fn generate_dps_report(context: &AppContext, query: &TableQuery) -> Vec<Interval
 
         let total_damage: i32 = intervals.iter().map(|i| i.damage).sum();
 
-        let elapsed_seconds = DateTime::parse_from_rfc3339(last_interval.log_date.as_str())
-            .unwrap()
-            .timestamp()
-            - DateTime::parse_from_rfc3339(first_interval.log_date.as_str())
-                .unwrap()
-                .timestamp();
+        let elapsed_seconds = match (
+            DateTime::parse_from_rfc3339(last_interval.log_date.as_str()),
+            DateTime::parse_from_rfc3339(first_interval.log_date.as_str()),
+        ) {
+            (Ok(last), Ok(first)) => last.timestamp() - first.timestamp(),
+            _ => continue,
+        };
         if elapsed_seconds > 0 {
             let elapsed_duration = Duration::from_secs(elapsed_seconds as u64).as_secs();
             let pretty_elapsed = format!(
@@ -0,0 +1,282 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use tera::Context;
+
+use crate::{assets, db, error::CyclopsError, AppContext};
+
+use super::{
+    charts, consumables, damage_by_power_table, death_recap, debuff_effectiveness,
+    location_timeline, market,
+    player_summary_table::{self, SummaryQuery},
+    powers_and_mobs_table, process_table, progression, team_roster,
+    web_structs_enums::{
+        ChartNames, ChartQuery, ConsumablesQuery, DamageByPowerQuery, DeathRecapQuery,
+        DebuffEffectivenessQuery, LocationTimelineQuery, MarketQuery, PowersMobsData,
+        ProgressionQuery, TableNames, TableQuery, TeamRosterQuery,
+    },
+};
+
+pub fn file_name(key: i32) -> String {
+    format!("summary_{}.html", key)
+}
+
+// The summary page with every table and chart filled in, the css and js are inlined
+// so the file can be shared and opened without the server
+pub fn render_report(context: &AppContext, query: &SummaryQuery) -> Result<String, CyclopsError> {
+    let mut report_context = Context::new();
+    if !player_summary_table::process(context, &mut report_context, query) {
+        return Err(CyclopsError::NotFound(format!(
+            "No session {} in report {}",
+            query.key, query.report
+        )));
+    }
+    report_context.insert("export", &true);
+    report_context.insert("inline_css", &assets::resource_string("cyclops.css"));
+    report_context.insert("inline_js", &assets::resource_string("cyclops.js"));
+    report_context.insert("sections", &render_sections(context, query));
+
+    Ok(context.tera.render("player_attack_report.html", &report_context)?)
+}
+
+// Writes an export of every session in the report next to its database
+pub fn write_report_files(context: &AppContext, db_path: &Path) {
+    let report = db::report_id(&context.output_dir, db_path).unwrap_or_default();
+    let mut conn = db::get_file_conn(db_path.to_path_buf());
+    let report_dir = db_path.parent().map(Path::to_path_buf).unwrap_or_default();
+
+    for summary in db::queries::get_summaries(&mut conn) {
+        let query = SummaryQuery {
+            key: summary.summary_key,
+            report: report.clone(),
+            db_path: db_path.to_string_lossy().to_string(),
+        };
+        let export_path: PathBuf = report_dir.join(file_name(summary.summary_key));
+        match render_report(context, &query) {
+            Ok(data) => match fs::write(&export_path, data) {
+                Ok(_) => println!("Exported report: {:?}", export_path),
+                Err(e) => println!("Unable to write export {:?}: {:?}", export_path, e),
+            },
+            Err(e) => println!("Unable to export session {} of {:?}: {}", summary.summary_key, db_path, e),
+        }
+    }
+}
+
+// Each table the summary page loads with htmx, keyed the same way as its div on the page
+fn render_sections(context: &AppContext, query: &SummaryQuery) -> HashMap<String, String> {
+    let key = query.key;
+    let report = query.report.clone();
+    let db_path = query.db_path.clone();
+    let mut sections = HashMap::new();
+
+    let mut table_context = Context::new();
+    let result = damage_by_power_table::process(
+        context,
+        &mut table_context,
+        &DamageByPowerQuery {
+            key,
+            report: report.clone(),
+            db_path: db_path.clone(),
+            sort_field: None,
+            sort_dir: None,
+            action: None,
+            power_row: None,
+            mob_level: None,
+            view_name: None,
+            rule: None,
+        },
+    );
+    match result {
+        Ok(()) => {
+            sections.insert(String::from("dbp"), render(context, "damage_by_power.html", &table_context));
+        }
+        Err(e) => {
+            println!("Unable to export the attack summary by power: {}", e);
+            sections.insert(String::from("dbp"), String::from("NO DATA"));
+        }
+    }
+
+    let tables = [
+        TableNames::DPSIntervals,
+        TableNames::DamageDealtByType,
+        TableNames::DamageTakenByType,
+        TableNames::DamageTakenByMob,
+        TableNames::DamageTakenByMobPower,
+        TableNames::DefeatedTargets,
+        TableNames::ProcRates,
+    ];
+    for table_name in tables {
+        let section = format!("{:?}", table_name);
+        let mut table_context = Context::new();
+        process_table(
+            context,
+            &mut table_context,
+            &table_name,
+            &TableQuery {
+                key,
+                report: report.clone(),
+                db_path: db_path.clone(),
+                table_name: Some(table_name.clone()),
+                sort_field: None,
+                sort_dir: None,
+                interval: None,
+                mob_level: None,
+            },
+        );
+        sections.insert(section, render(context, "simple_table.html", &table_context));
+    }
+
+    let charts = [
+        ChartNames::DpsOverTime,
+        ChartNames::DamageByType,
+        ChartNames::Rewards,
+        ChartNames::IncomingDamage,
+    ];
+    for chart in charts {
+        let section = format!("{:?}", chart);
+        let svg = charts::render(&ChartQuery {
+            key,
+            report: report.clone(),
+            db_path: db_path.clone(),
+            chart,
+            download: None,
+        });
+        sections.insert(section, svg);
+    }
+
+    let mut debuff_context = Context::new();
+    debuff_effectiveness::process(
+        &mut debuff_context,
+        &DebuffEffectivenessQuery {
+            key,
+            report: report.clone(),
+            db_path: db_path.clone(),
+            seconds: None,
+        },
+    );
+    sections.insert(
+        String::from("debuff_effectiveness"),
+        render(context, "debuff_effectiveness.html", &debuff_context),
+    );
+
+    let mut timeline_context = Context::new();
+    location_timeline::process(
+        &mut timeline_context,
+        &LocationTimelineQuery {
+            key,
+            report: report.clone(),
+            db_path: db_path.clone(),
+        },
+    );
+    sections.insert(
+        String::from("location_timeline"),
+        render(context, "location_timeline.html", &timeline_context),
+    );
+
+    let mut roster_context = Context::new();
+    team_roster::process(
+        context,
+        &mut roster_context,
+        &TeamRosterQuery {
+            key,
+            report: report.clone(),
+            db_path: db_path.clone(),
+        },
+    );
+    sections.insert(
+        String::from("team_roster"),
+        render(context, "team_roster.html", &roster_context),
+    );
+
+    let mut progression_context = Context::new();
+    progression::process(
+        context,
+        &mut progression_context,
+        &ProgressionQuery {
+            key,
+            report: report.clone(),
+            db_path: db_path.clone(),
+        },
+    );
+    sections.insert(
+        String::from("progression"),
+        render(context, "progression.html", &progression_context),
+    );
+
+    let mut market_context = Context::new();
+    market::process(
+        context,
+        &mut market_context,
+        &MarketQuery {
+            key,
+            report: report.clone(),
+            db_path: db_path.clone(),
+        },
+    );
+    sections.insert(String::from("market"), render(context, "market.html", &market_context));
+
+    let mut consumables_context = Context::new();
+    consumables::process(
+        &mut consumables_context,
+        &ConsumablesQuery {
+            key,
+            report: report.clone(),
+            db_path: db_path.clone(),
+            seconds: None,
+        },
+    );
+    sections.insert(
+        String::from("consumables"),
+        render(context, "consumables.html", &consumables_context),
+    );
+
+    let mut recap_context = Context::new();
+    death_recap::process(
+        &mut recap_context,
+        &DeathRecapQuery {
+            key,
+            report: report.clone(),
+            db_path: db_path.clone(),
+            seconds: None,
+        },
+    );
+    sections.insert(
+        String::from("death_recap"),
+        render(context, "death_recap.html", &recap_context),
+    );
+
+    let mut pam_context = Context::new();
+    powers_and_mobs_table::process(
+        &mut pam_context,
+        &PowersMobsData {
+            key,
+            report,
+            db_path,
+            table_name: None,
+            power_name: None,
+            mob_name: None,
+            mob_level: None,
+            sort_field: None,
+            sort_dir: None,
+        },
+    );
+    sections.insert(
+        String::from("pam"),
+        render(context, "powers_and_mobs_table.html", &pam_context),
+    );
+
+    sections
+}
+
+fn render(context: &AppContext, template: &str, table_context: &Context) -> String {
+    match context.tera.render(template, table_context) {
+        Ok(data) => data,
+        Err(e) => {
+            println!("Could not render {}:{:?}", template, e);
+            String::from("NO DATA")
+        }
+    }
+}
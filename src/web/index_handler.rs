@@ -1,37 +1,45 @@
 use std::{
-    collections::HashSet,
+    collections::{BTreeMap, BTreeSet, HashMap, HashSet},
     fs,
     path::{Path, PathBuf},
     sync::Mutex,
+    time::SystemTime,
 };
 
+use chrono::NaiveDate;
+use diesel::SqliteConnection;
 use lazy_static::lazy_static;
 use serde::{Deserialize, Serialize};
 use tera::Context;
+use utoipa::ToSchema;
 use walkdir::WalkDir;
 
 use crate::{
-    db, log_processing::{self, ParserJob, ProcessingError}, models::IndexDetails, read_log_file_dir, AppContext
+    db, error::CyclopsError, log_processing::{self, ParserJob, ProcessingError}, models::{BenchmarkReport, IndexDetails}, get_last_modified_file_in_dir, read_log_file_dir, AppContext
 };
 
+use super::annotations::{self, Annotation};
+use super::web_structs_enums::{IndexQuery, SortDirection};
+use super::{location_timeline, team_roster};
+
+// Sessions on one page of the index
+const INDEX_PAGE_SIZE: usize = 50;
+
+const INDEX_HEADERS: [(&str, &str); 4] = [
+    ("log_date", "Log Date"),
+    ("player_name", "Player"),
+    ("data_points", "Data Points"),
+    ("log_file", "Source File"),
+];
+
+// Teammate names by summary key
+type SessionTeammates = BTreeMap<String, Vec<String>>;
+
 lazy_static! {
     static ref INDEX_CACHE: Mutex<IndexCache> = Mutex::new(IndexCache::new());
     static ref LAST_PATH: Mutex<Option<PathBuf>> = Mutex::new(None);
-}
-
-#[derive(Serialize, Deserialize, Debug)]
-pub enum IndexSearch {
-    PlayerName,
-    LogDirectory,
-    LogFile,
-}
-
-#[derive(Deserialize, Debug)]
-pub struct IndexSearchQuery {
-    pub player_name: Option<String>,
-    pub log_path: Option<PathBuf>,
-    pub log_file: Option<PathBuf>,
-    pub action: IndexSearch,
+    // Teammates by summary key for each summary.db, kept until the db is written again
+    static ref TEAMMATE_CACHE: Mutex<HashMap<PathBuf, (SystemTime, SessionTeammates)>> = Mutex::new(HashMap::new());
 }
 
 #[derive(Clone)]
@@ -39,6 +47,63 @@ pub struct IndexCache {
     pub log_dirs: Vec<PathBuf>,
     pub player_names: Vec<String>,
     pub summaries: Vec<SummaryEntry>,
+    pub benchmarks: Vec<BenchmarkEntry>,
+    pub tags: Vec<String>,
+    pub teammates: Vec<String>,
+    // Report ids of every summary.db and scope copy in the output directory
+    pub reports: HashMap<String, PathBuf>,
+    // The output directory when the cache was built, None before the first scan
+    pub output_state: Option<OutputState>,
+}
+
+// Enough to tell if reports were added or removed without opening them
+#[derive(Clone, PartialEq)]
+pub struct OutputState {
+    pub modified: Option<SystemTime>,
+    pub report_files: usize,
+}
+
+// Sessions have to match every filter given
+#[derive(Default)]
+pub struct IndexFilters<'a> {
+    pub player_name: Option<&'a String>,
+    pub log_dir: Option<&'a PathBuf>,
+    pub log_file: Option<&'a PathBuf>,
+    pub tag: Option<&'a String>,
+    pub text: Option<&'a String>,
+    pub teammate: Option<&'a String>,
+    // YYYY-MM-DD, compared as text against the log date
+    pub from_date: Option<&'a String>,
+    pub to_date: Option<&'a String>,
+    pub min_data_points: Option<i64>,
+}
+
+impl<'a> IndexFilters<'a> {
+    pub fn from_query(query: &'a IndexQuery) -> Self {
+        IndexFilters {
+            player_name: query.player_name.as_ref(),
+            log_dir: query.log_dir.as_ref(),
+            log_file: query.log_file.as_ref(),
+            tag: query.tag.as_ref(),
+            text: query.text.as_ref(),
+            teammate: query.teammate.as_ref(),
+            from_date: query.from_date.as_ref(),
+            to_date: query.to_date.as_ref(),
+            min_data_points: query.min_data_points,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), CyclopsError> {
+        for date in [self.from_date, self.to_date].into_iter().flatten() {
+            if NaiveDate::parse_from_str(date, "%Y-%m-%d").is_err() {
+                return Err(CyclopsError::BadRequest(format!(
+                    "{} is not a date, use YYYY-MM-DD",
+                    date
+                )));
+            }
+        }
+        Ok(())
+    }
 }
 
 impl IndexCache {
@@ -47,21 +112,31 @@ impl IndexCache {
             log_dirs: Vec::<PathBuf>::new(),
             player_names: Vec::<String>::new(),
             summaries: Vec::<SummaryEntry>::new(),
+            benchmarks: Vec::<BenchmarkEntry>::new(),
+            tags: Vec::<String>::new(),
+            teammates: Vec::<String>::new(),
+            reports: HashMap::new(),
+            output_state: None,
         }
     }
 
-    pub fn update(
-        &mut self,
-        dirs: HashSet<PathBuf>,
-        names: HashSet<String>,
-        mut summaries: Vec<SummaryEntry>,
-    ) -> &mut Self {
+    pub fn update(&mut self, scan: IndexScan) -> &mut Self {
+        let IndexScan {
+            log_dirs,
+            player_names,
+            mut summaries,
+            mut benchmarks,
+            tags,
+            teammates,
+            reports,
+            output_state,
+        } = scan;
         summaries.sort_by(|a, b| b.log_date.cmp(&a.log_date));
 
-        let mut player_vec: Vec<String> = names.into_iter().collect();
+        let mut player_vec: Vec<String> = player_names.into_iter().collect();
         player_vec.sort();
 
-        let mut path_vec: Vec<PathBuf> = dirs.into_iter().collect();
+        let mut path_vec: Vec<PathBuf> = log_dirs.into_iter().collect();
         path_vec.sort();
 
         self.log_dirs.clear();
@@ -72,16 +147,64 @@ impl IndexCache {
         self.player_names.append(&mut player_vec);
         self.summaries.append(&mut summaries);
 
+        benchmarks.sort_by(|a, b| a.report.log_date.cmp(&b.report.log_date));
+        self.benchmarks.clear();
+        self.benchmarks.append(&mut benchmarks);
+
+        self.tags.clear();
+        self.tags.extend(tags);
+
+        self.teammates.clear();
+        self.teammates.extend(teammates);
+
+        self.reports = reports;
+        self.output_state = Some(output_state);
+
         self
     }
 }
 
+// Everything found by one walk of the output directory, replaces the cache contents
+pub struct IndexScan {
+    pub log_dirs: HashSet<PathBuf>,
+    pub player_names: HashSet<String>,
+    pub summaries: Vec<SummaryEntry>,
+    pub benchmarks: Vec<BenchmarkEntry>,
+    pub tags: BTreeSet<String>,
+    pub teammates: BTreeSet<String>,
+    pub reports: HashMap<String, PathBuf>,
+    pub output_state: OutputState,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct SummaryEntry {
     pub log_file: String,
     pub log_date: String,
     pub db_path: PathBuf,
+    pub report_id: String,
     pub indexes: Vec<IndexDetails>,
+    // Notes, tags and bookmarks keyed by summary key
+    pub annotations: BTreeMap<String, Annotation>,
+    // Teammate names keyed by summary key
+    pub teammates: BTreeMap<String, Vec<String>>,
+}
+
+// One parsed session with its notes and teammates
+#[derive(Serialize, Clone, Debug, ToSchema)]
+pub struct SessionEntry {
+    pub report_id: String,
+    pub details: IndexDetails,
+    pub annotation: Option<Annotation>,
+    pub teammates: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, ToSchema)]
+pub struct BenchmarkEntry {
+    // Not shown, the report id is used in links
+    #[serde(skip_serializing)]
+    pub db_path: PathBuf,
+    pub report_id: String,
+    pub report: BenchmarkReport,
 }
 
 pub fn create_parser_job<P: AsRef<Path>>(path_buf: P) -> Result<ParserJob, ParserJob> {
@@ -92,6 +215,10 @@ pub fn create_parser_job<P: AsRef<Path>>(path_buf: P) -> Result<ParserJob, Parse
         errors: Vec::new(),
         completion_date: "".to_string(),
         last_file: "".to_string(),
+        merge: false,
+        merged_report: None,
+        merged_report_id: None,
+        cancelled: false,
     };
 
     match log_processing::verify_file(&path_buf) {
@@ -116,6 +243,58 @@ pub fn create_parser_job<P: AsRef<Path>>(path_buf: P) -> Result<ParserJob, Parse
     }
 }
 
+// The most recently written log in the directory, usually the session being played
+pub fn create_latest_file_job<P: AsRef<Path>>(dir: P) -> Result<ParserJob, ParserJob> {
+    match get_last_modified_file_in_dir(&dir) {
+        Some(latest_file) => create_parser_job(latest_file),
+        None => {
+            // A missing directory is reported the same way as for the other actions
+            let mut parser_job = create_parser_job(&dir)?;
+            parser_job.files.clear();
+            parser_job.errors.push(ProcessingError {
+                file_name: dir.as_ref().to_path_buf(),
+                message: String::from("No log files found in the directory"),
+            });
+            Err(parser_job)
+        }
+    }
+}
+
+// Log files and directories separated by commas, every file is parsed and then merged into a team report
+pub fn create_merge_job(paths: &str) -> Result<ParserJob, ParserJob> {
+    let mut merge_job = ParserJob {
+        files: Vec::new(),
+        processed: 0,
+        run_time: 0,
+        errors: Vec::new(),
+        completion_date: "".to_string(),
+        last_file: "".to_string(),
+        merge: true,
+        merged_report: None,
+        merged_report_id: None,
+        cancelled: false,
+    };
+
+    for path in paths.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        match create_parser_job(path) {
+            Ok(mut job) => merge_job.files.append(&mut job.files),
+            Err(mut job) => merge_job.errors.append(&mut job.errors),
+        }
+    }
+    if merge_job.errors.is_empty() && merge_job.files.len() < 2 {
+        merge_job.errors.push(ProcessingError {
+            file_name: PathBuf::from(paths),
+            message: String::from("At least two logs are needed for a merge"),
+        });
+    }
+
+    if merge_job.errors.is_empty() {
+        Ok(merge_job)
+    } else {
+        Err(merge_job)
+    }
+}
+
 fn update_last_path( path: PathBuf) {
     let mut state = LAST_PATH.lock().unwrap();
     let _ = std::mem::replace(&mut *state, Some(path));
@@ -130,95 +309,92 @@ pub fn get_last_path() -> Option<PathBuf> {
     }
 }
 
-pub fn load_summaries(context: &AppContext) -> String {
-    let cache = find_all_summaries(&context.output_dir);
-    generate_index(&context, None, None, cache)
-}
-
-pub fn search_by_player_name(player_name: &String, context: &AppContext) -> String {
-    let cache = INDEX_CACHE.lock().unwrap();
-
-    let mut filtered_cache = IndexCache::new();
-
-    for i in &cache.summaries {
-        let mut filtered_indexes: Vec<IndexDetails> = Vec::new();
-        for d in &i.indexes {
-            if player_name.eq(&d.player_name) {
-                filtered_indexes.push(d.clone());
+// Sessions matching every filter given, newest first
+pub fn filter_sessions(cache: &IndexCache, filters: &IndexFilters) -> Vec<SessionEntry> {
+    let mut sessions = Vec::new();
+    for s in &cache.summaries {
+        for d in &s.indexes {
+            let key = d.summary_key.to_string();
+            let annotation = s.annotations.get(&key);
+            let teammates = s.teammates.get(&key).cloned().unwrap_or_default();
+            let matched = filters.player_name.is_none_or(|p| *p == d.player_name)
+                && filters
+                    .log_dir
+                    .is_none_or(|dir| Path::new(&d.file).parent() == Some(dir.as_path()))
+                && filters.log_file.is_none_or(|f| Path::new(&d.file) == f.as_path())
+                && filters
+                    .tag
+                    .is_none_or(|t| annotation.is_some_and(|a| a.has_tag(t)))
+                && filters
+                    .text
+                    .is_none_or(|t| annotation.is_some_and(|a| a.matches(t)))
+                && filters.teammate.is_none_or(|t| teammates.contains(t))
+                && filters.from_date.is_none_or(|from| d.log_date.as_str() >= from.as_str())
+                && filters.to_date.is_none_or(|to| d.log_date.as_str() <= to.as_str())
+                && filters
+                    .min_data_points
+                    .is_none_or(|min| data_points(d) >= min);
+            if matched {
+                sessions.push(SessionEntry {
+                    report_id: s.report_id.clone(),
+                    details: d.clone(),
+                    annotation: annotation.cloned(),
+                    teammates,
+                });
             }
         }
-        if filtered_indexes.len() > 0 {
-            let entry = SummaryEntry {
-                log_file: i.log_file.clone(),
-                log_date: i.log_date.clone(),
-                db_path: i.db_path.clone(), 
-                indexes: filtered_indexes,
-            };
-            filtered_cache.summaries.push(entry);
-        }
     }
-    filtered_cache.log_dirs.append(&mut cache.log_dirs.clone());
-    filtered_cache.player_names.append(&mut cache.player_names.clone());
+    sessions.sort_by(|a, b| {
+        (&b.details.log_date, b.details.summary_key).cmp(&(&a.details.log_date, a.details.summary_key))
+    });
+    sessions
+}
 
-    generate_index(&context, Some(player_name), None, filtered_cache)
+fn data_points(details: &IndexDetails) -> i64 {
+    details.data_points.parse().unwrap_or(0)
 }
 
-pub fn search_by_directory(log_dir: &PathBuf, context: &AppContext) -> String {
-    let cache = INDEX_CACHE.lock().unwrap();
+// The index, loading it first if it has not been built yet
+fn current_cache(context: &AppContext) -> IndexCache {
+    let empty = INDEX_CACHE.lock().unwrap().summaries.is_empty();
+    if empty {
+        find_all_summaries(&context.output_dir)
+    } else {
+        INDEX_CACHE.lock().unwrap().clone()
+    }
+}
 
-    let mut filtered_cache = IndexCache::new();
+// Every parsed session of a character
+// Each with the database path and report id it was found in
+pub fn find_player_sessions(player_name: &str, context: &AppContext) -> Vec<(PathBuf, String, IndexDetails)> {
+    let cache = current_cache(context);
 
-    for i in &cache.summaries {
-        let mut filtered_indexes: Vec<IndexDetails> = Vec::new();
-        for d in &i.indexes {
-            let parent = Path::new(&d.file).parent().unwrap().to_path_buf();
-            if parent.eq(log_dir) {
-                filtered_indexes.push(d.clone());
+    let mut sessions = Vec::new();
+    for s in &cache.summaries {
+        for d in &s.indexes {
+            if d.player_name == player_name {
+                sessions.push((s.db_path.clone(), s.report_id.clone(), d.clone()));
             }
         }
-        if filtered_indexes.len() > 0 {
-            let entry = SummaryEntry {
-                log_file: i.log_file.clone(),
-                log_date: i.log_date.clone(),
-                db_path: i.db_path.clone(), 
-                indexes: filtered_indexes,
-            };
-            filtered_cache.summaries.push(entry);
-        }
     }
-    filtered_cache.log_dirs.append(&mut cache.log_dirs.clone());
-    filtered_cache.player_names.append(&mut cache.player_names.clone());
-
-    generate_index(&context, None,Some( log_dir), filtered_cache)
+    sessions.sort_by(|a, b| a.2.log_date.cmp(&b.2.log_date));
+    sessions
 }
 
-pub fn search_by_log_file(log_file: &PathBuf, context: &AppContext) -> String {
-    let cache = INDEX_CACHE.lock().unwrap();
+// Every parsed session with exactly these teammates
+pub fn find_roster_sessions(teammates: &[String], context: &AppContext) -> Vec<(PathBuf, String, IndexDetails)> {
+    let cache = current_cache(context);
 
-    let mut filtered_cache = IndexCache::new();
-
-    for i in &cache.summaries {
-        let mut filtered_indexes: Vec<IndexDetails> = Vec::new();
-        for d in &i.indexes {
-            let index_file = Path::new(&d.file).to_path_buf();
-            if index_file.eq(log_file) {
-                filtered_indexes.push(d.clone());
+    let mut sessions = Vec::new();
+    for s in &cache.summaries {
+        for d in &s.indexes {
+            if s.teammates.get(&d.summary_key.to_string()).is_some_and(|names| names == teammates) {
+                sessions.push((s.db_path.clone(), s.report_id.clone(), d.clone()));
             }
         }
-        if filtered_indexes.len() > 0 {
-            let entry = SummaryEntry {
-                log_file: i.log_file.clone(),
-                log_date: i.log_date.clone(),
-                db_path: i.db_path.clone(), 
-                indexes: filtered_indexes,
-            };
-            filtered_cache.summaries.push(entry);
-        }
     }
-    filtered_cache.log_dirs.append(&mut cache.log_dirs.clone());
-    filtered_cache.player_names.append(&mut cache.player_names.clone());
-
-    generate_index(&context, None, None, filtered_cache)
+    sessions.sort_by(|a, b| a.2.log_date.cmp(&b.2.log_date));
+    sessions
 }
 
 pub fn find_all_summaries(output_path: &Path) -> IndexCache {
@@ -227,52 +403,254 @@ pub fn find_all_summaries(output_path: &Path) -> IndexCache {
     let mut player_set = HashSet::<String>::new();
     let mut log_dirs: HashSet<PathBuf> = HashSet::new();
     let mut entries: Vec<SummaryEntry> = Vec::new();
+    let mut benchmarks: Vec<BenchmarkEntry> = Vec::new();
+    let mut tags = BTreeSet::<String>::new();
+    let mut teammates = BTreeSet::<String>::new();
+    let mut reports = HashMap::<String, PathBuf>::new();
+    let mut report_files = 0;
 
     let walker = WalkDir::new(output_path).into_iter();
     for entry in walker.into_iter().filter_map(|e| e.ok()) {
-        if entry.path().ends_with("summary.db") {
-            let db_path = fs::canonicalize(entry.path()).unwrap().to_path_buf();
+        if is_report_file(entry.path()) {
+            report_files += 1;
+        }
+        if location_timeline::is_scope_db(entry.path()) {
+            if let Some(report_id) = db::report_id(output_path, entry.path()) {
+                reports.insert(report_id, entry.path().to_path_buf());
+            }
+        } else if entry.path().ends_with("summary.db") {
+            // A damaged report is left out of the index instead of breaking it for every report
+            if let Err(e) = db::check_report(entry.path()) {
+                println!("Skipping report {:?}: {}", entry.path(), e);
+                continue;
+            }
+            let report_id = db::report_id(output_path, entry.path()).unwrap_or_default();
+            reports.insert(report_id.clone(), entry.path().to_path_buf());
+            let db_path = fs::canonicalize(entry.path()).unwrap_or_else(|_| entry.path().to_path_buf());
             let mut conn = db::get_file_conn(db_path.clone());
             let details = db::queries::index_details(&mut conn);
+            let first = match details.first() {
+                Some(d) => d,
+                None => {
+                    println!("Skipping report {:?}: no sessions", entry.path());
+                    continue;
+                }
+            };
 
             let mut entry = SummaryEntry {
-                log_file: details.get(0).unwrap().file.to_owned(),
-                log_date: details.get(0).unwrap().log_date.to_owned(),
+                log_file: first.file.to_owned(),
+                log_date: first.log_date.to_owned(),
                 db_path: entry.path().to_path_buf(),
+                report_id,
                 indexes: Vec::new(),
+                annotations: annotations::load_all(entry.path()),
+                teammates: session_teammates(&mut conn, &db_path, &details),
             };
+            for a in entry.annotations.values() {
+                tags.extend(a.tags.iter().cloned());
+            }
             for d in &details {
                 player_set.insert(d.player_name.clone());
                 let f = Path::new(&d.file);
                 log_dirs.insert(f.parent().unwrap().to_path_buf());
                 entry.indexes.push(d.clone());
             }
+            for names in entry.teammates.values() {
+                teammates.extend(names.iter().cloned());
+            }
+
+            for report in db::queries::get_benchmark_reports(&mut conn) {
+                benchmarks.push(BenchmarkEntry {
+                    db_path: entry.db_path.clone(),
+                    report_id: entry.report_id.clone(),
+                    report,
+                });
+            }
 
             entries.push(entry);
         }
     }
-    cache.update(log_dirs, player_set, entries).clone()
+    cache
+        .update(IndexScan {
+            log_dirs,
+            player_names: player_set,
+            summaries: entries,
+            benchmarks,
+            tags,
+            teammates,
+            reports,
+            output_state: OutputState {
+                modified: dir_modified(output_path),
+                report_files,
+            },
+        })
+        .clone()
 }
 
-pub fn generate_index(
-    context: &AppContext,
-    last_player_name: Option<&String>,
-    last_log_dir: Option<&PathBuf>,
-    cache: IndexCache,
-) -> String {
-    let mut index_content = Context::new();
-    if last_player_name.is_some() {
-        index_content.insert("last_player_name", last_player_name.unwrap());
+fn is_report_file(path: &Path) -> bool {
+    path.ends_with("summary.db") || location_timeline::is_scope_db(path)
+}
+
+fn dir_modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// Walks the output directory without opening any of the reports
+fn output_state(output_path: &Path) -> OutputState {
+    let report_files = WalkDir::new(output_path)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| is_report_file(e.path()))
+        .count();
+    OutputState {
+        modified: dir_modified(output_path),
+        report_files,
     }
-    if last_log_dir.is_some() {
-        index_content.insert("last_log_dir", last_log_dir.unwrap());
+}
+
+// Building a roster takes several queries per session, so they are only rebuilt when the db changes
+fn session_teammates(
+    conn: &mut SqliteConnection,
+    db_path: &Path,
+    details: &[IndexDetails],
+) -> SessionTeammates {
+    let modified = fs::metadata(db_path).and_then(|m| m.modified()).ok();
+    if let Some(modified) = modified {
+        if let Some((cached_at, teammates)) = TEAMMATE_CACHE.lock().unwrap().get(db_path) {
+            if *cached_at == modified {
+                return teammates.clone();
+            }
+        }
+    }
+
+    let mut teammates = SessionTeammates::new();
+    for d in details {
+        if let Some(summary) = db::queries::get_summary(conn, d.summary_key).first() {
+            teammates.insert(d.summary_key.to_string(), team_roster::teammate_names(conn, summary));
+        }
     }
+    if let Some(modified) = modified {
+        TEAMMATE_CACHE
+            .lock()
+            .unwrap()
+            .insert(db_path.to_path_buf(), (modified, teammates.clone()));
+    }
+    teammates
+}
+
+// The database behind a report id. The index is rebuilt once for a miss,
+// only if reports were added or removed since it was built.
+pub fn find_report(context: &AppContext, report: &str) -> Result<PathBuf, CyclopsError> {
+    let (cached, scanned) = {
+        let cache = INDEX_CACHE.lock().unwrap();
+        (cache.reports.get(report).cloned(), cache.output_state.clone())
+    };
+    if let Some(db_path) = cached {
+        if db_path.is_file() {
+            return Ok(db_path);
+        }
+    }
+
+    let changed = match scanned {
+        Some(state) => state != output_state(&context.output_dir),
+        None => true,
+    };
+    if changed {
+        if let Some(db_path) = find_all_summaries(&context.output_dir).reports.get(report) {
+            return Ok(db_path.clone());
+        }
+    }
+    Err(CyclopsError::NotFound(format!(
+        "No report with the id {}. It might have been deleted or the link is incomplete.",
+        report
+    )))
+}
+
+// One page of the sessions matching the query, sorted and with links that keep the other settings.
+// A page past the end is changed to the last page.
+pub fn generate_index(context: &AppContext, query: &mut IndexQuery) -> Result<String, CyclopsError> {
+    let filters = IndexFilters::from_query(query);
+    filters.validate()?;
+    let cache = current_cache(context);
+    let mut sessions = filter_sessions(&cache, &filters);
+
+    let sort_dir = query.sort_dir.clone().unwrap_or(SortDirection::DESC);
+    if let Some(sort_field) = &query.sort_field {
+        sort_sessions(sort_field, &sort_dir, &mut sessions);
+    }
+
+    let session_count = sessions.len();
+    let page_count = session_count.div_ceil(INDEX_PAGE_SIZE).max(1);
+    let page = query.page.unwrap_or(1).clamp(1, page_count);
+    query.page = Some(page);
+    let page_sessions: Vec<SessionEntry> = sessions
+        .into_iter()
+        .skip((page - 1) * INDEX_PAGE_SIZE)
+        .take(INDEX_PAGE_SIZE)
+        .collect();
+
+    // Sorting starts over on the first page, paging keeps the sort
+    let filter_query = IndexQuery {
+        sort_field: None,
+        sort_dir: None,
+        page: None,
+        ..query.clone()
+    };
+    let sort_query = IndexQuery {
+        page: None,
+        ..query.clone()
+    };
+
+    let mut index_content = Context::new();
+    index_content.insert("query", &*query);
+    index_content.insert("filter_query", &query_string(&filter_query));
+    index_content.insert("sort_query", &query_string(&sort_query));
+    match query.sort_dir {
+        Some(SortDirection::ASC) => index_content.insert("sort_dir", &SortDirection::DESC),
+        _ => index_content.insert("sort_dir", &SortDirection::ASC),
+    }
+    index_content.insert("headers", &INDEX_HEADERS);
+    index_content.insert("page", &page);
+    index_content.insert("page_count", &page_count);
+    index_content.insert("session_count", &session_count);
     index_content.insert("players", &cache.player_names);
+    index_content.insert("tags", &cache.tags);
+    index_content.insert("teammates", &cache.teammates);
     index_content.insert("log_dirs", &cache.log_dirs);
-    index_content.insert("summaries", &cache.summaries);
-    let result = context.tera.render("index_table.html", &index_content);
-    match result {
-        Ok(data) => data,
-        Err(e) => panic!("Could not render {}:{:?}", "index_table.html", e),
+    index_content.insert("sessions", &page_sessions);
+    Ok(context.tera.render("index_table.html", &index_content)?)
+}
+
+// The index page url for a query, pushed to the browser history so the view can be bookmarked
+pub fn index_url(query: &IndexQuery) -> String {
+    let query = IndexQuery {
+        page: query.page.filter(|p| *p > 1),
+        ..query.clone()
+    };
+    match query_string(&query) {
+        q if q.is_empty() => String::from("/"),
+        q => format!("/?{}", q),
+    }
+}
+
+fn query_string(query: &IndexQuery) -> String {
+    serde_qs::to_string(query).unwrap_or_default()
+}
+
+fn sort_sessions(sort_field: &str, sort_dir: &SortDirection, sessions: &mut [SessionEntry]) {
+    match sort_field {
+        "log_date" => sessions.sort_by(|a, b| {
+            (&a.details.log_date, a.details.summary_key).cmp(&(&b.details.log_date, b.details.summary_key))
+        }),
+        "player_name" => sessions.sort_by(|a, b| a.details.player_name.cmp(&b.details.player_name)),
+        "data_points" => sessions.sort_by_key(|s| data_points(&s.details)),
+        "log_file" => sessions.sort_by(|a, b| a.details.file.cmp(&b.details.file)),
+        _ => {
+            println!("Unknown sort field provided: {}", sort_field);
+            return;
+        }
+    }
+    if *sort_dir == SortDirection::DESC {
+        sessions.reverse();
     }
 }
@@ -0,0 +1,270 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, FixedOffset};
+use serde::Serialize;
+use tera::Context;
+use utoipa::ToSchema;
+
+use crate::{
+    db,
+    models::{LocationEvent, ReportScope, Summary},
+    web::web_structs_enums::{LocationTimelineQuery, ScopeQuery},
+};
+
+use super::dates::{format_elapsed, parse_date};
+
+const UNKNOWN_ZONE: &str = "Unknown zone";
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, ToSchema)]
+enum SegmentType {
+    Zone,
+    Mission,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+struct SegmentRow {
+    segment_type: SegmentType,
+    name: String,
+    first_line_number: i32,
+    last_line_number: i32,
+    start: String,
+    duration: String,
+    experience: i64,
+    influence: i64,
+    defeats: usize,
+    completed: bool,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+struct CompletionRow {
+    event: String,
+    name: String,
+    elapsed: String,
+}
+
+struct Completion {
+    event: &'static str,
+    name: String,
+    date: DateTime<FixedOffset>,
+}
+
+// A zone or mission visit before the rewards and defeats are added
+struct Segment {
+    segment_type: SegmentType,
+    name: String,
+    first_line_number: i32,
+    start: DateTime<FixedOffset>,
+    completed: bool,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct LocationTimelineReport {
+    segment_rows: Vec<SegmentRow>,
+    completion_rows: Vec<CompletionRow>,
+}
+
+pub fn process(context: &mut Context, query: &LocationTimelineQuery) {
+    if let Some(report) = report(query) {
+        context.insert("key", &query.key);
+        context.insert("report", &query.report);
+        super::insert_report(context, &report);
+    }
+}
+
+// None when the session is missing or has no zone or mission changes
+pub fn report(query: &LocationTimelineQuery) -> Option<LocationTimelineReport> {
+    let db_path: PathBuf = query.db_path.clone().into();
+    let mut conn = db::get_file_conn(db_path);
+
+    let binding = db::queries::get_summary(&mut conn, query.key);
+    let summary = binding.first()?;
+    let events = db::queries::get_location_events(&mut conn, query.key);
+    if events.is_empty() {
+        return None;
+    }
+
+    let session_start = parse_date(&summary.log_date);
+    let session_end = db::queries::get_log_date_at_line(&mut conn, query.key, summary.last_line_number)
+        .map(|d| parse_date(&d))
+        .unwrap_or(session_start)
+        .max(parse_date(&events.last().unwrap().log_date));
+    let rewards = db::queries::get_rewards(&mut conn, query.key);
+    let defeats = db::queries::get_player_defeat_lines(&mut conn, query.key, &summary.player_name);
+
+    let (segments, completions) = build_segments(summary, &events);
+    let rows: Vec<SegmentRow> = segments
+        .iter()
+        .enumerate()
+        .filter_map(|(i, s)| {
+            let (last_line_number, end) = match segments.get(i + 1) {
+                Some(next) => (next.first_line_number - 1, next.start),
+                None => (summary.last_line_number, session_end),
+            };
+            if last_line_number <= s.first_line_number {
+                return None;
+            }
+
+            let in_segment = |line: i32| line >= s.first_line_number && line <= last_line_number;
+            let (experience, influence) = rewards
+                .iter()
+                .filter(|r| in_segment(r.line_number))
+                .fold((0, 0), |(xp, inf), r| {
+                    (
+                        xp + r.experience.unwrap_or(0) as i64,
+                        inf + r.influence.unwrap_or(0) as i64,
+                    )
+                });
+            Some(SegmentRow {
+                segment_type: s.segment_type,
+                name: s.name.clone(),
+                first_line_number: s.first_line_number,
+                last_line_number,
+                start: format_elapsed((s.start - session_start).num_seconds()),
+                duration: format_elapsed((end - s.start).num_seconds()),
+                experience,
+                influence,
+                defeats: defeats.iter().filter(|d| in_segment(**d)).count(),
+                completed: s.completed,
+            })
+        })
+        .collect();
+
+    let completion_rows: Vec<CompletionRow> = completions
+        .into_iter()
+        .map(|c| CompletionRow {
+            event: c.event.to_string(),
+            name: c.name,
+            elapsed: format_elapsed((c.date - session_start).num_seconds()),
+        })
+        .collect();
+
+    Some(LocationTimelineReport {
+        segment_rows: rows,
+        completion_rows,
+    })
+}
+
+// Zone changes and mission entries start a new segment, leaving a mission returns to the last zone
+fn build_segments(
+    summary: &Summary,
+    events: &[LocationEvent],
+) -> (Vec<Segment>, Vec<Completion>) {
+    let mut segments = vec![Segment {
+        segment_type: SegmentType::Zone,
+        name: String::from(UNKNOWN_ZONE),
+        first_line_number: summary.first_line_number,
+        start: parse_date(&summary.log_date),
+        completed: false,
+    }];
+    let mut completions = Vec::new();
+    let mut last_zone = String::from(UNKNOWN_ZONE);
+
+    for e in events {
+        let date = parse_date(&e.log_date);
+        let current_type = segments.last().map(|s| s.segment_type);
+        match e.event_type.as_str() {
+            "Zone" => {
+                last_zone = e.name.clone().unwrap_or(String::from(UNKNOWN_ZONE));
+                segments.push(new_segment(SegmentType::Zone, &last_zone, e, date));
+            }
+            "MissionEntered" => {
+                let name = e.name.clone().unwrap_or(String::from("Mission"));
+                segments.push(new_segment(SegmentType::Mission, &name, e, date));
+            }
+            "MissionExited" if current_type == Some(SegmentType::Mission) => {
+                segments.push(new_segment(SegmentType::Zone, &last_zone, e, date));
+            }
+            "MissionComplete" => {
+                let mut name = e.name.clone().unwrap_or_default();
+                if let Some(current) = segments.last_mut() {
+                    if current.segment_type == SegmentType::Mission {
+                        current.completed = true;
+                        if name.is_empty() {
+                            name = current.name.clone();
+                        }
+                    }
+                }
+                completions.push(Completion {
+                    event: "Mission",
+                    name,
+                    date,
+                });
+            }
+            "TaskForceComplete" => {
+                completions.push(Completion {
+                    event: "Task Force",
+                    name: e.name.clone().unwrap_or_default(),
+                    date,
+                });
+            }
+            _ => (),
+        }
+    }
+    (segments, completions)
+}
+
+fn new_segment(segment_type: SegmentType, name: &str, event: &LocationEvent, start: DateTime<FixedOffset>) -> Segment {
+    Segment {
+        segment_type,
+        name: name.to_string(),
+        first_line_number: event.line_number,
+        start,
+        completed: false,
+    }
+}
+
+// Builds a copy of the report with only the lines in the scope and returns its path
+pub fn create_scope(query: &ScopeQuery) -> Option<PathBuf> {
+    let db_path: PathBuf = query.db_path.clone().into();
+    let mut conn = db::get_file_conn(db_path.clone());
+
+    let binding = db::queries::get_summary(&mut conn, query.key);
+    let summary = binding.first()?;
+    // Scopes always come from the full session
+    if db::queries::get_report_scope(&mut conn, query.key).is_some() {
+        return None;
+    }
+
+    let first_line_number = query.first_line.max(summary.first_line_number);
+    let last_line_number = query.last_line.min(summary.last_line_number);
+    if last_line_number <= first_line_number {
+        return None;
+    }
+    let log_date = db::queries::get_location_events(&mut conn, query.key)
+        .into_iter()
+        .find(|e| e.line_number == first_line_number)
+        .map_or(summary.log_date.clone(), |e| e.log_date);
+
+    let target = scope_path(&db_path, query.key, first_line_number, last_line_number);
+    let scope = ReportScope {
+        summary_key: query.key,
+        scope_name: query.name.clone(),
+        first_line_number,
+        last_line_number,
+        source_db: query.db_path.clone(),
+    };
+    db::create_scoped_copy(db_path, target.clone(), &scope, &log_date);
+    Some(target)
+}
+
+pub fn is_scope_db(path: &Path) -> bool {
+    path.file_name()
+        .map(|f| f.to_string_lossy())
+        .is_some_and(|f| f.starts_with("scope_") && f.ends_with(".db"))
+}
+
+fn scope_path(db_path: &Path, key: i32, first_line: i32, last_line: i32) -> PathBuf {
+    let file_name = format!("scope_{}_{}_{}.db", key, first_line, last_line);
+    match db_path.parent() {
+        Some(dir) => dir.join(file_name),
+        None => PathBuf::from(file_name),
+    }
+}
+
+pub fn summary_url(key: i32, report_id: &str) -> String {
+    let mut params = BTreeMap::new();
+    params.insert("key", key.to_string());
+    params.insert("report", report_id.to_string());
+    format!("/summary?{}", serde_qs::to_string(&params).unwrap_or_default())
+}
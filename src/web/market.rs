@@ -0,0 +1,257 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use diesel::SqliteConnection;
+use serde::Serialize;
+use tera::Context;
+use utoipa::ToSchema;
+
+use crate::{
+    db,
+    models::{MarketTransaction, Summary},
+    web::web_structs_enums::MarketQuery,
+    AppContext,
+};
+
+use super::dates::{format_elapsed, parse_date};
+use super::index_handler;
+
+const SECONDS_PER_HOUR: i64 = 3600;
+
+#[derive(Serialize, Debug, Default, Clone, ToSchema)]
+struct IncomeRow {
+    earned: i64,
+    spent: i64,
+    items_sold: i64,
+    items_bought: i64,
+    net_profit: i64,
+    reward_influence: i64,
+    // Reward influence plus the market profit
+    total_income: i64,
+}
+
+impl IncomeRow {
+    fn add_transaction(&mut self, t: &MarketTransaction) {
+        if t.transaction_type == "Sold" {
+            self.earned += t.influence;
+            self.items_sold += t.quantity as i64;
+        } else {
+            self.spent += t.influence;
+            self.items_bought += t.quantity as i64;
+        }
+        self.net_profit = self.earned - self.spent;
+        self.total_income = self.reward_influence + self.net_profit;
+    }
+
+    fn add_reward(&mut self, influence: i64) {
+        self.reward_influence += influence;
+        self.total_income = self.reward_influence + self.net_profit;
+    }
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+struct HourRow {
+    hour: i64,
+    income: IncomeRow,
+    cumulative_profit: i64,
+    partial: bool,
+}
+
+#[derive(Serialize, Debug, Default, ToSchema)]
+struct ItemRow {
+    item_name: String,
+    sold: i64,
+    earned: i64,
+    bought: i64,
+    spent: i64,
+    net_profit: i64,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+struct TransactionRow {
+    time: String,
+    transaction_type: String,
+    item_name: String,
+    quantity: i32,
+    influence: i64,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+struct HistoryRow {
+    summary_key: i32,
+    report_id: String,
+    log_date: String,
+    income: IncomeRow,
+    cumulative_income: i64,
+    current: bool,
+}
+
+// Market transactions and reward influence for one session, times are seconds from the start of the session
+struct SessionMarket {
+    duration: i64,
+    transactions: Vec<(MarketTransaction, i64)>,
+    rewards: Vec<(i64, i64)>,
+}
+
+impl SessionMarket {
+    fn load(conn: &mut SqliteConnection, summary: &Summary) -> SessionMarket {
+        let start = parse_date(&summary.log_date);
+        let transactions: Vec<(MarketTransaction, i64)> =
+            db::queries::get_market_transactions(conn, summary.summary_key)
+                .into_iter()
+                .map(|t| {
+                    let elapsed = (parse_date(&t.log_date) - start).num_seconds().max(0);
+                    (t, elapsed)
+                })
+                .collect();
+        let rewards: Vec<(i64, i64)> = db::queries::get_reward_timeline(conn, summary.summary_key)
+            .iter()
+            .filter(|r| r.influence > 0)
+            .map(|r| (r.elapsed as i64, r.influence as i64))
+            .collect();
+
+        let last_action = db::queries::get_log_date_at_line(conn, summary.summary_key, summary.last_line_number)
+            .map_or(0, |date| (parse_date(&date) - start).num_seconds());
+        let duration = transactions
+            .iter()
+            .map(|t| t.1)
+            .chain(rewards.iter().map(|r| r.0))
+            .chain(std::iter::once(last_action))
+            .max()
+            .unwrap_or(0)
+            .max(0);
+
+        SessionMarket {
+            duration,
+            transactions,
+            rewards,
+        }
+    }
+
+    fn income_between(&self, start: i64, end: i64) -> IncomeRow {
+        let mut income = IncomeRow::default();
+        for (t, _) in self.transactions.iter().filter(|t| t.1 >= start && t.1 < end) {
+            income.add_transaction(t);
+        }
+        for (_, influence) in self.rewards.iter().filter(|r| r.0 >= start && r.0 < end) {
+            income.add_reward(*influence);
+        }
+        income
+    }
+
+    fn session_row(&self) -> IncomeRow {
+        self.income_between(0, i64::MAX)
+    }
+
+    fn hour_rows(&self) -> Vec<HourRow> {
+        let hours = self.duration / SECONDS_PER_HOUR + 1;
+        let mut cumulative_profit = 0;
+        (0..hours)
+            .map(|h| {
+                let start = h * SECONDS_PER_HOUR;
+                let income = self.income_between(start, start + SECONDS_PER_HOUR);
+                cumulative_profit += income.net_profit;
+                HourRow {
+                    hour: h + 1,
+                    income,
+                    cumulative_profit,
+                    partial: start + SECONDS_PER_HOUR > self.duration,
+                }
+            })
+            .collect()
+    }
+
+    fn item_rows(&self) -> Vec<ItemRow> {
+        let mut items = BTreeMap::<String, ItemRow>::new();
+        for (t, _) in &self.transactions {
+            let item = items.entry(t.item_name.clone()).or_insert_with(|| ItemRow {
+                item_name: t.item_name.clone(),
+                ..Default::default()
+            });
+            if t.transaction_type == "Sold" {
+                item.sold += t.quantity as i64;
+                item.earned += t.influence;
+            } else {
+                item.bought += t.quantity as i64;
+                item.spent += t.influence;
+            }
+            item.net_profit = item.earned - item.spent;
+        }
+        let mut rows: Vec<ItemRow> = items.into_values().collect();
+        rows.sort_by_key(|r| -r.net_profit);
+        rows
+    }
+
+    fn transaction_rows(&self) -> Vec<TransactionRow> {
+        self.transactions
+            .iter()
+            .map(|(t, elapsed)| TransactionRow {
+                time: format_elapsed(*elapsed),
+                transaction_type: t.transaction_type.clone(),
+                item_name: t.item_name.clone(),
+                quantity: t.quantity,
+                influence: t.influence,
+            })
+            .collect()
+    }
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct MarketReport {
+    player_name: String,
+    session: IncomeRow,
+    hour_rows: Vec<HourRow>,
+    item_rows: Vec<ItemRow>,
+    transaction_rows: Vec<TransactionRow>,
+    history_rows: Vec<HistoryRow>,
+}
+
+pub fn process(app_context: &AppContext, context: &mut Context, query: &MarketQuery) {
+    if let Some(report) = report(app_context, query) {
+        super::insert_report(context, &report);
+    }
+}
+
+pub fn report(app_context: &AppContext, query: &MarketQuery) -> Option<MarketReport> {
+    let db_path: PathBuf = query.db_path.clone().into();
+    let mut conn = db::get_file_conn(db_path.clone());
+
+    let binding = db::queries::get_summary(&mut conn, query.key);
+    let summary = binding.first()?;
+
+    let market = SessionMarket::load(&mut conn, summary);
+    Some(MarketReport {
+        player_name: summary.player_name.clone(),
+        session: market.session_row(),
+        hour_rows: market.hour_rows(),
+        item_rows: market.item_rows(),
+        transaction_rows: market.transaction_rows(),
+        history_rows: history_rows(app_context, summary, &db_path),
+    })
+}
+
+// Every session of the same character, oldest first, with a running total of income
+fn history_rows(app_context: &AppContext, current: &Summary, current_db: &Path) -> Vec<HistoryRow> {
+    let current_db = current_db.canonicalize().unwrap_or(current_db.to_path_buf());
+    let mut cumulative_income = 0;
+    index_handler::find_player_sessions(&current.player_name, app_context)
+        .into_iter()
+        .filter_map(|(db_path, report_id, details)| {
+            let mut conn = db::get_file_conn(db_path.clone());
+            let binding = db::queries::get_summary(&mut conn, details.summary_key);
+            let summary = binding.first()?;
+            let income = SessionMarket::load(&mut conn, summary).session_row();
+            cumulative_income += income.total_income;
+            let is_current = summary.summary_key == current.summary_key
+                && db_path.canonicalize().unwrap_or(db_path.clone()) == current_db;
+            Some(HistoryRow {
+                summary_key: summary.summary_key,
+                log_date: summary.log_date.clone(),
+                income,
+                cumulative_income,
+                current: is_current,
+                report_id,
+            })
+        })
+        .collect()
+}
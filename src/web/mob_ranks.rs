@@ -0,0 +1,151 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use diesel::SqliteConnection;
+use serde::Serialize;
+use utoipa::ToSchema;
+
+use crate::{
+    db,
+    game_data::{self, MobClass},
+    models::DefeatedTargetDamage,
+};
+
+use super::web_structs_enums::TableQuery;
+
+#[derive(Clone, Debug)]
+pub struct MobRank {
+    pub mob_class: MobClass,
+    pub level: i32,
+    pub hp: i32,
+    pub overridden: bool,
+    // The HP comes from one of the estimated tables
+    pub estimated_hp: bool,
+}
+
+impl MobRank {
+    fn new(mob_class: MobClass, level: i32, overridden: bool) -> MobRank {
+        let hp = game_data::find_mob_hp(mob_class, level);
+        MobRank {
+            mob_class,
+            level,
+            hp: hp.map_or(1, |h| h.hp),
+            overridden,
+            estimated_hp: hp.is_some_and(|h| h.estimated),
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        let mut description = format!("{:?} {}", self.mob_class, self.level);
+        if self.overridden {
+            description.push_str(" (override)");
+        }
+        if self.estimated_hp {
+            description.push_str(" (estimated HP)");
+        }
+        description
+    }
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct MobRankRow {
+    mob_name: String,
+    mob_class: MobClass,
+    level: i32,
+    hp: i32,
+    overridden: bool,
+    estimated_hp: bool,
+}
+
+// Every mob the player defeated or was damaged by, with the rank used in the reports
+pub fn rows(query: &TableQuery) -> Vec<MobRankRow> {
+    let mut conn = db::get_file_conn(query.db_path.clone().into());
+    let defeats = db::queries::get_defeated_target_damage(&mut conn, query.key);
+    let ranks = MobRanks::from_defeats(&defeats, game_data::mob_level(&query.mob_level));
+
+    let mut names: BTreeSet<String> = defeats.into_iter().map(|d| d.target_name).collect();
+    names.extend(
+        db::queries::get_damage_taken_by_mob_query(query)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|d| d.source_name),
+    );
+    names
+        .into_iter()
+        .map(|mob_name| {
+            let rank = ranks.get(&mob_name);
+            MobRankRow {
+                mob_name,
+                mob_class: rank.mob_class,
+                level: rank.level,
+                hp: rank.hp,
+                overridden: rank.overridden,
+                estimated_hp: rank.estimated_hp,
+            }
+        })
+        .collect()
+}
+
+// A level set for the mob in the overrides file, otherwise the level picked for the report
+fn target_level(mob_name: &str, default_level: i32) -> i32 {
+    game_data::find_mob_rank_override(mob_name)
+        .and_then(|o| o.level)
+        .unwrap_or(default_level)
+}
+
+// Rank and level for each mob name in a summary.
+// Overrides from resources/mob_rank_overrides.csv win, otherwise the rank
+// inferred most often from the mob's defeats is used. Mobs never defeated are minions.
+// Each mob's HP is looked up at its own level from the overrides, the logs have no mob levels.
+pub struct MobRanks {
+    level: i32,
+    inferred: HashMap<String, MobClass>,
+}
+
+impl MobRanks {
+    pub fn load(conn: &mut SqliteConnection, key: i32, level: i32) -> MobRanks {
+        let defeats = db::queries::get_defeated_target_damage(conn, key);
+        MobRanks::from_defeats(&defeats, level)
+    }
+
+    pub fn from_defeats(defeats: &[DefeatedTargetDamage], level: i32) -> MobRanks {
+        let mut counts = HashMap::<String, BTreeMap<MobClass, usize>>::new();
+        for d in defeats {
+            *counts
+                .entry(d.target_name.clone())
+                .or_default()
+                .entry(game_data::infer_mob_class(
+                    d.damage_before_final_blow,
+                    target_level(&d.target_name, level),
+                ))
+                .or_default() += 1;
+        }
+
+        // Ties go to the tougher rank
+        let inferred = counts
+            .into_iter()
+            .filter_map(|(name, classes)| {
+                classes
+                    .into_iter()
+                    .max_by_key(|(class, count)| (*count, *class))
+                    .map(|(class, _)| (name, class))
+            })
+            .collect();
+
+        MobRanks { level, inferred }
+    }
+
+    pub fn get(&self, mob_name: &str) -> MobRank {
+        let level = target_level(mob_name, self.level);
+        match game_data::find_mob_rank_override(mob_name) {
+            Some(o) => MobRank::new(o.mob_class, level, true),
+            None => {
+                let mob_class = self
+                    .inferred
+                    .get(mob_name)
+                    .copied()
+                    .unwrap_or(MobClass::Minion);
+                MobRank::new(mob_class, level, false)
+            }
+        }
+    }
+}
@@ -1,27 +1,57 @@
 use actix_files as fs;
+use actix_multipart::Multipart;
 use actix_web::{
+    dev::ServiceResponse,
     get,
+    http::header,
+    middleware::{ErrorHandlerResponse, ErrorHandlers},
+    post,
     web::{self},
-    App, HttpRequest, HttpResponse, HttpServer, Responder,
+    App, HttpRequest, HttpResponse, HttpServer,
 };
-use index_handler::{IndexSearch, IndexSearchQuery};
 use player_summary_table::SummaryQuery;
+use serde::{de::DeserializeOwned, Serialize};
 use tera::Context;
-use web_structs_enums::{DamageByPowerQuery, ParseLog, ParseLogRequest, PowersMobsData, SortDirection, TableNames, TableQuery};
+use web_structs_enums::{AnnotationQuery, BenchmarkQuery, ChartQuery, CompareQuery, ConsumablesQuery, DamageByPowerQuery, DeathRecapQuery, DebuffEffectivenessQuery, IndexQuery, JobQuery, LocationTimelineQuery, MarketQuery, ParseLog, ParseLogRequest, PowersMobsData, ProgressionQuery, ScopeQuery, SortDirection, TableNames, TableQuery, TeamReportQuery, TeamRosterQuery};
 
+mod annotations;
+mod api;
+mod benchmark_handler;
+mod charts;
+mod compare_summaries;
+mod consumables;
 mod damage_by_power_table;
 mod damage_dealt_by_type_table;
 mod damage_taken_by_mob_power_table;
 mod damage_taken_by_mob_table;
 mod damage_taken_by_type_table;
+mod dates;
+mod death_recap;
+mod debuff_effectiveness;
+mod defeated_targets_table;
 mod dps_interval_table;
+pub mod export;
 mod index_handler;
+mod location_timeline;
+mod market;
+mod mob_ranks;
+mod monitor_stream;
 mod player_summary_table;
 mod powers_and_mobs_table;
+mod power_views;
+mod preferences;
+mod proc_rate_table;
+mod progression;
+mod team_report;
+mod team_roster;
+mod upload;
 pub mod web_structs_enums;
 
 use crate::{
-    get_last_modified_file_in_dir, log_processing::{self, ParserJob}, monitoring, AppContext
+    assets, db,
+    error::CyclopsError,
+    log_processing::{jobs, ParserJob},
+    monitoring, AppContext,
 };
 
 fn create_job_result(context: &AppContext, job: &ParserJob) -> HttpResponse {
@@ -37,65 +67,159 @@ fn create_job_result(context: &AppContext, job: &ParserJob) -> HttpResponse {
     }
 }
 
-fn create_job_start(context: &AppContext, job: &ParserJob) -> HttpResponse {
-    let mut result_context = Context::new();
-    result_context.insert("job", &job);
-    result_context.insert("file_count", &job.files.len());
-    let result = context.tera.render("job_start.html", &result_context);
+// Parses the query string, a malformed one gets the error page instead of a panic
+fn parse_query<T: DeserializeOwned>(req: &HttpRequest) -> Result<web::Query<T>, CyclopsError> {
+    Ok(web::Query::<T>::from_query(req.query_string())?)
+}
+
+// The database behind a report id from the url. Only reports in the index can be opened.
+fn find_report(context: &AppContext, report: &str) -> Result<String, CyclopsError> {
+    let db_path = index_handler::find_report(context, report)?;
+    db::check_report(&db_path)?;
+    Ok(db_path.to_string_lossy().to_string())
+}
+
+// The fields of a report become the page's variables, the api returns the same report as json
+fn insert_report<T: Serialize>(context: &mut Context, report: &T) {
+    match Context::from_serialize(report) {
+        Ok(report_context) => context.extend(report_context),
+        Err(e) => println!("Unable to add the report to the page: {:?}", e),
+    }
+}
+
+// Logs every failed request and replaces the plain text error with the error page.
+// The api has its own json errors and is left alone.
+fn render_error_page<B>(res: ServiceResponse<B>) -> actix_web::Result<ErrorHandlerResponse<B>> {
+    let message = match res.response().error() {
+        Some(e) => e.to_string(),
+        None => return Ok(ErrorHandlerResponse::Response(res.map_into_left_body())),
+    };
+    let status = res.status();
+    println!("{} {} failed with {}: {}", res.request().method(), res.request().uri(), status, message);
+    if res.request().path().starts_with(api::API_ROOT) {
+        return Ok(ErrorHandlerResponse::Response(res.map_into_left_body()));
+    }
+
+    let mut error_context = Context::new();
+    error_context.insert("status", &status.as_u16());
+    error_context.insert("reason", &status.canonical_reason().unwrap_or_default());
+    error_context.insert("message", &message);
+    let page = match res.request().app_data::<web::Data<AppContext>>() {
+        Some(context) => context.tera.render("error.html", &error_context).unwrap_or_else(|e| {
+            println!("Could not render error.html:{:?}", e);
+            message
+        }),
+        None => message,
+    };
+
+    let (req, _) = res.into_parts();
+    let response = HttpResponse::build(status)
+        .insert_header(header::ContentType::html())
+        .body(page);
+    Ok(ErrorHandlerResponse::Response(
+        ServiceResponse::new(req, response).map_into_right_body(),
+    ))
+}
+
+fn create_job_start(context: &AppContext, job: ParserJob) -> HttpResponse {
+    let id = jobs::add_job(job);
+    match jobs::get_status(id) {
+        Some(status) => create_job_progress(context, &status),
+        None => HttpResponse::NoContent().into(),
+    }
+}
+
+fn create_job_progress(context: &AppContext, status: &jobs::JobStatus) -> HttpResponse {
+    let mut progress_context = Context::new();
+    progress_context.insert("status", status);
+    progress_context.insert("queue_position", &queue_position(status.id));
+    let result = context.tera.render("job_start.html", &progress_context);
     match result {
         Ok(data) => HttpResponse::Ok().body(data),
         Err(e) => HttpResponse::Ok().body(format!("ERROR CHECK LOGS: {:?}", e)),
     }
 }
 
+// Jobs waiting ahead of this one, counting the running job
+fn queue_position(id: usize) -> usize {
+    jobs::get_statuses()
+        .iter()
+        .filter(|s| s.id < id && !s.is_done())
+        .count()
+}
+
 #[get("parse_request")]
-async fn parse_request(req: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
-    let form: web::Query<ParseLogRequest> = web::Query::from_query(req.query_string()).unwrap();
+async fn parse_request(req: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let form: web::Query<ParseLogRequest> = parse_query(&req)?;
     println!("Latest Request: {:?}", form.log_path);
     let stripped_path = form.log_path.replace("\"", "");
 
-    match form.action {
-        ParseLog::ParsePath => match index_handler::create_parser_job(&stripped_path) {
-            Ok(job) => {
-                log_processing::add_job(job.clone());
-                create_job_start(&context, &job)
-            }
-            Err(e) => create_job_result(&context, &e),
+    let job = match form.action {
+        ParseLog::ParsePath => index_handler::create_parser_job(&stripped_path),
+        ParseLog::LatestFile => index_handler::create_latest_file_job(&stripped_path),
+        ParseLog::MergeLogs => index_handler::create_merge_job(&stripped_path),
+    };
+    Ok(match job {
+        Ok(job) => create_job_start(&context, job),
+        Err(e) => create_job_result(&context, &e),
+    })
+}
+
+#[post("/upload_logs")]
+async fn upload_logs(payload: Multipart, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    Ok(match upload::create_upload_job(&context.inbox_dir, payload).await {
+        Ok(job) => create_job_start(&context, job),
+        Err(e) => create_job_result(&context, &e),
+    })
+}
+
+// Polled by the status box until the job is done
+#[get("/job_status")]
+async fn job_status(req: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let query: web::Query<JobQuery> = parse_query(&req)?;
+    Ok(match jobs::get_status(query.id) {
+        Some(status) => match &status.result {
+            Some(result) if status.is_done() => create_job_result(&context, result),
+            _ => create_job_progress(&context, &status),
         },
-        ParseLog::LatestFile => {
-            let latest_file = get_last_modified_file_in_dir(&stripped_path);
-            match index_handler::create_parser_job(&latest_file) {
-                Ok(job) => {
-                    log_processing::add_job(job.clone());
-                    create_job_start(&context, &job)
-                }
-                Err(e) => create_job_result(&context, &e),
-            }
-        }
-    }
+        None => HttpResponse::Ok().body(format!("Job {} is no longer tracked, see the jobs page.", query.id)),
+    })
 }
 
-#[get("/execute_job")]
-async fn execute_job(_: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
-    match log_processing::get_job() {
-        Some(job) => create_job_result(&context, &job.process_logs(&context)),
-        None => HttpResponse::NoContent().into(),
+#[get("/jobs")]
+async fn jobs_page(_: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let data = context.tera.render("jobs.html", &Context::new())?;
+    Ok(HttpResponse::Ok().body(data))
+}
+
+#[get("/jobs_table")]
+async fn jobs_table(_: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let mut jobs_context = Context::new();
+    jobs_context.insert("jobs", &jobs::get_statuses());
+    let data = context.tera.render("jobs_table.html", &jobs_context)?;
+    Ok(HttpResponse::Ok().body(data))
+}
+
+// The polling pages pick up the new state, nothing to swap in
+#[get("/cancel_job")]
+async fn cancel_job(req: HttpRequest) -> Result<HttpResponse, CyclopsError> {
+    let query: web::Query<JobQuery> = parse_query(&req)?;
+    if !jobs::cancel_job(query.id) {
+        println!("Job {} is not queued or running, nothing to cancel", query.id);
     }
+    Ok(HttpResponse::NoContent().finish())
 }
 
 #[get("/")]
-async fn index(_: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
+async fn index(_: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
     index_handler::find_all_summaries(&context.output_dir);
 
-    let result = context.tera.render("index.html", &Context::new());
-    match result {
-        Ok(data) => HttpResponse::Ok().body(data),
-        Err(e) => panic!("Could not render {}:{:?}", "index.html", e),
-    }
+    let data = context.tera.render("index.html", &Context::new())?;
+    Ok(HttpResponse::Ok().body(data))
 }
 
 #[get("/refresh_actions")]
-async fn refresh_actions(_: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
+async fn refresh_actions(_: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
     let cache = index_handler::find_all_summaries(&context.output_dir);
     let last_path = index_handler::get_last_path();
 
@@ -104,182 +228,398 @@ async fn refresh_actions(_: HttpRequest, context: web::Data<AppContext>) -> impl
         index_context.insert("last_path", &last_path);
         index_context.insert("log_dirs", &cache.log_dirs);
     }
-    let result = context.tera.render("index_actions.html", &index_context);
-    match result {
-        Ok(data) => HttpResponse::Ok()
-            .insert_header(("HX-Trigger", "{\"refreshTable\": \"load\"}"))
-            .body(data),
-        Err(e) => panic!("Could not render {}:{:?}", "index.html", e),
-    }
+    let data = context.tera.render("index_actions.html", &index_context)?;
+    Ok(HttpResponse::Ok()
+        .insert_header(("HX-Trigger", "{\"refreshTable\": \"load\"}"))
+        .body(data))
 }
 
 #[get("/index_table")]
-async fn index_table(_: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
-    let data = index_handler::load_summaries(&context);
-    HttpResponse::Ok().body(data)
-}
-
-#[get("/index_search")]
-async fn index_search(req: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
-    let query: web::Query<IndexSearchQuery> = web::Query::from_query(req.query_string()).unwrap();
-
-    match query.action {
-        IndexSearch::PlayerName => {
-            let player_name = query.player_name.clone().unwrap().replace("_", " ");
-            let data = index_handler::search_by_player_name(&player_name, &context);
-            HttpResponse::Ok().body(data)
-        }
-        IndexSearch::LogDirectory => {
-            let data =
-                index_handler::search_by_directory(&query.log_path.clone().unwrap(), &context);
-            HttpResponse::Ok().body(data)
-        }
-        IndexSearch::LogFile => {
-            let data =
-                index_handler::search_by_log_file(&query.log_file.clone().unwrap(), &context);
-            HttpResponse::Ok().body(data)
-        }
-    }
+async fn index_table(req: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let mut query: web::Query<IndexQuery> = parse_query(&req)?;
+    let data = index_handler::generate_index(&context, &mut query)?;
+    Ok(HttpResponse::Ok()
+        .insert_header(("HX-Push-Url", index_handler::index_url(&query)))
+        .body(data))
 }
 
 #[get("/damage_by_power")]
-async fn damage_by_power(req: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
+async fn damage_by_power(req: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
     let qs_non_strict = serde_qs::Config::new(5, false);
-    let query: DamageByPowerQuery = qs_non_strict.deserialize_str(&req.query_string()).unwrap();
+    let mut query: DamageByPowerQuery = qs_non_strict.deserialize_str(req.query_string())?;
+    query.db_path = find_report(&context, &query.report)?;
 
     let mut table_context = Context::new();
-    damage_by_power_table::process(&mut table_context, &query);
-    let result = context.tera.render("damage_by_power.html", &table_context);
-    match result {
-        Ok(data) => HttpResponse::Ok().body(data),
-        Err(e) => {
-            println!("Could not render {}:{:?}", "damage_by_power.html", e);
-            HttpResponse::Ok().body("NO DATA")
-        }
-    }
+    damage_by_power_table::process(&context, &mut table_context, &query)?;
+    let data = context.tera.render("damage_by_power.html", &table_context)?;
+    Ok(HttpResponse::Ok().body(data))
 }
 
 #[get("/damage_table")]
-async fn damage_table(req: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
-    let query: web::Query<TableQuery> = web::Query::from_query(req.query_string()).unwrap();
-
-    match &query.table_name {
-        Some(table_name) => {
-            let mut table_context = Context::new();
-            match table_name {
-                TableNames::DamageDealtByType => {
-                    damage_dealt_by_type_table::process(&mut table_context, &query);
-                }
-                TableNames::DamageTakenByType => {
-                    damage_taken_by_type_table::process(&mut table_context, &query);
-                }
-                TableNames::DamageTakenByMob => {
-                    damage_taken_by_mob_table::process(&mut table_context, &query);
-                }
-                TableNames::DamageTakenByMobPower => {
-                    damage_taken_by_mob_power_table::process(&mut table_context, &query);
-                }
-                TableNames::DPSIntervals => {
-                    dps_interval_table::process(&context, &mut table_context, &query);
-                }
-            }
-            let result = context.tera.render("simple_table.html", &table_context);
-            match result {
-                Ok(data) => HttpResponse::Ok().body(data),
-                Err(e) => {
-                    println!("Could not render {}:{:?}", "simple_table.html", e);
-                    HttpResponse::Ok().body("NO DATA")
-                }
-            }
+async fn damage_table(req: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let mut query: web::Query<TableQuery> = parse_query(&req)?;
+    query.db_path = find_report(&context, &query.report)?;
+
+    let table_name = match &query.table_name {
+        Some(table_name) => table_name,
+        None => return Err(CyclopsError::BadRequest(String::from("No table_name given"))),
+    };
+    let mut table_context = Context::new();
+    process_table(&context, &mut table_context, table_name, &query);
+    let data = context.tera.render("simple_table.html", &table_context)?;
+    Ok(HttpResponse::Ok().body(data))
+}
+
+// The summary page's interval selector, the choice is remembered for the character
+#[post("/dps_interval")]
+async fn dps_interval_query(body: String, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let mut query: TableQuery = serde_qs::from_str(&body)?;
+    query.db_path = find_report(&context, &query.report)?;
+    dps_interval_table::remember_interval(&context, &query);
+
+    let mut table_context = Context::new();
+    dps_interval_table::process(&context, &mut table_context, &query);
+    let data = context.tera.render("simple_table.html", &table_context)?;
+    Ok(HttpResponse::Ok().body(data))
+}
+
+// Every table shown with simple_table.html
+fn process_table(context: &AppContext, table_context: &mut Context, table_name: &TableNames, query: &TableQuery) {
+    match table_name {
+        TableNames::DamageDealtByType => {
+            damage_dealt_by_type_table::process(table_context, query);
+        }
+        TableNames::DamageTakenByType => {
+            damage_taken_by_type_table::process(table_context, query);
+        }
+        TableNames::DamageTakenByMob => {
+            damage_taken_by_mob_table::process(table_context, query);
+        }
+        TableNames::DamageTakenByMobPower => {
+            damage_taken_by_mob_power_table::process(table_context, query);
+        }
+        TableNames::DPSIntervals => {
+            dps_interval_table::process(context, table_context, query);
+        }
+        TableNames::ProcRates => {
+            proc_rate_table::process(table_context, query);
+        }
+        TableNames::DefeatedTargets => {
+            defeated_targets_table::process(table_context, query);
         }
-        None => HttpResponse::Ok().body("NO DATA"),
     }
 }
 
 #[get("/powers_and_mobs")]
-async fn powers_and_mobs_query(req: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
-    let query: web::Query<PowersMobsData> = web::Query::from_query(req.query_string()).unwrap();
+async fn powers_and_mobs_query(req: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let mut query: web::Query<PowersMobsData> = parse_query(&req)?;
+    query.db_path = find_report(&context, &query.report)?;
 
     let mut table_context = Context::new();
     powers_and_mobs_table::process(&mut table_context, &query);
 
-    let result = context
+    let data = context
         .tera
-        .render("powers_and_mobs_table.html", &table_context);
-    match result {
-        Ok(data) => HttpResponse::Ok().body(data),
-        Err(e) => panic!("Could not render {}:{:?}", "powers_and_mobs_table.html", e),
+        .render("powers_and_mobs_table.html", &table_context)?;
+    Ok(HttpResponse::Ok().body(data))
+}
+
+#[get("/death_recap")]
+async fn death_recap_query(req: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let mut query: web::Query<DeathRecapQuery> = parse_query(&req)?;
+    query.db_path = find_report(&context, &query.report)?;
+
+    let mut recap_context = Context::new();
+    death_recap::process(&mut recap_context, &query);
+    let data = context.tera.render("death_recap.html", &recap_context)?;
+    Ok(HttpResponse::Ok().body(data))
+}
+
+#[get("/debuff_effectiveness")]
+async fn debuff_effectiveness_query(req: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let mut query: web::Query<DebuffEffectivenessQuery> = parse_query(&req)?;
+    query.db_path = find_report(&context, &query.report)?;
+
+    let mut debuff_context = Context::new();
+    debuff_effectiveness::process(&mut debuff_context, &query);
+    let data = context.tera.render("debuff_effectiveness.html", &debuff_context)?;
+    Ok(HttpResponse::Ok().body(data))
+}
+
+#[get("/consumables")]
+async fn consumables_query(req: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let mut query: web::Query<ConsumablesQuery> = parse_query(&req)?;
+    query.db_path = find_report(&context, &query.report)?;
+
+    let mut consumables_context = Context::new();
+    consumables::process(&mut consumables_context, &query);
+    let data = context.tera.render("consumables.html", &consumables_context)?;
+    Ok(HttpResponse::Ok().body(data))
+}
+
+#[get("/location_timeline")]
+async fn location_timeline_query(req: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let mut query: web::Query<LocationTimelineQuery> = parse_query(&req)?;
+    query.db_path = find_report(&context, &query.report)?;
+
+    let mut timeline_context = Context::new();
+    location_timeline::process(&mut timeline_context, &query);
+    let data = context.tera.render("location_timeline.html", &timeline_context)?;
+    Ok(HttpResponse::Ok().body(data))
+}
+
+#[get("/scope")]
+async fn scope_query(req: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let mut query: web::Query<ScopeQuery> = parse_query(&req)?;
+    query.db_path = find_report(&context, &query.report)?;
+
+    let scope_report = location_timeline::create_scope(&query)
+        .and_then(|scope_db| db::report_id(&context.output_dir, &scope_db));
+    Ok(match scope_report {
+        Some(report_id) => HttpResponse::Found()
+            .insert_header(("Location", location_timeline::summary_url(query.key, &report_id)))
+            .finish(),
+        None => HttpResponse::Ok().body("Unable to create a report for this part of the session"),
+    })
+}
+
+#[get("/progression")]
+async fn progression_query(req: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let mut query: web::Query<ProgressionQuery> = parse_query(&req)?;
+    query.db_path = find_report(&context, &query.report)?;
+
+    let mut progression_context = Context::new();
+    progression::process(&context, &mut progression_context, &query);
+    let data = context.tera.render("progression.html", &progression_context)?;
+    Ok(HttpResponse::Ok().body(data))
+}
+
+#[get("/market")]
+async fn market_query(req: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let mut query: web::Query<MarketQuery> = parse_query(&req)?;
+    query.db_path = find_report(&context, &query.report)?;
+
+    let mut market_context = Context::new();
+    market::process(&context, &mut market_context, &query);
+    let data = context.tera.render("market.html", &market_context)?;
+    Ok(HttpResponse::Ok().body(data))
+}
+
+#[get("/team_roster")]
+async fn team_roster_query(req: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let mut query: web::Query<TeamRosterQuery> = parse_query(&req)?;
+    query.db_path = find_report(&context, &query.report)?;
+
+    let mut roster_context = Context::new();
+    team_roster::process(&context, &mut roster_context, &query);
+    let data = context.tera.render("team_roster.html", &roster_context)?;
+    Ok(HttpResponse::Ok().body(data))
+}
+
+#[get("/team_report")]
+async fn team_report_query(req: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let mut query: web::Query<TeamReportQuery> = parse_query(&req)?;
+    query.db_path = find_report(&context, &query.report)?;
+
+    let mut report_context = Context::new();
+    team_report::process(&mut report_context, &query);
+    let data = context.tera.render("team_report.html", &report_context)?;
+    Ok(HttpResponse::Ok().body(data))
+}
+
+#[get("/annotations")]
+async fn annotations_query(req: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let mut query: web::Query<AnnotationQuery> = parse_query(&req)?;
+    query.db_path = find_report(&context, &query.report)?;
+
+    let mut annotation_context = Context::new();
+    annotations::process(&mut annotation_context, &query);
+    let data = context.tera.render("annotations.html", &annotation_context)?;
+    Ok(HttpResponse::Ok().body(data))
+}
+
+#[post("/annotations")]
+async fn annotations_update(body: String, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let mut query: AnnotationQuery = serde_qs::from_str(&body)?;
+    query.db_path = find_report(&context, &query.report)?;
+
+    let mut annotation_context = Context::new();
+    annotations::process_action(&mut annotation_context, &query);
+    let data = context.tera.render("annotations.html", &annotation_context)?;
+    Ok(HttpResponse::Ok().body(data))
+}
+
+#[get("/chart")]
+async fn chart_query(req: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let mut query: web::Query<ChartQuery> = parse_query(&req)?;
+    query.db_path = find_report(&context, &query.report)?;
+
+    let svg = charts::render(&query);
+    let mut response = HttpResponse::Ok();
+    response.content_type("image/svg+xml");
+    if query.download.unwrap_or(false) {
+        response.insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", charts::file_name(&query)),
+        ));
     }
+    Ok(response.body(svg))
 }
 
 #[get("/summary")]
-async fn player_summary_query(req: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
-    let query: web::Query<SummaryQuery> = web::Query::from_query(req.query_string()).unwrap();
+async fn player_summary_query(req: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let mut query: web::Query<SummaryQuery> = parse_query(&req)?;
+    query.db_path = find_report(&context, &query.report)?;
     let mut report_context = Context::new();
 
-    player_summary_table::process(&context, &mut report_context, &query);
-    let result = context
+    if !player_summary_table::process(&context, &mut report_context, &query) {
+        return Err(CyclopsError::NotFound(format!(
+            "No session {} in report {}",
+            query.key, query.report
+        )));
+    }
+    let data = context
         .tera
-        .render("player_attack_report.html", &report_context);
-    match result {
-        Ok(data) => HttpResponse::Ok().body(data),
-        Err(e) => panic!("Could not render {}:{:?}", "player_attack_report.html", e),
+        .render("player_attack_report.html", &report_context)?;
+    Ok(HttpResponse::Ok().body(data))
+}
+
+// The summary page as one file that opens without the server
+#[get("/export")]
+async fn export_query(req: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let mut query: web::Query<SummaryQuery> = parse_query(&req)?;
+    query.db_path = find_report(&context, &query.report)?;
+
+    let data = export::render_report(&context, &query)?;
+    Ok(HttpResponse::Ok()
+        .content_type("text/html; charset=utf-8")
+        .insert_header((
+            "Content-Disposition",
+            format!("attachment; filename=\"{}\"", export::file_name(query.key)),
+        ))
+        .body(data))
+}
+
+#[get("/benchmarks")]
+async fn benchmarks(req: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let query: web::Query<BenchmarkQuery> = parse_query(&req)?;
+
+    let mut benchmark_context = Context::new();
+    benchmark_handler::process(&context, &mut benchmark_context, &query);
+    let data = context.tera.render("benchmarks.html", &benchmark_context)?;
+    Ok(HttpResponse::Ok().body(data))
+}
+
+#[get("/compare")]
+async fn compare_query(req: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let mut query: web::Query<CompareQuery> = parse_query(&req)?;
+    query.left_db_path = find_report(&context, &query.left_report)?;
+    query.right_db_path = find_report(&context, &query.right_report)?;
+
+    let mut compare_context = Context::new();
+    if !compare_summaries::process(&mut compare_context, &query) {
+        return Err(CyclopsError::NotFound(String::from("Unable to find one or both summaries")));
     }
+    let data = context.tera.render("compare.html", &compare_context)?;
+    Ok(HttpResponse::Ok().body(data))
 }
 
 #[get("/monitor")]
-async fn monitor(_: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
+async fn monitor(_: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
     let monitor_context = Context::new();
 
-    let result = context
+    let data = context
         .tera
-        .render("monitor.html", &monitor_context);
-    match result {
-        Ok(data) => HttpResponse::Ok().body(data),
-        Err(e) => panic!("Could not render {}:{:?}", "monitor.html", e),
-    }
+        .render("monitor.html", &monitor_context)?;
+    Ok(HttpResponse::Ok().body(data))
 }
 
 #[get("/monitor_messages")]
-async fn monitor_messages(_: HttpRequest, context: web::Data<AppContext>) -> impl Responder {
+async fn monitor_messages(_: HttpRequest, context: web::Data<AppContext>) -> Result<HttpResponse, CyclopsError> {
+    let data = render_monitor_messages(&context)?;
+    Ok(HttpResponse::Ok().body(data))
+}
+
+// css, js and images built into the binary or from the assets directory
+#[get("/resources/{name}")]
+async fn resource_query(name: web::Path<String>) -> Result<HttpResponse, CyclopsError> {
+    let name = name.into_inner();
+    match assets::resource(&name) {
+        Some(data) => {
+            let extension = name.rsplit('.').next().unwrap_or_default();
+            Ok(HttpResponse::Ok()
+                .content_type(fs::file_extension_to_mime(extension))
+                .body(data.into_owned()))
+        }
+        None => Err(CyclopsError::NotFound(format!("No resource named {}", name))),
+    }
+}
+
+// Pushes the messages to the monitor page as they change, in place of polling /monitor_messages
+#[get("/monitor_stream")]
+async fn monitor_stream_query(_: HttpRequest, context: web::Data<AppContext>) -> HttpResponse {
+    monitor_stream::event_stream("messages", move || match render_monitor_messages(&context) {
+        Ok(data) => data,
+        Err(e) => {
+            println!("Could not render messages.html:{:?}", e);
+            String::from("NO DATA")
+        }
+    })
+}
+
+fn render_monitor_messages(context: &AppContext) -> Result<String, tera::Error> {
     let mut message_context = Context::new();
 
     let (now, stats, messages) = monitoring::get_messages();
     message_context.insert("now", &now.timestamp());
     message_context.insert("stats", &stats);
     message_context.insert("messages", &messages);
-    let result = context
-        .tera
-        .render("messages.html", &message_context);
-    match result {
-        Ok(data) => HttpResponse::Ok().body(data),
-        Err(e) => panic!("Could not render {}:{:?}", "monitoring_messages.html", e),
-    }
+    context.tera.render("messages.html", &message_context)
 }
 
 #[actix_web::main]
 pub async fn start(context: AppContext) -> std::io::Result<()> {
     let address = context.web_address.to_string();
     let port = context.web_port;
+    jobs::start_worker(context.clone());
     let server = HttpServer::new(move || {
         App::new()
             .app_data(web::Data::new(context.clone()))
+            .wrap(ErrorHandlers::new().default_handler(render_error_page))
             .service(index)
             .service(refresh_actions)
             .service(index_table)
-            .service(index_search)
-            .service(execute_job)
+            .service(job_status)
+            .service(jobs_page)
+            .service(jobs_table)
+            .service(cancel_job)
             .service(parse_request)
+            .service(upload_logs)
             .service(player_summary_query)
+            .service(export_query)
             .service(damage_by_power)
             .service(damage_table)
+            .service(dps_interval_query)
             .service(powers_and_mobs_query)
+            .service(death_recap_query)
+            .service(debuff_effectiveness_query)
+            .service(compare_query)
+            .service(chart_query)
+            .service(annotations_query)
+            .service(annotations_update)
+            .service(progression_query)
+            .service(market_query)
+            .service(team_roster_query)
+            .service(team_report_query)
+            .service(consumables_query)
+            .service(location_timeline_query)
+            .service(scope_query)
+            .service(benchmarks)
             .service(monitor)
             .service(monitor_messages)
-            .service(fs::Files::new(
-                "/resources",
-                context.resources_dir.to_owned(),
-            ))
+            .service(monitor_stream_query)
+            .service(api::scope())
+            .service(resource_query)
             .service(
                 fs::Files::new("/", context.output_dir.to_owned())
                     .index_file("index.html")
@@ -288,3 +628,62 @@ pub async fn start(context: AppContext) -> std::io::Result<()> {
     });
     server.bind((address, port))?.run().await
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use actix_web::{http::StatusCode, test};
+    use tera::Tera;
+
+    use super::*;
+
+    fn test_context(templates: &[(&str, &str)]) -> AppContext {
+        let mut tera = Tera::default();
+        tera.add_raw_templates(templates.iter().copied()).unwrap();
+        let output_dir = std::env::temp_dir().join("cyclops_web_tests");
+        AppContext {
+            working_dir: PathBuf::from("."),
+            inbox_dir: output_dir.join("inbox"),
+            output_dir,
+            dps_interval: 60,
+            export_html: false,
+            web_address: String::from("127.0.0.1"),
+            web_port: 11227,
+            tera,
+        }
+    }
+
+    #[actix_web::test]
+    async fn template_errors_are_not_a_200() {
+        let context = test_context(&[("jobs.html", "{{ missing.value }}")]);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(context))
+                .wrap(ErrorHandlers::new().default_handler(render_error_page))
+                .service(jobs_page),
+        )
+        .await;
+        let res = test::call_service(&app, test::TestRequest::get().uri("/jobs").to_request()).await;
+        assert_eq!(res.status(), StatusCode::INTERNAL_SERVER_ERROR);
+        let body = test::read_body(res).await;
+        assert!(String::from_utf8_lossy(&body).contains("Unable to render the page"));
+    }
+
+    #[actix_web::test]
+    async fn unknown_reports_are_not_found() {
+        let context = test_context(&[("death_recap.html", "")]);
+        let app = test::init_service(
+            App::new()
+                .app_data(web::Data::new(context))
+                .wrap(ErrorHandlers::new().default_handler(render_error_page))
+                .service(death_recap_query),
+        )
+        .await;
+        let req = test::TestRequest::get()
+            .uri("/death_recap?report=0000000000000000&key=1")
+            .to_request();
+        let res = test::call_service(&app, req).await;
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+}
@@ -0,0 +1,34 @@
+use actix_web::{web::Bytes, HttpResponse};
+use futures_util::{stream, StreamExt};
+
+use crate::monitoring;
+
+// Server-sent events with the monitor state. An event is sent when the stream opens
+// and again each time the monitor finishes a pass over the log, render builds the data of the event.
+pub fn event_stream<F>(event: &'static str, render: F) -> HttpResponse
+where
+    F: Fn() -> String + 'static,
+{
+    let updates = stream::unfold(monitoring::subscribe(), |mut updates| async move {
+        updates.changed().await.ok()?;
+        Some(((), updates))
+    });
+    let events = updates.map(move |_| Ok::<_, actix_web::Error>(format_event(event, &render())));
+
+    HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(("Cache-Control", "no-cache"))
+        .streaming(events)
+}
+
+// Every line of the data needs its own data: field
+fn format_event(event: &str, data: &str) -> Bytes {
+    let mut message = format!("event: {}\n", event);
+    for line in data.lines() {
+        message.push_str("data: ");
+        message.push_str(line);
+        message.push('\n');
+    }
+    message.push('\n');
+    Bytes::from(message)
+}
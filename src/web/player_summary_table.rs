@@ -1,37 +1,72 @@
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
 use serde::Deserialize;
 use tera::Context;
+use utoipa::IntoParams;
 
-use crate::{db, web::TableNames, AppContext};
+use crate::{db, game_data, web::{location_timeline, preferences, TableNames}, AppContext};
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct SummaryQuery {
     pub key: i32,
+    pub report: String,
+    // Found from report in the index, never taken from the url
+    #[serde(skip)]
     pub db_path: String,
 }
 
-pub fn process(app_context: &AppContext, report_context: &mut Context, query: &SummaryQuery) {
+// False when the report has no session with this key
+pub fn process(app_context: &AppContext, report_context: &mut Context, query: &SummaryQuery) -> bool {
     let db_path: PathBuf = query.db_path.clone().into();
     let mut conn = db::get_file_conn(db_path);
 
     let binding = db::queries::get_summary(&mut conn, query.key);
-    let summary = binding.first().unwrap();
-    report_context.insert("db_path", &query.db_path);
+    let summary = match binding.first() {
+        Some(s) => s,
+        None => return false,
+    };
+    report_context.insert("report", &query.report);
 
     report_context.insert("summary", &summary);
+    if let Some(scope) = db::queries::get_report_scope(&mut conn, summary.summary_key) {
+        if let Some(report_id) = db::report_id(&app_context.output_dir, Path::new(&scope.source_db)) {
+            report_context.insert(
+                "full_session_url",
+                &location_timeline::summary_url(scope.summary_key, &report_id),
+            );
+        }
+        report_context.insert("scope", &scope);
+    }
+    if !db::queries::get_merge_sources(&mut conn).is_empty() {
+        report_context.insert("merged", &true);
+    }
     report_context.insert(
         "rewards_defeats",
         &db::queries::get_rewards_defeats(&mut conn, summary.summary_key, &summary.player_name),
     );
-    report_context.insert(
-        "total_damage",
-        &db::queries::get_total_damage_report(&mut conn, summary.summary_key),
-    );
+    if let Some(total_damage) = db::queries::get_total_damage_report(&mut conn, summary.summary_key) {
+        report_context.insert("total_damage", &total_damage);
+    }
+    if let Some(benchmark) = db::queries::get_benchmark_report(&mut conn, summary.summary_key) {
+        report_context.insert("benchmark", &benchmark);
+    }
+    if let Some(deaths) = db::queries::get_death_count(&mut conn, summary.summary_key) {
+        report_context.insert("deaths", &deaths);
+    }
     if let Some(damage_taken) = db::queries::get_damage_taken_report(&mut conn, summary.summary_key) {
         report_context.insert("damage_taken", &damage_taken);
     }
-    report_context.insert("dps_interval", &app_context.dps_interval);
+    report_context.insert(
+        "dps_interval",
+        &preferences::get_dps_interval(app_context, &summary.player_name),
+    );
+    report_context.insert(
+        "dps_interval_options",
+        &preferences::dps_interval_options(app_context, &summary.player_name),
+    );
+    report_context.insert("mob_levels", &game_data::MINION_HP_TABLE.as_slice());
+    report_context.insert("mob_level", &game_data::DEFAULT_MOB_LEVEL);
     report_context.insert("dps_report", &TableNames::DPSIntervals);
     report_context.insert("damage_dealt_by_type", &TableNames::DamageDealtByType);
     report_context.insert("damage_taken_by_type", &TableNames::DamageTakenByType);
@@ -40,5 +75,7 @@ pub fn process(app_context: &AppContext, report_context: &mut Context, query: &S
         "damage_taken_by_mob_power",
         &TableNames::DamageTakenByMobPower,
     );
-
+    report_context.insert("proc_rates", &TableNames::ProcRates);
+    report_context.insert("defeated_targets", &TableNames::DefeatedTargets);
+    true
 }
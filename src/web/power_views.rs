@@ -0,0 +1,84 @@
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+// Stored next to summary.db so a re-parse, which replaces the db, keeps the views
+const POWER_VIEWS_FILE: &str = "power_views.json";
+
+lazy_static! {
+    static ref POWER_VIEWS_LOCK: Mutex<()> = Mutex::new(());
+}
+
+// One change to the attack summary by power. Rows are named by power instead of their
+// position in the table, so the edits can be replayed on a fresh table to rebuild a view.
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq)]
+pub enum PowerEdit {
+    Merge(Vec<String>),
+    MergeGuess,
+    Delete(Vec<String>),
+    RemoveNonDamaging,
+}
+
+// View name to the edits that make it, in the order they were made
+pub type PowerViews = BTreeMap<String, Vec<PowerEdit>>;
+
+fn power_views_path(db_path: &Path) -> PathBuf {
+    match db_path.parent() {
+        Some(dir) => dir.join(POWER_VIEWS_FILE),
+        None => PathBuf::from(POWER_VIEWS_FILE),
+    }
+}
+
+// All views for a report directory keyed by summary key
+fn load_all(db_path: &Path) -> BTreeMap<String, PowerViews> {
+    match fs::read_to_string(power_views_path(db_path)) {
+        Ok(data) => serde_json::from_str(&data).unwrap_or_default(),
+        Err(_) => BTreeMap::new(),
+    }
+}
+
+fn save_all(db_path: &Path, views: &BTreeMap<String, PowerViews>) {
+    match serde_json::to_string_pretty(views) {
+        Ok(data) => {
+            if let Err(e) = fs::write(power_views_path(db_path), data) {
+                println!("Unable to save power views: {:?}", e);
+            }
+        }
+        Err(e) => println!("Unable to serialize power views: {:?}", e),
+    }
+}
+
+pub fn load(db_path: &Path, key: i32) -> PowerViews {
+    load_all(db_path)
+        .remove(&key.to_string())
+        .unwrap_or_default()
+}
+
+// Saving under an existing name replaces that view
+pub fn save(db_path: &Path, key: i32, name: &str, edits: Vec<PowerEdit>) {
+    let _guard = POWER_VIEWS_LOCK.lock().unwrap();
+    let mut views = load_all(db_path);
+    views
+        .entry(key.to_string())
+        .or_default()
+        .insert(name.to_string(), edits);
+    save_all(db_path, &views);
+}
+
+pub fn delete(db_path: &Path, key: i32, name: &str) {
+    let _guard = POWER_VIEWS_LOCK.lock().unwrap();
+    let mut views = load_all(db_path);
+    if let Some(summary_views) = views.get_mut(&key.to_string()) {
+        summary_views.remove(name);
+        if summary_views.is_empty() {
+            views.remove(&key.to_string());
+        }
+    }
+    save_all(db_path, &views);
+}
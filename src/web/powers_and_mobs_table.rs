@@ -1,7 +1,8 @@
 use tera::Context;
 
-use crate::{db, game_data::{self, get_mob_hp}, models::DamageDealtToMobByPower};
+use crate::{db, game_data, models::DamageDealtToMobByPower};
 
+use super::mob_ranks::MobRanks;
 use super::web_structs_enums::{PowersMobsData, SortDirection};
 
 pub fn process(tera_context: &mut Context, query: &PowersMobsData) {
@@ -20,44 +21,44 @@ pub fn process(tera_context: &mut Context, query: &PowersMobsData) {
     tera_context.insert("mobs_damaged", &db::queries::get_mobs_damaged(&query));
     tera_context.insert("mob_levels", &game_data::MINION_HP_TABLE.as_slice());
     tera_context.insert("headers", &headers());
-    if query.mob_level.is_some() {
-        tera_context.insert("mob_level", &i32::from_str_radix(&query.mob_level.as_ref().unwrap(), 10).unwrap());
-    } else {
-        tera_context.insert("mob_level", &54);
-    } 
-
-    match db::queries::get_damage_dealt_by_power_or_mob(&query) {
-        Some(mut data) => {
-            let mob_level = query.mob_level.as_ref().unwrap();
-            data.iter_mut()
-                .for_each(|r| r.overkill = 
-                           calc_overkill(r.damage_per_hit,
-                           get_mob_hp(&mob_level).into()));
+    let mob_level = game_data::mob_level(&query.mob_level);
+    tera_context.insert("mob_level", &mob_level);
 
-            if query.sort_field.is_some() {
-                sort(
-                    query.sort_field.clone().unwrap(),
-                    query.sort_dir.clone().unwrap(),
-                    &mut data,
-                );
-            }
-
-            if query.power_name.is_some() && !query.power_name.as_ref().unwrap().is_empty() {
-                tera_context.insert("power_name", &query.power_name);
-            } else if query.mob_name.is_some() && !query.mob_name.as_ref().unwrap().is_empty() {
-                tera_context.insert("mob_name", &query.mob_name);
-            }
-            let rows = flatten(data);
-            tera_context.insert("table_rows", &rows);
+    if let Some((data, ranks)) = rows(query) {
+        if query.power_name.is_some() && !query.power_name.as_ref().unwrap().is_empty() {
+            tera_context.insert("power_name", &query.power_name);
+        } else if query.mob_name.is_some() && !query.mob_name.as_ref().unwrap().is_empty() {
+            tera_context.insert("mob_name", &query.mob_name);
         }
-        None => (),
+        let rows = flatten(data, &ranks);
+        tera_context.insert("table_rows", &rows);
     }
+}
 
+// Rows with overkill against the selected mob level, and the ranks used for it
+pub fn rows(query: &PowersMobsData) -> Option<(Vec<DamageDealtToMobByPower>, MobRanks)> {
+    let mut data = db::queries::get_damage_dealt_by_power_or_mob(query)?;
+    let mob_level = game_data::mob_level(&query.mob_level);
+    let mut conn = db::get_file_conn(query.db_path.clone().into());
+    let ranks = MobRanks::load(&mut conn, query.key, mob_level);
+    data.iter_mut()
+        .for_each(|r| r.overkill = calc_overkill(r.damage_per_hit, ranks.get(&r.target_name).hp));
+
+    if let Some(sort_field) = &query.sort_field {
+        sort(
+            sort_field.clone(),
+            query.sort_dir.clone().unwrap_or(SortDirection::DESC),
+            &mut data,
+            &ranks,
+        );
+    }
+    Some((data, ranks))
 }
 
 pub fn headers() -> Vec<(&'static str, &'static str)> {
     let mut headers = Vec::<(&'static str, &'static str)>::new();
     headers.push(("target_name", "Target Name"));
+    headers.push(("rank", "Rank"));
     headers.push(("power_name", "Power Name"));
     headers.push(("proc_fires", "Procs"));
     headers.push(("hits", "Hits"));
@@ -71,12 +72,14 @@ pub fn headers() -> Vec<(&'static str, &'static str)> {
     headers
 }
 
-pub fn flatten(data: Vec<DamageDealtToMobByPower>) -> Vec<Vec<String>> {
+pub fn flatten(data: Vec<DamageDealtToMobByPower>, ranks: &MobRanks) -> Vec<Vec<String>> {
     let mut result = Vec::<Vec<String>>::new();
 
     for d in data {
         let mut row = Vec::<String>::new();
+        let rank = ranks.get(&d.target_name).describe();
         row.push(d.target_name);
+        row.push(rank);
         row.push(d.power_name);
         match d.proc_fires {
             Some(count) => row.push(count.to_string()),
@@ -94,12 +97,16 @@ pub fn flatten(data: Vec<DamageDealtToMobByPower>) -> Vec<Vec<String>> {
     result
 }
 
-pub fn sort(sort_field: String, sort_dir: SortDirection, data: &mut Vec<DamageDealtToMobByPower>) {
+pub fn sort(sort_field: String, sort_dir: SortDirection, data: &mut [DamageDealtToMobByPower], ranks: &MobRanks) {
     match sort_field.as_str() {
         "target_name" => match sort_dir {
             SortDirection::DESC => data.sort_by(|a, b| b.target_name.cmp(&a.target_name)),
             SortDirection::ASC => data.sort_by(|a, b| a.target_name.cmp(&b.target_name)),
         },
+        "rank" => match sort_dir {
+            SortDirection::DESC => data.sort_by_key(|a| std::cmp::Reverse(ranks.get(&a.target_name).hp)),
+            SortDirection::ASC => data.sort_by_key(|a| ranks.get(&a.target_name).hp),
+        },
         "power_name" => match sort_dir {
             SortDirection::DESC => data.sort_by(|a, b| b.power_name.cmp(&a.power_name)),
             SortDirection::ASC => data.sort_by(|a, b| a.power_name.cmp(&b.power_name)),
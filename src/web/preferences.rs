@@ -0,0 +1,126 @@
+use std::{collections::HashMap, fs, path::PathBuf, sync::Mutex};
+
+use lazy_static::lazy_static;
+use serde::{Deserialize, Serialize};
+
+use crate::AppContext;
+
+const PREFERENCES_FILE: &str = "preferences.json";
+
+// Choices offered on the summary page, the command line value is always added
+const DPS_INTERVAL_OPTIONS: [usize; 7] = [5, 10, 15, 30, 60, 90, 120];
+
+lazy_static! {
+    static ref PREFERENCES: Mutex<Option<Preferences>> = Mutex::new(None);
+}
+
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+struct Preferences {
+    // Player name to the last DPS interval used for that character
+    #[serde(default)]
+    dps_intervals: HashMap<String, usize>,
+    // Player name to groups of powers merged into one row of the attack summary
+    #[serde(default)]
+    merge_rules: HashMap<String, Vec<Vec<String>>>,
+}
+
+fn preferences_path(app_context: &AppContext) -> PathBuf {
+    app_context.output_dir.join(PREFERENCES_FILE)
+}
+
+fn load(app_context: &AppContext) -> Preferences {
+    match fs::read_to_string(preferences_path(app_context)) {
+        Ok(data) => serde_json::from_str(&data).unwrap_or_default(),
+        Err(_) => Preferences::default(),
+    }
+}
+
+pub fn get_dps_interval(app_context: &AppContext, player_name: &str) -> usize {
+    let mut preferences = PREFERENCES.lock().unwrap();
+    preferences
+        .get_or_insert_with(|| load(app_context))
+        .dps_intervals
+        .get(player_name)
+        .copied()
+        .unwrap_or(app_context.dps_interval)
+}
+
+// The interval from the request, else the one saved for the character, else the command line value
+pub fn dps_interval(app_context: &AppContext, player_name: &str, requested: Option<usize>) -> usize {
+    match requested {
+        Some(interval) if interval > 0 => interval,
+        _ => get_dps_interval(app_context, player_name),
+    }
+}
+
+pub fn set_dps_interval(app_context: &AppContext, player_name: &str, interval: usize) {
+    let mut guard = PREFERENCES.lock().unwrap();
+    let preferences = guard.get_or_insert_with(|| load(app_context));
+    if preferences.dps_intervals.get(player_name) == Some(&interval) {
+        return;
+    }
+    preferences
+        .dps_intervals
+        .insert(player_name.to_string(), interval);
+    save(app_context, preferences);
+}
+
+fn save(app_context: &AppContext, preferences: &Preferences) {
+    match serde_json::to_string_pretty(preferences) {
+        Ok(data) => {
+            if let Err(e) = fs::write(preferences_path(app_context), data) {
+                println!("Unable to save preferences: {:?}", e);
+            }
+        }
+        Err(e) => println!("Unable to serialize preferences: {:?}", e),
+    }
+}
+
+pub fn get_merge_rules(app_context: &AppContext, player_name: &str) -> Vec<Vec<String>> {
+    let mut preferences = PREFERENCES.lock().unwrap();
+    preferences
+        .get_or_insert_with(|| load(app_context))
+        .merge_rules
+        .get(player_name)
+        .cloned()
+        .unwrap_or_default()
+}
+
+pub fn add_merge_rule(app_context: &AppContext, player_name: &str, powers: Vec<String>) {
+    let mut guard = PREFERENCES.lock().unwrap();
+    let preferences = guard.get_or_insert_with(|| load(app_context));
+    let rules = preferences
+        .merge_rules
+        .entry(player_name.to_string())
+        .or_default();
+    if rules.contains(&powers) {
+        return;
+    }
+    rules.push(powers);
+    save(app_context, preferences);
+}
+
+pub fn remove_merge_rule(app_context: &AppContext, player_name: &str, index: usize) {
+    let mut guard = PREFERENCES.lock().unwrap();
+    let preferences = guard.get_or_insert_with(|| load(app_context));
+    match preferences.merge_rules.get_mut(player_name) {
+        Some(rules) if index < rules.len() => {
+            rules.remove(index);
+            if rules.is_empty() {
+                preferences.merge_rules.remove(player_name);
+            }
+        }
+        _ => return,
+    }
+    save(app_context, preferences);
+}
+
+// The saved interval for the character is listed too, so the select can show it
+pub fn dps_interval_options(app_context: &AppContext, player_name: &str) -> Vec<usize> {
+    let mut options = DPS_INTERVAL_OPTIONS.to_vec();
+    options.push(app_context.dps_interval);
+    options.push(get_dps_interval(app_context, player_name));
+    options.sort();
+    options.dedup();
+    options
+}
@@ -0,0 +1,164 @@
+use std::{cmp::Reverse, collections::HashSet};
+
+use serde::Serialize;
+use tera::Context;
+use utoipa::ToSchema;
+
+use crate::{
+    db, game_data,
+    models::ProcRate,
+    web::{SortDirection, TableQuery},
+};
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ProcRateRow {
+    proc_name: String,
+    power_name: String,
+    activations: i32,
+    fires: i32,
+    observed_rate: f32,
+    ppm: Option<f32>,
+    average_recharge: Option<i32>,
+    expected_rate: Option<f32>,
+}
+
+pub fn process(context: &mut Context, query: &TableQuery) {
+    match &query.sort_dir {
+        Some(dir) => match dir {
+            SortDirection::ASC => context.insert("sort_dir", &SortDirection::DESC),
+            SortDirection::DESC => context.insert("sort_dir", &SortDirection::ASC),
+        },
+        None => context.insert("sort_dir", &SortDirection::DESC),
+    };
+    match rows(query) {
+        Some(rows) => {
+            context.insert("table_title", "Proc Rates");
+            context.insert("table_name", &query.table_name);
+            context.insert("headers", &headers());
+            context.insert("table_rows", &flatten(rows));
+        }
+        None => println!("Proc rates returned no data"),
+    };
+}
+
+pub fn rows(query: &TableQuery) -> Option<Vec<ProcRateRow>> {
+    let data = db::queries::get_proc_rates_query(query)?;
+    let area_powers: HashSet<String> = db::queries::get_power_targets(query)
+        .into_iter()
+        .filter(|(_, ate)| ate.is_some_and(|t| t > 1))
+        .map(|(power_name, _)| power_name)
+        .collect();
+    let mut rows = calc_rates(data, &area_powers);
+    if let Some(sort_field) = &query.sort_field {
+        sort(
+            sort_field.clone(),
+            query.sort_dir.clone().unwrap_or(SortDirection::DESC),
+            &mut rows,
+        );
+    }
+    Some(rows)
+}
+
+// No expected chance for procs slotted in area powers, their area factor isn't known
+fn calc_rates(data: Vec<ProcRate>, area_powers: &HashSet<String>) -> Vec<ProcRateRow> {
+    let mut result = Vec::<ProcRateRow>::new();
+
+    for d in data {
+        let observed_rate = if d.activations > 0 {
+            d.fires as f32 / d.activations as f32 * 100.0
+        } else {
+            0.0
+        };
+        let ppm = game_data::find_proc(&d.proc_name).and_then(|p| p.ppm);
+        let expected_rate = match (ppm, d.average_recharge) {
+            (Some(ppm), Some(recharge)) if !area_powers.contains(&d.power_name) => Some(game_data::expected_proc_chance(ppm, recharge)),
+            _ => None,
+        };
+        result.push(ProcRateRow {
+            proc_name: d.proc_name,
+            power_name: d.power_name,
+            activations: d.activations,
+            fires: d.fires,
+            observed_rate,
+            ppm,
+            average_recharge: d.average_recharge,
+            expected_rate,
+        });
+    }
+    result
+}
+
+fn headers() -> Vec<(&'static str, &'static str)> {
+    vec![
+        ("proc_name", "Proc"),
+        ("power_name", "Slotted Power"),
+        ("activations", "Activations"),
+        ("fires", "Proc Fires"),
+        ("observed_rate", "Fires Per Activation %"),
+        ("ppm", "PPM"),
+        ("average_recharge", "Average Recharge"),
+        ("expected_rate", "Expected Chance %"),
+    ]
+}
+
+fn flatten(data: Vec<ProcRateRow>) -> Vec<Vec<String>> {
+    let mut result = Vec::<Vec<String>>::new();
+
+    for d in data {
+        result.push(vec![
+            d.proc_name,
+            d.power_name,
+            d.activations.to_string(),
+            d.fires.to_string(),
+            format!("{:.1}", d.observed_rate),
+            d.ppm.map_or(String::from("n/a"), |p| p.to_string()),
+            d.average_recharge
+                .map_or(String::from("n/a"), |r| r.to_string()),
+            d.expected_rate
+                .map_or(String::from("n/a"), |r| format!("{:.1}", r)),
+        ]);
+    }
+    result
+}
+
+fn sort(sort_field: String, sort_dir: SortDirection, data: &mut [ProcRateRow]) {
+    match sort_field.as_str() {
+        "proc_name" => match sort_dir {
+            SortDirection::DESC => data.sort_by(|a, b| b.proc_name.cmp(&a.proc_name)),
+            SortDirection::ASC => data.sort_by(|a, b| a.proc_name.cmp(&b.proc_name)),
+        },
+        "power_name" => match sort_dir {
+            SortDirection::DESC => data.sort_by(|a, b| b.power_name.cmp(&a.power_name)),
+            SortDirection::ASC => data.sort_by(|a, b| a.power_name.cmp(&b.power_name)),
+        },
+        "activations" => match sort_dir {
+            SortDirection::DESC => data.sort_by_key(|a| Reverse(a.activations)),
+            SortDirection::ASC => data.sort_by_key(|a| a.activations),
+        },
+        "fires" => match sort_dir {
+            SortDirection::DESC => data.sort_by_key(|a| Reverse(a.fires)),
+            SortDirection::ASC => data.sort_by_key(|a| a.fires),
+        },
+        "observed_rate" => match sort_dir {
+            SortDirection::DESC => data.sort_by(|a, b| b.observed_rate.total_cmp(&a.observed_rate)),
+            SortDirection::ASC => data.sort_by(|a, b| a.observed_rate.total_cmp(&b.observed_rate)),
+        },
+        "ppm" => match sort_dir {
+            SortDirection::DESC => data.sort_by(|a, b| b.ppm.partial_cmp(&a.ppm).unwrap()),
+            SortDirection::ASC => data.sort_by(|a, b| a.ppm.partial_cmp(&b.ppm).unwrap()),
+        },
+        "average_recharge" => match sort_dir {
+            SortDirection::DESC => data.sort_by_key(|a| Reverse(a.average_recharge)),
+            SortDirection::ASC => data.sort_by_key(|a| a.average_recharge),
+        },
+        "expected_rate" => match sort_dir {
+            SortDirection::DESC => {
+                data.sort_by(|a, b| b.expected_rate.partial_cmp(&a.expected_rate).unwrap())
+            }
+            SortDirection::ASC => {
+                data.sort_by(|a, b| a.expected_rate.partial_cmp(&b.expected_rate).unwrap())
+            }
+        },
+        _ => println!("Unknown sort field provided: {}", sort_field),
+    }
+}
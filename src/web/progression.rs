@@ -0,0 +1,229 @@
+use std::path::{Path, PathBuf};
+
+use diesel::SqliteConnection;
+use serde::Serialize;
+use tera::Context;
+use utoipa::ToSchema;
+
+use crate::{
+    db,
+    models::{LevelUp, RewardTimeline, Summary},
+    web::web_structs_enums::ProgressionQuery,
+    AppContext,
+};
+
+use super::dates::{format_elapsed, parse_date};
+use super::index_handler;
+
+const SECONDS_PER_HOUR: i64 = 3600;
+
+#[derive(Serialize, Debug, ToSchema)]
+struct SessionRow {
+    duration: String,
+    experience: i64,
+    influence: i64,
+    experience_per_hour: i64,
+    influence_per_hour: i64,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+struct HourRow {
+    hour: i64,
+    experience: i64,
+    influence: i64,
+    partial: bool,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+struct LevelRow {
+    level: i32,
+    reached: String,
+    time_at_level: String,
+    experience: i64,
+    // The session started or ended part way through the level
+    partial: bool,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+struct HistoryRow {
+    summary_key: i32,
+    report_id: String,
+    log_date: String,
+    levels: String,
+    session: SessionRow,
+    current: bool,
+}
+
+// Rewards and level ups for one session, times are seconds from the start of the session
+struct SessionProgress {
+    duration: i64,
+    rewards: Vec<RewardTimeline>,
+    level_ups: Vec<(LevelUp, i64)>,
+}
+
+impl SessionProgress {
+    fn load(conn: &mut SqliteConnection, summary: &Summary) -> SessionProgress {
+        let start = parse_date(&summary.log_date);
+        let rewards = db::queries::get_reward_timeline(conn, summary.summary_key);
+        let level_ups: Vec<(LevelUp, i64)> = db::queries::get_level_ups(conn, summary.summary_key)
+            .into_iter()
+            .map(|l| {
+                let elapsed = (parse_date(&l.log_date) - start).num_seconds().max(0);
+                (l, elapsed)
+            })
+            .collect();
+
+        let last_action = db::queries::get_log_date_at_line(conn, summary.summary_key, summary.last_line_number)
+            .map_or(0, |date| (parse_date(&date) - start).num_seconds());
+        let duration = rewards
+            .iter()
+            .map(|r| r.elapsed as i64)
+            .chain(level_ups.iter().map(|l| l.1))
+            .chain(std::iter::once(last_action))
+            .max()
+            .unwrap_or(0)
+            .max(0);
+
+        SessionProgress {
+            duration,
+            rewards,
+            level_ups,
+        }
+    }
+
+    fn rewards_between(&self, start: i64, end: i64) -> (i64, i64) {
+        self.rewards
+            .iter()
+            .filter(|r| (r.elapsed as i64) >= start && (r.elapsed as i64) < end)
+            .fold((0, 0), |(xp, inf), r| (xp + r.experience as i64, inf + r.influence as i64))
+    }
+
+    fn session_row(&self) -> SessionRow {
+        let (experience, influence) = self.rewards_between(0, i64::MAX);
+        SessionRow {
+            duration: format_elapsed(self.duration),
+            experience,
+            influence,
+            experience_per_hour: per_hour(experience, self.duration),
+            influence_per_hour: per_hour(influence, self.duration),
+        }
+    }
+
+    fn hour_rows(&self) -> Vec<HourRow> {
+        let hours = self.duration / SECONDS_PER_HOUR + 1;
+        (0..hours)
+            .map(|h| {
+                let start = h * SECONDS_PER_HOUR;
+                let (experience, influence) = self.rewards_between(start, start + SECONDS_PER_HOUR);
+                HourRow {
+                    hour: h + 1,
+                    experience,
+                    influence,
+                    partial: start + SECONDS_PER_HOUR > self.duration,
+                }
+            })
+            .collect()
+    }
+
+    fn level_rows(&self) -> Vec<LevelRow> {
+        let first = match self.level_ups.first() {
+            Some(l) => l,
+            None => return Vec::new(),
+        };
+
+        let mut rows = Vec::new();
+        let (experience, _) = self.rewards_between(0, first.1);
+        rows.push(LevelRow {
+            level: first.0.level - 1,
+            reached: String::from("Before session"),
+            time_at_level: format_elapsed(first.1),
+            experience,
+            partial: true,
+        });
+
+        for (i, (level_up, reached)) in self.level_ups.iter().enumerate() {
+            let next = self.level_ups.get(i + 1).map(|l| l.1);
+            let end = next.unwrap_or(self.duration + 1);
+            let (experience, _) = self.rewards_between(*reached, end);
+            rows.push(LevelRow {
+                level: level_up.level,
+                reached: format_elapsed(*reached),
+                time_at_level: format_elapsed(end.min(self.duration) - reached),
+                experience,
+                partial: next.is_none(),
+            });
+        }
+        rows
+    }
+
+    fn levels(&self) -> String {
+        match (self.level_ups.first(), self.level_ups.last()) {
+            (Some(first), Some(last)) => format!("{} to {}", first.0.level - 1, last.0.level),
+            _ => String::from("-"),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct ProgressionReport {
+    player_name: String,
+    session: SessionRow,
+    hour_rows: Vec<HourRow>,
+    level_rows: Vec<LevelRow>,
+    history_rows: Vec<HistoryRow>,
+}
+
+pub fn process(app_context: &AppContext, context: &mut Context, query: &ProgressionQuery) {
+    if let Some(report) = report(app_context, query) {
+        super::insert_report(context, &report);
+    }
+}
+
+pub fn report(app_context: &AppContext, query: &ProgressionQuery) -> Option<ProgressionReport> {
+    let db_path: PathBuf = query.db_path.clone().into();
+    let mut conn = db::get_file_conn(db_path.clone());
+
+    let binding = db::queries::get_summary(&mut conn, query.key);
+    let summary = binding.first()?;
+
+    let progress = SessionProgress::load(&mut conn, summary);
+    Some(ProgressionReport {
+        player_name: summary.player_name.clone(),
+        session: progress.session_row(),
+        hour_rows: progress.hour_rows(),
+        level_rows: progress.level_rows(),
+        history_rows: history_rows(app_context, summary, &db_path),
+    })
+}
+
+// Every session of the same character, oldest first
+fn history_rows(app_context: &AppContext, current: &Summary, current_db: &Path) -> Vec<HistoryRow> {
+    let current_db = current_db.canonicalize().unwrap_or(current_db.to_path_buf());
+    index_handler::find_player_sessions(&current.player_name, app_context)
+        .into_iter()
+        .filter_map(|(db_path, report_id, details)| {
+            let mut conn = db::get_file_conn(db_path.clone());
+            let binding = db::queries::get_summary(&mut conn, details.summary_key);
+            let summary = binding.first()?;
+            let progress = SessionProgress::load(&mut conn, summary);
+            let is_current = summary.summary_key == current.summary_key
+                && db_path.canonicalize().unwrap_or(db_path.clone()) == current_db;
+            Some(HistoryRow {
+                summary_key: summary.summary_key,
+                log_date: summary.log_date.clone(),
+                levels: progress.levels(),
+                session: progress.session_row(),
+                current: is_current,
+                report_id,
+            })
+        })
+        .collect()
+}
+
+fn per_hour(amount: i64, seconds: i64) -> i64 {
+    if seconds > 0 {
+        amount * SECONDS_PER_HOUR / seconds
+    } else {
+        0
+    }
+}
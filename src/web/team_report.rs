@@ -0,0 +1,128 @@
+use std::collections::{BTreeMap, HashSet};
+use std::path::PathBuf;
+
+use chrono::DateTime;
+use serde::Serialize;
+use tera::Context;
+
+use crate::{db, models::Summary, web::web_structs_enums::TeamReportQuery};
+
+#[derive(Serialize, Debug, Default)]
+struct CharacterRow {
+    summary_key: i32,
+    player_name: String,
+    log_file_name: String,
+    damage: i64,
+    dps: i64,
+    heals: usize,
+    health: i64,
+    endurance: i64,
+    defeats: usize,
+    deaths: i64,
+}
+
+// A heal or endurance grant as (time, giver, receiver, power, amount, type). The giver logs
+// "You heal Bob" and Bob logs "Healer heals you", both become the same event.
+type HealEvent = (String, String, String, String, i32, String);
+
+pub fn process(context: &mut Context, query: &TeamReportQuery) {
+    let db_path: PathBuf = query.db_path.clone().into();
+    let mut conn = db::get_file_conn(db_path);
+
+    let sources = db::queries::get_merge_sources(&mut conn);
+    let first = match sources.first() {
+        Some(s) => s,
+        None => return,
+    };
+    let summaries: Vec<Summary> = sources
+        .iter()
+        .filter_map(|s| db::queries::get_summary(&mut conn, s.summary_key).into_iter().next())
+        .collect();
+    let names: BTreeMap<i32, String> = summaries
+        .iter()
+        .map(|s| (s.summary_key, s.player_name.clone()))
+        .collect();
+    let window_seconds = match (
+        DateTime::parse_from_rfc3339(&first.window_start),
+        DateTime::parse_from_rfc3339(&first.window_end),
+    ) {
+        (Ok(start), Ok(end)) => (end - start).num_seconds().max(1),
+        _ => 1,
+    };
+
+    let mut rows: BTreeMap<String, CharacterRow> = summaries
+        .iter()
+        .map(|s| {
+            let damage = db::queries::get_player_damage_total(&mut conn, s.summary_key);
+            let row = CharacterRow {
+                summary_key: s.summary_key,
+                player_name: s.player_name.clone(),
+                log_file_name: s.log_file_name.clone(),
+                damage,
+                dps: damage / window_seconds,
+                deaths: db::queries::get_death_count(&mut conn, s.summary_key).unwrap_or(0),
+                ..Default::default()
+            };
+            (s.player_name.clone(), row)
+        })
+        .collect();
+
+    // Several logs can report the same defeat, count each one once
+    let mut duplicates = 0;
+    let mut defeats = HashSet::new();
+    for d in db::queries::get_all_defeats(&mut conn) {
+        if !defeats.insert((d.log_date.clone(), d.source_name.clone(), d.target_name.clone())) {
+            duplicates += 1;
+            continue;
+        }
+        if let Some(row) = rows.get_mut(&d.source_name) {
+            row.defeats += 1;
+        }
+    }
+
+    let mut heals = HashSet::<HealEvent>::new();
+    for h in db::queries::get_all_heals_endurance(&mut conn) {
+        let player_name = match names.get(&h.summary_key) {
+            Some(name) => name.clone(),
+            None => continue,
+        };
+        let (giver, receiver) = match (h.source_name.as_str(), h.target_name.as_str()) {
+            ("Player", target) => (player_name, target.to_string()),
+            (source, _) => (source.to_string(), player_name),
+        };
+        let event = (h.log_date, giver.clone(), receiver, h.power_name, h.amount, h.action_type.clone());
+        if !heals.insert(event) {
+            duplicates += 1;
+            continue;
+        }
+        if let Some(row) = rows.get_mut(&giver) {
+            if h.action_type == "Endurance" {
+                row.endurance += h.amount as i64;
+            } else {
+                row.heals += 1;
+                row.health += h.amount as i64;
+            }
+        }
+    }
+
+    let rows: Vec<CharacterRow> = rows.into_values().collect();
+    let total = CharacterRow {
+        player_name: String::from("Team"),
+        damage: rows.iter().map(|r| r.damage).sum(),
+        dps: rows.iter().map(|r| r.dps).sum(),
+        heals: rows.iter().map(|r| r.heals).sum(),
+        health: rows.iter().map(|r| r.health).sum(),
+        endurance: rows.iter().map(|r| r.endurance).sum(),
+        defeats: rows.iter().map(|r| r.defeats).sum(),
+        deaths: rows.iter().map(|r| r.deaths).sum(),
+        ..Default::default()
+    };
+
+    context.insert("report", &query.report);
+    context.insert("window_start", &first.window_start);
+    context.insert("window_end", &first.window_end);
+    context.insert("window_seconds", &window_seconds);
+    context.insert("character_rows", &rows);
+    context.insert("total", &total);
+    context.insert("duplicates", &duplicates);
+}
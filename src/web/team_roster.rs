@@ -0,0 +1,262 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use diesel::SqliteConnection;
+use serde::Serialize;
+use tera::Context;
+use utoipa::ToSchema;
+
+use crate::{
+    db::{self, event_processing::PLACEHOLDER_NAME},
+    models::Summary,
+    web::web_structs_enums::TeamRosterQuery,
+    AppContext,
+};
+
+use super::dates::{format_elapsed, parse_date};
+use super::index_handler;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, ToSchema)]
+enum RosterRole {
+    Teammate,
+    Pet,
+    // Only seen defeating enemies, a teammate who never healed or buffed you or one of their pets
+    Ally,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+struct MemberRow {
+    name: String,
+    role: RosterRole,
+    first_seen: String,
+    last_seen: String,
+    heals: usize,
+    health: i64,
+    endurance_grants: usize,
+    endurance: i64,
+    buffs: usize,
+    buff_powers: String,
+    defeats: usize,
+    chat_lines: usize,
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+struct RosterSessionRow {
+    summary_key: i32,
+    report_id: String,
+    log_date: String,
+    player_name: String,
+    experience: i64,
+    influence: i64,
+    mobs_defeated: i64,
+    dps: i64,
+    current: bool,
+}
+
+// Everything one name did for the player during a session
+#[derive(Default)]
+struct Contribution {
+    first_date: String,
+    last_date: String,
+    heals: usize,
+    health: i64,
+    endurance_grants: usize,
+    endurance: i64,
+    buff_powers: Vec<String>,
+    defeats: usize,
+    chat_lines: usize,
+}
+
+impl Contribution {
+    fn seen(&mut self, log_date: &str) {
+        if self.first_date.is_empty() || log_date < self.first_date.as_str() {
+            self.first_date = log_date.to_string();
+        }
+        if log_date > self.last_date.as_str() {
+            self.last_date = log_date.to_string();
+        }
+    }
+
+    fn role(&self, is_pet: bool) -> RosterRole {
+        if is_pet {
+            RosterRole::Pet
+        } else if self.chat_lines > 0 || self.heals > 0 || self.endurance_grants > 0 || !self.buff_powers.is_empty() {
+            RosterRole::Teammate
+        } else {
+            RosterRole::Ally
+        }
+    }
+}
+
+struct Roster {
+    members: BTreeMap<String, Contribution>,
+    pets: Vec<String>,
+}
+
+impl Roster {
+    // Names seen helping the player or defeating enemies, leaving out anything that attacked the player
+    fn load(conn: &mut SqliteConnection, summary: &Summary) -> Roster {
+        let key = summary.summary_key;
+        let mut members = BTreeMap::<String, Contribution>::new();
+
+        for e in db::queries::get_team_events(conn, key) {
+            let member = members.entry(e.member_name.clone()).or_default();
+            member.seen(&e.log_date);
+            match e.power_name {
+                Some(power) if e.event_type == "Buff" => member.buff_powers.push(power),
+                _ => member.chat_lines += 1,
+            }
+        }
+        for h in db::queries::get_heals_endurance_received(conn, key) {
+            let member = members.entry(h.source_name.clone()).or_default();
+            member.seen(&h.log_date);
+            if h.action_type == "Endurance" {
+                member.endurance_grants += 1;
+                member.endurance += h.amount as i64;
+            } else {
+                member.heals += 1;
+                member.health += h.amount as i64;
+            }
+        }
+        for d in db::queries::get_other_defeats(conn, key, &summary.player_name) {
+            let member = members.entry(d.source_name.clone()).or_default();
+            member.seen(&d.log_date);
+            member.defeats += 1;
+        }
+
+        for name in db::queries::get_hostile_names(conn, key) {
+            members.remove(&name);
+        }
+        for name in [summary.player_name.as_str(), "Player", "You", PLACEHOLDER_NAME] {
+            members.remove(name);
+        }
+
+        Roster {
+            members,
+            pets: db::queries::get_player_pet_names(conn, key),
+        }
+    }
+
+    fn role(&self, name: &str, contribution: &Contribution) -> RosterRole {
+        contribution.role(self.pets.iter().any(|p| p == name))
+    }
+
+    fn teammates(&self) -> Vec<String> {
+        self.members
+            .iter()
+            .filter(|(name, c)| self.role(name, c) == RosterRole::Teammate)
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+}
+
+// Teammates for a session, used by the index to filter by who you played with
+pub fn teammate_names(conn: &mut SqliteConnection, summary: &Summary) -> Vec<String> {
+    Roster::load(conn, summary).teammates()
+}
+
+#[derive(Serialize, Debug, ToSchema)]
+pub struct TeamRosterReport {
+    player_name: String,
+    member_rows: Vec<MemberRow>,
+    teammates: Vec<String>,
+    // Sessions with the same teammates, only when there were teammates
+    #[serde(skip_serializing_if = "Option::is_none")]
+    session_rows: Option<Vec<RosterSessionRow>>,
+}
+
+pub fn process(app_context: &AppContext, context: &mut Context, query: &TeamRosterQuery) {
+    if let Some(report) = report(app_context, query) {
+        super::insert_report(context, &report);
+    }
+}
+
+// None when the session is missing or nobody else was seen
+pub fn report(app_context: &AppContext, query: &TeamRosterQuery) -> Option<TeamRosterReport> {
+    let db_path: PathBuf = query.db_path.clone().into();
+    let mut conn = db::get_file_conn(db_path.clone());
+
+    let binding = db::queries::get_summary(&mut conn, query.key);
+    let summary = binding.first()?;
+
+    let roster = Roster::load(&mut conn, summary);
+    if roster.members.is_empty() {
+        return None;
+    }
+    let start = parse_date(&summary.log_date);
+
+    let mut rows: Vec<MemberRow> = roster
+        .members
+        .iter()
+        .map(|(name, c)| {
+            let mut powers = c.buff_powers.clone();
+            powers.sort();
+            powers.dedup();
+            MemberRow {
+                name: name.clone(),
+                role: roster.role(name, c),
+                first_seen: format_elapsed((parse_date(&c.first_date) - start).num_seconds()),
+                last_seen: format_elapsed((parse_date(&c.last_date) - start).num_seconds()),
+                heals: c.heals,
+                health: c.health,
+                endurance_grants: c.endurance_grants,
+                endurance: c.endurance,
+                buffs: c.buff_powers.len(),
+                buff_powers: powers.join(", "),
+                defeats: c.defeats,
+                chat_lines: c.chat_lines,
+            }
+        })
+        .collect();
+    rows.sort_by_key(|r| match r.role {
+        RosterRole::Teammate => 0,
+        RosterRole::Pet => 1,
+        RosterRole::Ally => 2,
+    });
+
+    let teammates = roster.teammates();
+    let session_rows = if teammates.is_empty() {
+        None
+    } else {
+        Some(roster_sessions(app_context, summary, &db_path, &teammates))
+    };
+    Some(TeamRosterReport {
+        player_name: summary.player_name.clone(),
+        member_rows: rows,
+        teammates,
+        session_rows,
+    })
+}
+
+// Every parsed session with exactly the same teammates, for stats per static team
+fn roster_sessions(
+    app_context: &AppContext,
+    current: &Summary,
+    current_db: &Path,
+    teammates: &[String],
+) -> Vec<RosterSessionRow> {
+    let current_db = current_db.canonicalize().unwrap_or(current_db.to_path_buf());
+    index_handler::find_roster_sessions(teammates, app_context)
+        .into_iter()
+        .filter_map(|(db_path, report_id, details)| {
+            let mut conn = db::get_file_conn(db_path.clone());
+            let binding = db::queries::get_summary(&mut conn, details.summary_key);
+            let summary = binding.first()?;
+            let rewards = db::queries::get_rewards_defeats(&mut conn, details.summary_key, &details.player_name);
+            let dps = db::queries::get_session_stats(&mut conn, details.summary_key).map_or(0, |s| s.total_dps);
+            let is_current = details.summary_key == current.summary_key
+                && db_path.canonicalize().unwrap_or(db_path.clone()) == current_db;
+            Some(RosterSessionRow {
+                summary_key: details.summary_key,
+                log_date: summary.log_date.clone(),
+                player_name: details.player_name,
+                experience: rewards.experience as i64,
+                influence: rewards.influence as i64,
+                mobs_defeated: rewards.mobs_defeated as i64,
+                dps: dps as i64,
+                current: is_current,
+                report_id,
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,227 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use actix_multipart::{Field, Multipart};
+use chrono::Local;
+use futures_util::StreamExt;
+
+use crate::log_processing::{self, ParserJob, ProcessingError};
+
+use super::{index_handler, web_structs_enums::ParseLog};
+
+const MEGABYTE: u64 = 1024 * 1024;
+// Everything sent in one upload, logs and zips together
+const MAX_UPLOAD_BYTES: u64 = 1024 * MEGABYTE;
+// One log extracted from a zip, and all logs extracted from the zips of one upload
+const MAX_ZIP_ENTRY_BYTES: u64 = 512 * MEGABYTE;
+const MAX_UNZIPPED_BYTES: u64 = 2048 * MEGABYTE;
+
+// Saves the uploaded logs to a new directory in the inbox and creates a job to parse them
+pub async fn create_upload_job(inbox_dir: &Path, payload: Multipart) -> Result<ParserJob, ParserJob> {
+    let upload_dir = inbox_dir.join(format!(
+        "upload_{}",
+        Local::now().format("%Y_%m_%d_%H%M%S_%3f")
+    ));
+    log_processing::create_dir(&upload_dir);
+
+    match save_upload(&upload_dir, payload).await {
+        Ok(ParseLog::MergeLogs) => index_handler::create_merge_job(&upload_dir.to_string_lossy()),
+        Ok(_) => index_handler::create_parser_job(&upload_dir),
+        Err(e) => {
+            let _ = fs::remove_dir_all(&upload_dir);
+            Err(ParserJob {
+                files: Vec::new(),
+                processed: 0,
+                run_time: 0,
+                errors: vec![e],
+                completion_date: "".to_string(),
+                last_file: "".to_string(),
+                merge: false,
+                merged_report: None,
+                merged_report_id: None,
+                cancelled: false,
+            })
+        }
+    }
+}
+
+// Writes every file field to the upload directory, returns the action button that was pressed
+async fn save_upload(upload_dir: &Path, mut payload: Multipart) -> Result<ParseLog, ProcessingError> {
+    let mut action = ParseLog::ParsePath;
+    let mut saved = 0;
+    let mut received: u64 = 0;
+    let mut unzipped: u64 = 0;
+
+    while let Some(field) = payload.next().await {
+        let mut field = field.map_err(|e| upload_error(upload_dir, e.to_string()))?;
+        let file_name = field
+            .content_disposition()
+            .and_then(|cd| cd.get_filename())
+            .and_then(clean_file_name);
+
+        let file_name = match file_name {
+            Some(f) => f,
+            None => {
+                if field.name() == Some("action") && read_text(&mut field, &mut received).await? == "MergeLogs" {
+                    action = ParseLog::MergeLogs;
+                }
+                continue;
+            }
+        };
+
+        if is_extension(&file_name, "zip") {
+            let zip_path = upload_dir.join(&file_name);
+            write_field(&mut field, &zip_path, &mut received).await?;
+            saved += unzip_logs(&zip_path, upload_dir, &mut unzipped)?;
+            let _ = fs::remove_file(&zip_path);
+        } else if is_extension(&file_name, "txt") {
+            let log_path = unique_path(upload_dir, &file_name);
+            write_field(&mut field, &log_path, &mut received).await?;
+            saved += 1;
+        } else {
+            return Err(upload_error(
+                Path::new(&file_name),
+                String::from("Only chat logs (.txt) and zipped folders of logs (.zip) can be uploaded"),
+            ));
+        }
+    }
+
+    if saved == 0 {
+        Err(upload_error(upload_dir, String::from("No log files were uploaded")))
+    } else {
+        println!("Saved {} uploaded log(s) to {:?}", saved, upload_dir);
+        Ok(action)
+    }
+}
+
+// received is the running total for the whole upload, the upload stops once it is over the limit
+async fn write_field(field: &mut Field, path: &Path, received: &mut u64) -> Result<(), ProcessingError> {
+    let mut file = File::create(path).map_err(|e| upload_error(path, e.to_string()))?;
+    while let Some(chunk) = field.next().await {
+        let chunk = chunk.map_err(|e| upload_error(path, e.to_string()))?;
+        count_upload_bytes(path, received, chunk.len())?;
+        file.write_all(&chunk)
+            .map_err(|e| upload_error(path, e.to_string()))?;
+    }
+    Ok(())
+}
+
+async fn read_text(field: &mut Field, received: &mut u64) -> Result<String, ProcessingError> {
+    let mut bytes = Vec::new();
+    while let Some(chunk) = field.next().await {
+        let chunk = chunk.map_err(|e| upload_error(Path::new("action"), e.to_string()))?;
+        count_upload_bytes(Path::new("action"), received, chunk.len())?;
+        bytes.extend_from_slice(&chunk);
+    }
+    Ok(String::from_utf8_lossy(&bytes).to_string())
+}
+
+fn count_upload_bytes(path: &Path, received: &mut u64, bytes: usize) -> Result<(), ProcessingError> {
+    *received += bytes as u64;
+    if *received > MAX_UPLOAD_BYTES {
+        return Err(upload_error(
+            path,
+            format!("Uploads are limited to {} MB", MAX_UPLOAD_BYTES / MEGABYTE),
+        ));
+    }
+    Ok(())
+}
+
+// Every .txt file in the archive is extracted to the upload directory, folders inside the zip are flattened.
+// unzipped is the running total for the upload. The sizes in the zip are checked first, then the copy
+// is cut off at the limit in case the zip lies about them.
+fn unzip_logs(zip_path: &Path, upload_dir: &Path, unzipped: &mut u64) -> Result<usize, ProcessingError> {
+    let file = File::open(zip_path).map_err(|e| upload_error(zip_path, e.to_string()))?;
+    let mut archive = zip::ZipArchive::new(file).map_err(|e| upload_error(zip_path, e.to_string()))?;
+
+    let mut count = 0;
+    for i in 0..archive.len() {
+        let mut entry = archive
+            .by_index(i)
+            .map_err(|e| upload_error(zip_path, e.to_string()))?;
+        if !entry.is_file() {
+            continue;
+        }
+        let file_name = entry
+            .enclosed_name()
+            .and_then(|p| p.file_name().map(|f| f.to_string_lossy().to_string()))
+            .and_then(|f| clean_file_name(&f));
+        let file_name = match file_name {
+            // Mac resource forks share the name of the log they belong to
+            Some(f) if is_extension(&f, "txt") && !f.starts_with("._") => f,
+            _ => continue,
+        };
+
+        check_unzipped_size(&file_name, entry.size(), *unzipped)?;
+
+        let log_path = unique_path(upload_dir, &file_name);
+        let mut log_file = File::create(&log_path).map_err(|e| upload_error(&log_path, e.to_string()))?;
+        let copied = io::copy(&mut entry.by_ref().take(MAX_ZIP_ENTRY_BYTES + 1), &mut log_file)
+            .map_err(|e| upload_error(&log_path, e.to_string()))?;
+        check_unzipped_size(&file_name, copied, *unzipped)?;
+        *unzipped += copied;
+        count += 1;
+    }
+    Ok(count)
+}
+
+fn check_unzipped_size(file_name: &str, size: u64, unzipped: u64) -> Result<(), ProcessingError> {
+    if size > MAX_ZIP_ENTRY_BYTES {
+        return Err(upload_error(
+            Path::new(file_name),
+            format!("Logs in a zip are limited to {} MB each", MAX_ZIP_ENTRY_BYTES / MEGABYTE),
+        ));
+    }
+    if unzipped + size > MAX_UNZIPPED_BYTES {
+        return Err(upload_error(
+            Path::new(file_name),
+            format!("Zipped logs are limited to {} MB in total once extracted", MAX_UNZIPPED_BYTES / MEGABYTE),
+        ));
+    }
+    Ok(())
+}
+
+// Only the file name is kept from what the browser sends, the extension is lower cased for the directory reader
+fn clean_file_name(name: &str) -> Option<String> {
+    let mut file_name = PathBuf::from(Path::new(name).file_name()?);
+    if let Some(extension) = file_name.extension().map(|e| e.to_ascii_lowercase()) {
+        file_name.set_extension(extension);
+    }
+    Some(file_name.to_string_lossy().to_string())
+}
+
+fn is_extension(file_name: &str, extension: &str) -> bool {
+    Path::new(file_name)
+        .extension()
+        .is_some_and(|e| e == extension)
+}
+
+// Logs from different characters often share a file name, number the later ones
+fn unique_path(dir: &Path, file_name: &str) -> PathBuf {
+    let path = dir.join(file_name);
+    if !path.exists() {
+        return path;
+    }
+    let stem = Path::new(file_name)
+        .file_stem()
+        .map(|s| s.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let mut n = 1;
+    loop {
+        let numbered = dir.join(format!("{}_{}.txt", stem, n));
+        if !numbered.exists() {
+            return numbered;
+        }
+        n += 1;
+    }
+}
+
+fn upload_error(path: &Path, message: String) -> ProcessingError {
+    ProcessingError {
+        file_name: path.to_path_buf(),
+        message: format!("Upload failed. {}", message),
+    }
+}
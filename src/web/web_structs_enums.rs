@@ -1,24 +1,38 @@
-use serde::{Deserialize, Serialize};
+use std::{fmt::Display, path::PathBuf, str::FromStr};
 
-#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq)]
+use serde::{Deserialize, Deserializer, Serialize};
+use utoipa::{IntoParams, ToSchema};
+
+#[derive(Clone, Serialize, Deserialize, Debug, PartialEq, Eq, ToSchema)]
 pub enum SortDirection {
     ASC,
     DESC,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Clone, Serialize, Deserialize, Debug, ToSchema)]
 pub enum TableNames {
     DamageDealtByType,
     DamageTakenByType,
     DamageTakenByMob,
     DamageTakenByMobPower,
     DPSIntervals,
+    ProcRates,
+    DefeatedTargets,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+pub enum ChartNames {
+    DpsOverTime,
+    DamageByType,
+    Rewards,
+    IncomingDamage,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub enum ParseLog {
     ParsePath,
     LatestFile,
+    MergeLogs,
 }
 
 #[derive(Deserialize, Debug)]
@@ -28,37 +42,66 @@ pub struct ParseLogRequest {
 }
 
 #[derive(Deserialize, Debug)]
+pub struct JobQuery {
+    pub id: usize,
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct TableQuery {
     pub key: i32,
+    pub report: String,
+    // Found from report in the index, never taken from the url
+    #[serde(skip)]
     pub db_path: String,
     pub table_name: Option<TableNames>,
     pub sort_field: Option<String>,
     pub sort_dir: Option<SortDirection>,
+    pub interval: Option<usize>,
+    // Level used for mob ranks and HP, like the attack summary's mob level
+    pub mob_level: Option<String>,
 }
 
-#[derive(Serialize, Deserialize, Debug)]
+#[derive(Serialize, Deserialize, Debug, ToSchema)]
 pub enum PowerTableActions {
     Revert,
     RemoveNonDamaging,
     Merge,
     MergeGuess,
     Delete,
+    SaveView,
+    LoadView,
+    DeleteView,
+    SaveMergeRule,
+    DeleteMergeRule,
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct DamageByPowerQuery {
     pub key: i32,
+    pub report: String,
+    // Found from report in the index, never taken from the url
+    #[serde(skip)]
     pub db_path: String,
     pub sort_field: Option<String>,
     pub sort_dir: Option<SortDirection>,
     pub action: Option<PowerTableActions>,
     pub power_row: Option<Vec<u8>>,
     pub mob_level: Option<String>,
+    // Saved view to save, load or delete
+    pub view_name: Option<String>,
+    // Index of the character merge rule to delete
+    pub rule: Option<usize>,
 }
 
-#[derive(Deserialize, Serialize, Debug)]
+#[derive(Deserialize, Serialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
 pub struct PowersMobsData {
     pub key: i32,
+    pub report: String,
+    // Found from report in the index, never taken from the url
+    #[serde(skip)]
     pub db_path: String,
     pub table_name: Option<TableNames>,
     pub power_name: Option<String>,
@@ -67,3 +110,212 @@ pub struct PowersMobsData {
     pub sort_field: Option<String>,
     pub sort_dir: Option<SortDirection>,
 }
+
+// Filters for the session list, every filter given has to match
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct SessionsQuery {
+    pub player_name: Option<String>,
+    pub log_dir: Option<String>,
+    pub tag: Option<String>,
+    pub text: Option<String>,
+    pub teammate: Option<String>,
+    // Dates as YYYY-MM-DD, both ends are included
+    pub from_date: Option<String>,
+    pub to_date: Option<String>,
+    pub min_data_points: Option<i64>,
+}
+
+// Filters, sort and page of the index page. Kept in the url so a view can be bookmarked.
+#[derive(Serialize, Deserialize, Debug, Default, Clone)]
+pub struct IndexQuery {
+    #[serde(default, deserialize_with = "empty_as_none", skip_serializing_if = "Option::is_none")]
+    pub player_name: Option<String>,
+    #[serde(default, deserialize_with = "empty_as_none", skip_serializing_if = "Option::is_none")]
+    pub log_dir: Option<PathBuf>,
+    #[serde(default, deserialize_with = "empty_as_none", skip_serializing_if = "Option::is_none")]
+    pub log_file: Option<PathBuf>,
+    #[serde(default, deserialize_with = "empty_as_none", skip_serializing_if = "Option::is_none")]
+    pub tag: Option<String>,
+    #[serde(default, deserialize_with = "empty_as_none", skip_serializing_if = "Option::is_none")]
+    pub text: Option<String>,
+    #[serde(default, deserialize_with = "empty_as_none", skip_serializing_if = "Option::is_none")]
+    pub teammate: Option<String>,
+    #[serde(default, deserialize_with = "empty_as_none", skip_serializing_if = "Option::is_none")]
+    pub from_date: Option<String>,
+    #[serde(default, deserialize_with = "empty_as_none", skip_serializing_if = "Option::is_none")]
+    pub to_date: Option<String>,
+    #[serde(default, deserialize_with = "empty_as_none", skip_serializing_if = "Option::is_none")]
+    pub min_data_points: Option<i64>,
+    #[serde(default, deserialize_with = "empty_as_none", skip_serializing_if = "Option::is_none")]
+    pub sort_field: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort_dir: Option<SortDirection>,
+    #[serde(default, deserialize_with = "empty_as_none", skip_serializing_if = "Option::is_none")]
+    pub page: Option<usize>,
+}
+
+// Empty form fields are sent as "", those count as not set
+fn empty_as_none<'de, D, T>(deserializer: D) -> Result<Option<T>, D::Error>
+where
+    D: Deserializer<'de>,
+    T: FromStr,
+    T::Err: Display,
+{
+    match Option::<String>::deserialize(deserializer)? {
+        Some(value) if !value.trim().is_empty() => value
+            .trim()
+            .parse::<T>()
+            .map(Some)
+            .map_err(serde::de::Error::custom),
+        _ => Ok(None),
+    }
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DeathRecapQuery {
+    pub key: i32,
+    pub report: String,
+    // Found from report in the index, never taken from the url
+    #[serde(skip)]
+    pub db_path: String,
+    pub seconds: Option<i32>,
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct DebuffEffectivenessQuery {
+    pub key: i32,
+    pub report: String,
+    // Found from report in the index, never taken from the url
+    #[serde(skip)]
+    pub db_path: String,
+    pub seconds: Option<i32>,
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ConsumablesQuery {
+    pub key: i32,
+    pub report: String,
+    // Found from report in the index, never taken from the url
+    #[serde(skip)]
+    pub db_path: String,
+    pub seconds: Option<i32>,
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct LocationTimelineQuery {
+    pub key: i32,
+    pub report: String,
+    // Found from report in the index, never taken from the url
+    #[serde(skip)]
+    pub db_path: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ScopeQuery {
+    pub key: i32,
+    pub report: String,
+    // Found from report in the index, never taken from the url
+    #[serde(skip)]
+    pub db_path: String,
+    pub first_line: i32,
+    pub last_line: i32,
+    pub name: String,
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ProgressionQuery {
+    pub key: i32,
+    pub report: String,
+    // Found from report in the index, never taken from the url
+    #[serde(skip)]
+    pub db_path: String,
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct MarketQuery {
+    pub key: i32,
+    pub report: String,
+    // Found from report in the index, never taken from the url
+    #[serde(skip)]
+    pub db_path: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct TeamReportQuery {
+    pub report: String,
+    // Found from report in the index, never taken from the url
+    #[serde(skip)]
+    pub db_path: String,
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct TeamRosterQuery {
+    pub key: i32,
+    pub report: String,
+    // Found from report in the index, never taken from the url
+    #[serde(skip)]
+    pub db_path: String,
+}
+
+#[derive(Deserialize, Debug)]
+pub enum AnnotationAction {
+    SaveNotes,
+    AddTag,
+    RemoveTag,
+    AddBookmark,
+    RemoveBookmark,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct AnnotationQuery {
+    pub key: i32,
+    pub report: String,
+    // Found from report in the index, never taken from the url
+    #[serde(skip)]
+    pub db_path: String,
+    pub action: Option<AnnotationAction>,
+    pub notes: Option<String>,
+    pub tag: Option<String>,
+    pub line_number: Option<i32>,
+    pub label: Option<String>,
+}
+
+#[derive(Deserialize, Debug)]
+pub struct ChartQuery {
+    pub key: i32,
+    pub report: String,
+    // Found from report in the index, never taken from the url
+    #[serde(skip)]
+    pub db_path: String,
+    pub chart: ChartNames,
+    pub download: Option<bool>,
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct CompareQuery {
+    pub left_key: i32,
+    pub left_report: String,
+    pub right_key: i32,
+    pub right_report: String,
+    // Found from the reports in the index, never taken from the url
+    #[serde(skip)]
+    pub left_db_path: String,
+    #[serde(skip)]
+    pub right_db_path: String,
+}
+
+#[derive(Deserialize, Debug, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct BenchmarkQuery {
+    pub test_name: Option<String>,
+    pub player_name: Option<String>,
+}